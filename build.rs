@@ -0,0 +1,13 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/opensea_stream.proto");
+
+        let fds = protox::compile(["proto/opensea_stream.proto"], ["proto"])
+            .expect("could not compile protobuf definitions");
+        tonic_build::configure()
+            .build_client(false)
+            .compile_fds(fds)
+            .expect("could not generate gRPC service");
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{
+    middleware::{EventMiddleware, Next},
+    schema::{NftId, Payload, PaymentToken, StreamEvent},
+};
+use chrono::{DateTime, Utc};
+use ethers_core::{types::U256, utils::format_units};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Unusual activity in a collection, reported by an [`AnomalyDetector`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    /// Items of the collection are listed much faster than usual.
+    ListingFlood {
+        /// Slug of the collection.
+        collection: String,
+        /// `sent_at` of the listing that started the flood.
+        at: DateTime<Utc>,
+        /// Listings per second over the flood window.
+        rate: f64,
+        /// Listings per second over the rest of the window.
+        baseline: f64,
+    },
+    /// An item of the collection sold far below the floor.
+    LowSale {
+        /// Slug of the collection.
+        collection: String,
+        /// The item.
+        item: NftId,
+        /// `sent_at` of the sale.
+        at: DateTime<Utc>,
+        /// Price of a single item, in ETH.
+        price: f64,
+        /// Lowest price of a single item listed in the window, in ETH.
+        floor: f64,
+        /// Standard deviation of the prices of the sales in the window, in ETH.
+        std_dev: f64,
+    },
+}
+
+impl Anomaly {
+    /// Returns the slug of the collection.
+    pub fn collection(&self) -> &str {
+        match self {
+            Anomaly::ListingFlood { collection, .. } | Anomaly::LowSale { collection, .. } => {
+                collection
+            }
+        }
+    }
+}
+
+/// Watches the listing rates and sale prices of every collection, and reports [`Anomaly`]s, such as those of
+/// fraud or wash trading.
+///
+/// - A listing flood is reported when a collection receives at least `min_listings` listings in the flood window
+///   (one minute by default), at a rate more than `flood_factor` (10 by default) times its rate over the rest of the
+///   window (one hour by default). It is reported once, until the rate falls below the threshold again.
+/// - A low sale is reported when a single item sells for more than `sigmas` (3 by default) standard deviations of
+///   the prices of recent sales below the lowest listing in the window, once the collection has had at least
+///   `min_sales` (10 by default) sales in the window.
+///
+/// Windows are measured by the `sent_at` of events, so that recorded events can be replayed through a detector.
+/// A collection only has a baseline once it has been watched for the whole window. Prices are compared in ETH,
+/// using the `eth_price` of the payment token.
+///
+/// The detector is cheap to clone; all clones share the same state. Events are recorded with
+/// [`AnomalyDetector::record`], or by adding the detector to a [`Pipeline`](crate::middleware::Pipeline), and
+/// anomalies are sent to the side channel set with [`AnomalyDetector::anomalies`].
+/// ```no_run
+/// # use opensea_stream::{anomaly::AnomalyDetector, middleware::Pipeline, Client, Collection, Network, SubscribeConfig};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let (tx, mut anomalies) = tokio::sync::mpsc::unbounded_channel();
+/// let detector = AnomalyDetector::new().sigmas(4.0).anomalies(tx);
+///
+/// tokio::spawn(async move {
+///     while let Some(anomaly) = anomalies.recv().await {
+///         eprintln!("{}: {:?}", anomaly.collection(), anomaly);
+///     }
+/// });
+///
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let config = SubscribeConfig::new().middleware(Pipeline::new().layer(detector));
+/// let mut events = client.subscribe_with_config(Collection::All, config).await?;
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    config: Config,
+    anomalies: Option<mpsc::UnboundedSender<Anomaly>>,
+    collections: Arc<Mutex<HashMap<String, Activity>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Config {
+    window: Duration,
+    flood_window: Duration,
+    flood_factor: f64,
+    min_listings: usize,
+    sigmas: f64,
+    min_sales: usize,
+}
+
+/// Recent activity of a collection, oldest first.
+#[derive(Debug)]
+struct Activity {
+    since: DateTime<Utc>,
+    listings: VecDeque<(DateTime<Utc>, Option<f64>)>,
+    sales: VecDeque<(DateTime<Utc>, f64)>,
+    flooding: bool,
+}
+
+impl AnomalyDetector {
+    /// Constructs a new `AnomalyDetector` with the default thresholds and without a side channel.
+    pub fn new() -> Self {
+        Self {
+            config: Config {
+                window: Duration::from_secs(60 * 60),
+                flood_window: Duration::from_secs(60),
+                flood_factor: 10.0,
+                min_listings: 20,
+                sigmas: 3.0,
+                min_sales: 10,
+            },
+            anomalies: None,
+            collections: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the window that baselines, floors and the prices of sales are computed over.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.config.window = window;
+        self
+    }
+
+    /// Sets the window that the rate of listings is compared to the baseline over. It should be shorter than the
+    /// window.
+    pub fn flood_window(mut self, flood_window: Duration) -> Self {
+        self.config.flood_window = flood_window;
+        self
+    }
+
+    /// Sets how many times faster than the baseline listings must arrive to be a flood.
+    pub fn flood_factor(mut self, factor: f64) -> Self {
+        self.config.flood_factor = factor;
+        self
+    }
+
+    /// Sets how many listings must arrive in the flood window to be a flood.
+    pub fn min_listings(mut self, min: usize) -> Self {
+        self.config.min_listings = min;
+        self
+    }
+
+    /// Sets how many standard deviations below the floor a sale must be to be reported.
+    pub fn sigmas(mut self, sigmas: f64) -> Self {
+        self.config.sigmas = sigmas;
+        self
+    }
+
+    /// Sets how many sales must be in the window before low sales are reported.
+    pub fn min_sales(mut self, min: usize) -> Self {
+        self.config.min_sales = min;
+        self
+    }
+
+    /// Sends every anomaly to `tx`.
+    pub fn anomalies(mut self, tx: mpsc::UnboundedSender<Anomaly>) -> Self {
+        self.anomalies = Some(tx);
+        self
+    }
+
+    /// Records an event, returning the anomalies it revealed. The anomalies are also sent to the side channel, if
+    /// any.
+    pub fn record(&self, event: &StreamEvent) -> Vec<Anomaly> {
+        if !matches!(event.payload, Payload::ItemListed(_) | Payload::ItemSold(_)) {
+            return Vec::new();
+        }
+        let collection = event.payload.collection().0.clone();
+        let at = event.sent_at;
+
+        let mut collections = self.collections.lock().unwrap();
+        let activity = collections
+            .entry(collection.clone())
+            .or_insert_with(|| Activity {
+                since: at,
+                listings: VecDeque::new(),
+                sales: VecDeque::new(),
+                flooding: false,
+            });
+        activity.expire(at, self.config.window);
+
+        let mut anomalies = Vec::new();
+        match &event.payload {
+            Payload::ItemListed(v) => {
+                let price = eth_value(&v.payment_token, v.unit_price());
+                activity.listings.push_back((at, price));
+                if let Some((rate, baseline)) = activity.flood(at, &self.config) {
+                    anomalies.push(Anomaly::ListingFlood {
+                        collection,
+                        at,
+                        rate,
+                        baseline,
+                    });
+                }
+            }
+            Payload::ItemSold(v) => {
+                if let Some(price) = eth_value(&v.payment_token, v.unit_price()) {
+                    if let Some((floor, std_dev)) = activity.low_sale(price, &self.config) {
+                        anomalies.push(Anomaly::LowSale {
+                            collection,
+                            item: v.context.item.nft_id.clone(),
+                            at,
+                            price,
+                            floor,
+                            std_dev,
+                        });
+                    }
+                    activity.sales.push_back((at, price));
+                }
+            }
+            _ => {}
+        }
+        drop(collections);
+
+        for anomaly in &anomalies {
+            warn!(
+                collection = anomaly.collection(),
+                ?anomaly,
+                "anomaly detected"
+            );
+            if let Some(tx) = &self.anomalies {
+                let _ = tx.send(anomaly.clone());
+            }
+        }
+        anomalies
+    }
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records every event that reaches this stage.
+impl EventMiddleware for AnomalyDetector {
+    async fn handle(&self, event: StreamEvent, next: Next<'_>) -> Option<StreamEvent> {
+        self.record(&event);
+        next.run(event).await
+    }
+}
+
+impl Activity {
+    /// Forgets events sent more than `window` before `now`.
+    fn expire(&mut self, now: DateTime<Utc>, window: Duration) {
+        let Ok(window) = chrono::Duration::from_std(window) else {
+            return;
+        };
+        let start = now - window;
+        while matches!(self.listings.front(), Some((at, _)) if *at < start) {
+            self.listings.pop_front();
+        }
+        while matches!(self.sales.front(), Some((at, _)) if *at < start) {
+            self.sales.pop_front();
+        }
+    }
+
+    /// Returns the rate and baseline of listings if a flood started at `now`, which must be the time of the last
+    /// listing.
+    fn flood(&mut self, now: DateTime<Utc>, config: &Config) -> Option<(f64, f64)> {
+        let flood_window = chrono::Duration::from_std(config.flood_window).ok()?;
+        let start = now - flood_window;
+        let recent = self.listings.iter().filter(|(at, _)| *at >= start).count();
+        let rate = recent as f64 / config.flood_window.as_secs_f64();
+
+        let rest = (config.window.saturating_sub(config.flood_window)).as_secs_f64();
+        let baseline = (self.listings.len() - recent) as f64 / rest;
+        if (now - self.since).to_std().unwrap_or_default() < config.window {
+            return None;
+        }
+
+        let flooding = recent >= config.min_listings && rate > config.flood_factor * baseline;
+        let started = flooding && !self.flooding;
+        self.flooding = flooding;
+        started.then_some((rate, baseline))
+    }
+
+    /// Returns the floor and the standard deviation of the prices of sales if a sale at `price` is low.
+    fn low_sale(&self, price: f64, config: &Config) -> Option<(f64, f64)> {
+        if self.sales.len() < config.min_sales {
+            return None;
+        }
+        let floor = self
+            .listings
+            .iter()
+            .filter_map(|(_, price)| *price)
+            .min_by(f64::total_cmp)?;
+
+        let n = self.sales.len() as f64;
+        let mean = self.sales.iter().map(|(_, p)| p).sum::<f64>() / n;
+        let variance = self
+            .sales
+            .iter()
+            .map(|(_, p)| (p - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let std_dev = variance.sqrt();
+
+        (price < floor - config.sigmas * std_dev).then_some((floor, std_dev))
+    }
+}
+
+/// Converts an amount in the smallest unit of a token to ETH.
+fn eth_value(token: &PaymentToken, amount: U256) -> Option<f64> {
+    let decimals = u32::try_from(token.decimals).ok()?;
+    let value: f64 = format_units(amount, decimals).ok()?.parse().ok()?;
+    Some(value * token.eth_price)
+}
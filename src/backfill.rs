@@ -0,0 +1,584 @@
+use crate::{
+    cursor::event_key,
+    enrich::{rest_endpoint, EnrichError},
+    schema::StreamEvent,
+    Client, ClientError, Collection, Error, EventSource, EventStream, Network, SubscribeConfig,
+    Subscription,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use thiserror::Error;
+use tokio::{sync::mpsc, task::JoinHandle};
+use url::Url;
+
+/// Builder for a [`Backfill`].
+#[derive(Debug, Clone)]
+pub struct BackfillBuilder {
+    endpoint: Url,
+    api_key: String,
+    page_size: u32,
+    max_events: usize,
+}
+
+impl BackfillBuilder {
+    /// Constructs a new `BackfillBuilder` for the REST API corresponding to `network`.
+    pub fn new(network: Network, api_key: &str) -> Self {
+        Self {
+            endpoint: rest_endpoint(network),
+            api_key: api_key.to_owned(),
+            page_size: 50,
+            max_events: 1000,
+        }
+    }
+
+    /// Sets the base URL of the REST API. This should end with a `/`.
+    pub fn endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Sets the number of events requested per page (at most 50).
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Sets the maximum number of events fetched by [`Backfill::fetch`]. The most recent events are kept.
+    pub fn max_events(mut self, max_events: usize) -> Self {
+        self.max_events = max_events;
+        self
+    }
+
+    /// Builds the `Backfill`.
+    pub fn build(self) -> Backfill {
+        Backfill {
+            http: reqwest::Client::new(),
+            endpoint: self.endpoint,
+            api_key: self.api_key,
+            page_size: self.page_size,
+            max_events: self.max_events,
+        }
+    }
+}
+
+/// Fetches recent events of a collection from the
+/// [events endpoint](https://docs.opensea.io/reference/list_events_by_collection) of the OpenSea REST API.
+///
+/// Events are converted into the same [`Payload`](crate::schema::Payload) types as events of the stream (see
+/// [`convert`]), and can be delivered ahead of a live subscription with [`Spliced`].
+/// ```no_run
+/// # use opensea_stream::{backfill::{Backfill, Spliced}, Client, Collection, Network};
+/// # use chrono::{Duration, Utc};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let collection = Collection::Collection("wandernauts".to_string());
+///
+/// // Subscribe first, so that no event is missed while the history is fetched.
+/// let live = client.subscribe(collection).await?;
+/// let backfill = Backfill::builder(Network::Mainnet, "YOUR_API_KEY_HERE").build();
+/// let history = backfill.fetch("wandernauts", Utc::now() - Duration::hours(1)).await?;
+///
+/// let mut events = Spliced::new(history, live);
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Backfill {
+    http: reqwest::Client,
+    endpoint: Url,
+    api_key: String,
+    page_size: u32,
+    max_events: usize,
+}
+
+impl Backfill {
+    /// Constructs a new [`BackfillBuilder`].
+    pub fn builder(network: Network, api_key: &str) -> BackfillBuilder {
+        BackfillBuilder::new(network, api_key)
+    }
+
+    /// Fetches the events of the collection with `slug` that happened after `after`, oldest first.
+    ///
+    /// Events that cannot be converted (see [`convert`]) are skipped.
+    pub async fn fetch(
+        &self,
+        slug: &str,
+        after: DateTime<Utc>,
+    ) -> Result<Vec<StreamEvent>, EnrichError> {
+        #[derive(Deserialize)]
+        struct Response {
+            #[serde(default)]
+            asset_events: Vec<Value>,
+            next: Option<String>,
+        }
+
+        let mut url = self.endpoint.join(&format!("events/collection/{}", slug))?;
+        url.query_pairs_mut()
+            .append_pair("after", &after.timestamp().to_string())
+            .append_pair("limit", &self.page_size.to_string());
+
+        let mut events = Vec::new();
+        let mut next: Option<String> = None;
+        loop {
+            let mut page = url.clone();
+            if let Some(next) = &next {
+                page.query_pairs_mut().append_pair("next", next);
+            }
+
+            let response: Response = self
+                .http
+                .get(page)
+                .header("X-API-KEY", &self.api_key)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            events.extend(response.asset_events.iter().filter_map(convert));
+            next = response.next.filter(|next| !next.is_empty());
+            if next.is_none() || events.len() >= self.max_events {
+                break;
+            }
+        }
+
+        // Pages are returned newest first.
+        events.truncate(self.max_events);
+        events.sort_by_key(StreamEvent::timestamp);
+        Ok(events)
+    }
+}
+
+/// Converts an event of the REST API into a [`StreamEvent`], or returns `None` if it has no equivalent in the stream.
+///
+/// Sales, transfers, cancellations and orders (listings, item offers, collection offers and trait offers) are
+/// converted. The REST API does not report the prices of payment tokens, so `eth_price` and `usd_price` are `0`,
+/// and `name` is the symbol of the token. `sent_at` is the time the event happened.
+pub fn convert(event: &Value) -> Option<StreamEvent> {
+    let timestamp = time(&event["event_timestamp"])?;
+    let chain = event["chain"].as_str()?;
+    let nft = match &event["nft"] {
+        Value::Null => &event["asset"],
+        nft => nft,
+    };
+
+    let (event_type, mut payload) = match event["event_type"].as_str()? {
+        "sale" => (
+            "item_sold",
+            json!({
+                "closing_date": time(&event["closing_date"]).unwrap_or(timestamp),
+                "is_private": false,
+                "listing_type": null,
+                "maker": account(&event["seller"])?,
+                "payment_token": payment_token(&event["payment"])?,
+                "quantity": event["quantity"],
+                "sale_price": event["payment"]["quantity"],
+                "taker": account(&event["buyer"])?,
+                "transaction": { "hash": event["transaction"], "timestamp": timestamp },
+            }),
+        ),
+        "transfer" => (
+            "item_transferred",
+            json!({
+                "from_account": account(&event["from_address"])?,
+                "quantity": event["quantity"],
+                "to_account": account(&event["to_address"])?,
+                "transaction": { "hash": event["transaction"], "timestamp": timestamp },
+            }),
+        ),
+        "cancel" => (
+            "item_cancelled",
+            json!({
+                "listing_type": null,
+                "maker": account(&event["maker"])?,
+                "order_hash": event["order_hash"],
+                "payment_token": payment_token(&event["payment"])?,
+                "quantity": event["quantity"],
+                "transaction": { "hash": event["transaction"], "timestamp": timestamp },
+            }),
+        ),
+        "order" => {
+            let mut order = json!({
+                "base_price": event["payment"]["quantity"],
+                "expiration_date": time(&event["expiration_date"])?,
+                "maker": account(&event["maker"])?,
+                "order_hash": event["order_hash"],
+                "payment_token": payment_token(&event["payment"])?,
+                "quantity": event["quantity"],
+                "taker": account(&event["taker"]),
+            });
+            let start_date = time(&event["start_date"]).unwrap_or(timestamp);
+            let criteria = &event["criteria"];
+            let event_type = match event["order_type"].as_str()? {
+                "listing" => {
+                    order["listing_date"] = json!(start_date);
+                    order["listing_type"] = Value::Null;
+                    order["is_private"] = json!(event["is_private_listing"] == true);
+                    "item_listed"
+                }
+                "item_offer" => {
+                    order["created_date"] = json!(start_date);
+                    "item_received_offer"
+                }
+                order_type @ ("collection_offer" | "trait_offer") => {
+                    order["created_date"] = json!(start_date);
+                    order["collection"] = json!({ "slug": criteria["collection"]["slug"] });
+                    order["asset_contract_criteria"] =
+                        json!({ "address": criteria["contract"]["address"] });
+                    if order_type == "collection_offer" {
+                        "collection_offer"
+                    } else {
+                        order["trait_criteria"] = json!({
+                            "trait_type": criteria["trait"]["type"],
+                            "trait_name": criteria["trait"]["value"],
+                        });
+                        "trait_offer"
+                    }
+                }
+                _ => return None,
+            };
+            (event_type, order)
+        }
+        _ => return None,
+    };
+
+    let payload_map = payload.as_object_mut()?;
+    payload_map.insert("event_timestamp".to_owned(), json!(timestamp));
+    if !matches!(event_type, "collection_offer" | "trait_offer") {
+        payload_map.extend(context(chain, nft)?);
+    }
+
+    serde_json::from_value(json!({
+        "event_type": event_type,
+        "sent_at": timestamp,
+        "payload": payload,
+    }))
+    .ok()
+}
+
+/// Converts the `nft` of a REST event into the fields of a [`Context`](crate::schema::Context).
+fn context(chain: &str, nft: &Value) -> Option<Map<String, Value>> {
+    let contract = nft["contract"].as_str()?;
+    let identifier = nft["identifier"].as_str()?;
+    let context = json!({
+        "collection": { "slug": nft["collection"] },
+        "item": {
+            "chain": { "name": chain },
+            "metadata": {
+                "animation_url": url(&nft["animation_url"]),
+                "description": nft["description"],
+                "image_url": url(&nft["image_url"]),
+                "metadata_url": url(&nft["metadata_url"]),
+                "name": nft["name"],
+            },
+            "nft_id": format!("{}/{}/{}", chain, contract, identifier),
+            "permalink": url(&nft["opensea_url"]).unwrap_or_else(|| {
+                format!("https://opensea.io/assets/{}/{}/{}", chain, contract, identifier)
+            }),
+        },
+    });
+    match context {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+fn account(address: &Value) -> Option<Value> {
+    Some(json!({ "address": address.as_str()? }))
+}
+
+fn payment_token(payment: &Value) -> Option<Value> {
+    let symbol = payment["symbol"].as_str()?;
+    Some(json!({
+        "address": payment["token_address"],
+        "decimals": payment["decimals"],
+        "eth_price": "0",
+        "name": symbol,
+        "symbol": symbol,
+        "usd_price": "0",
+    }))
+}
+
+/// Parses a timestamp of the REST API, which is in seconds since the Unix epoch.
+fn time(timestamp: &Value) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp.as_i64()?, 0)
+}
+
+/// Returns `url` if it is a valid URL, as the REST API uses empty strings for missing URLs.
+fn url(url: &Value) -> Option<String> {
+    url.as_str()
+        .filter(|url| Url::parse(url).is_ok())
+        .map(str::to_owned)
+}
+
+/// Events fetched by a [`Backfill`], followed by the events of a live [`Subscription`] or [`EventStream`].
+///
+/// Live events that were already delivered as part of the history are skipped, so that events that happened
+/// while the history was being fetched are delivered once.
+#[derive(Debug)]
+pub struct Spliced<S = EventStream> {
+    history: VecDeque<StreamEvent>,
+    /// Timestamp of the last event of the history, and the keys of every event of the history.
+    seam: Option<(DateTime<Utc>, HashSet<String>)>,
+    live: S,
+}
+
+impl<S: EventSource> Spliced<S> {
+    /// Constructs a new `Spliced` which delivers `history` (oldest first), then `live`.
+    pub fn new(history: Vec<StreamEvent>, live: S) -> Self {
+        let seam = history.last().map(|last| {
+            let keys = history.iter().map(|e| event_key(&e.payload)).collect();
+            (last.timestamp(), keys)
+        });
+        Self {
+            history: history.into(),
+            seam,
+            live,
+        }
+    }
+
+    /// Receives the next event, or `None` once the live subscription is closed.
+    pub async fn recv(&mut self) -> Option<Result<StreamEvent, Error>> {
+        if let Some(event) = self.history.pop_front() {
+            return Some(Ok(event));
+        }
+
+        loop {
+            let event = match self.live.recv().await? {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e)),
+            };
+            if let Some((last, keys)) = &self.seam {
+                if event.timestamp() > *last {
+                    self.seam = None;
+                } else if keys.contains(&event_key(&event.payload)) {
+                    continue;
+                }
+            }
+            return Some(Ok(event));
+        }
+    }
+}
+
+/// Errors that can be encountered while starting a [`Bootstrap`].
+#[derive(Debug, Error)]
+pub enum BootstrapError {
+    /// A collection could not be subscribed to.
+    #[error("could not subscribe to {0}")]
+    Subscribe(Collection, #[source] ClientError),
+    /// The history of a collection could not be fetched.
+    #[error("could not fetch the history of {0}")]
+    Backfill(String, #[source] EnrichError),
+}
+
+/// Cold start of several collections: their recent history, followed by their live events, as one stream.
+///
+/// Every collection is subscribed to before any history is fetched, and live events are buffered while the
+/// histories are fetched, so that no event is missed. Once every history is fetched, the histories and the buffered
+/// live events are merged, deduplicated and ordered by timestamp, and delivered ahead of the live events that
+/// follow. Live events that were already delivered are skipped until a collection receives an event newer than
+/// all of its delivered events.
+/// ```no_run
+/// # use opensea_stream::{backfill::{Backfill, Bootstrap}, Client, Network};
+/// # use chrono::{Duration, Utc};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let backfill = Backfill::builder(Network::Mainnet, "YOUR_API_KEY_HERE").build();
+///
+/// let mut events = Bootstrap::new(backfill, Utc::now() - Duration::hours(1))
+///     .collections(["wandernauts", "boredapeyachtclub"])
+///     .start(&mut client)
+///     .await?;
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Bootstrap {
+    backfill: Backfill,
+    after: DateTime<Utc>,
+    collections: Vec<String>,
+    config: SubscribeConfig,
+}
+
+impl Bootstrap {
+    /// Constructs a new `Bootstrap` without any collection, which fetches the events that happened after `after`
+    /// with `backfill`.
+    pub fn new(backfill: Backfill, after: DateTime<Utc>) -> Self {
+        Self {
+            backfill,
+            after,
+            collections: Vec::new(),
+            config: SubscribeConfig::new(),
+        }
+    }
+
+    /// Adds the collection with `slug`.
+    pub fn collection(mut self, slug: impl Into<String>) -> Self {
+        let slug = slug.into();
+        if !self.collections.contains(&slug) {
+            self.collections.push(slug);
+        }
+        self
+    }
+
+    /// Adds the collections with `slugs`.
+    pub fn collections<I, S>(self, slugs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        slugs.into_iter().fold(self, Self::collection)
+    }
+
+    /// Sets the configuration of the live subscriptions. The middleware of the configuration is only applied to live
+    /// events.
+    pub fn subscribe_config(mut self, config: SubscribeConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Subscribes `client` to every collection, then fetches their histories one after another, returning the
+    /// combined stream.
+    ///
+    /// The client must not already be subscribed to any of the collections. Dropping the stream does not leave the
+    /// channels of the collections.
+    pub async fn start(self, client: &mut Client) -> Result<Bootstrapped, BootstrapError> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut forwarders = Forwarders(Vec::new());
+        for slug in &self.collections {
+            let collection = Collection::Collection(slug.clone());
+            let subscription = client
+                .subscribe_with_config(collection.clone(), self.config.clone())
+                .await
+                .map_err(|e| BootstrapError::Subscribe(collection, e))?;
+            forwarders
+                .0
+                .push(tokio::spawn(forward(subscription, tx.clone())));
+        }
+
+        let mut history = Vec::new();
+        for slug in &self.collections {
+            let events = self
+                .backfill
+                .fetch(slug, self.after)
+                .await
+                .map_err(|e| BootstrapError::Backfill(slug.clone(), e))?;
+            history.extend(events);
+        }
+
+        let mut events = Bootstrapped::new(history, rx);
+        events.forwarders = forwarders;
+        Ok(events)
+    }
+}
+
+/// Forwards the events of a subscription until it closes or the stream is dropped.
+async fn forward(
+    mut subscription: Subscription,
+    tx: mpsc::UnboundedSender<Result<StreamEvent, Error>>,
+) {
+    while let Some(event) = subscription.recv().await {
+        if tx.send(event).is_err() {
+            break;
+        }
+    }
+}
+
+/// Tasks forwarding live events to a [`Bootstrapped`], aborted when it is dropped.
+#[derive(Debug)]
+struct Forwarders(Vec<JoinHandle<()>>);
+
+impl Drop for Forwarders {
+    fn drop(&mut self) {
+        for forwarder in &self.0 {
+            forwarder.abort();
+        }
+    }
+}
+
+/// Events of several collections started by a [`Bootstrap`]: their histories merged with the live events buffered
+/// while they were fetched, then live events.
+#[derive(Debug)]
+pub struct Bootstrapped {
+    buffered: VecDeque<Result<StreamEvent, Error>>,
+    /// Timestamp of the last buffered event of each collection, and the keys of its buffered events.
+    seams: HashMap<String, (DateTime<Utc>, HashSet<String>)>,
+    live: mpsc::UnboundedReceiver<Result<StreamEvent, Error>>,
+    forwarders: Forwarders,
+}
+
+impl Bootstrapped {
+    /// Constructs a new `Bootstrapped` from the histories of any number of collections, and the live events of
+    /// the same collections.
+    ///
+    /// Live events already waiting on `live` are merged into the histories; errors among them are delivered first.
+    pub fn new(
+        history: Vec<StreamEvent>,
+        mut live: mpsc::UnboundedReceiver<Result<StreamEvent, Error>>,
+    ) -> Self {
+        let mut keys = HashSet::new();
+        let mut events: Vec<StreamEvent> = history
+            .into_iter()
+            .filter(|event| keys.insert(event_key(&event.payload)))
+            .collect();
+        let mut errors = VecDeque::new();
+        while let Ok(event) = live.try_recv() {
+            match event {
+                Ok(event) if keys.insert(event_key(&event.payload)) => events.push(event),
+                Ok(_) => {}
+                Err(e) => errors.push_back(Err(e)),
+            }
+        }
+        events.sort_by_key(StreamEvent::timestamp);
+
+        let mut seams: HashMap<String, (DateTime<Utc>, HashSet<String>)> = HashMap::new();
+        for event in &events {
+            let seam = seams
+                .entry(event.payload.collection().0.clone())
+                .or_insert_with(|| (event.timestamp(), HashSet::new()));
+            seam.0 = event.timestamp();
+            seam.1.insert(event_key(&event.payload));
+        }
+
+        errors.extend(events.into_iter().map(Ok));
+        Self {
+            buffered: errors,
+            seams,
+            live,
+            forwarders: Forwarders(Vec::new()),
+        }
+    }
+
+    /// Receives the next event, or `None` once every live subscription is closed.
+    pub async fn recv(&mut self) -> Option<Result<StreamEvent, Error>> {
+        if let Some(event) = self.buffered.pop_front() {
+            return Some(event);
+        }
+
+        loop {
+            let event = match self.live.recv().await? {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e)),
+            };
+            let collection = &event.payload.collection().0;
+            match self.seams.get(collection) {
+                Some((last, _)) if event.timestamp() > *last => {
+                    self.seams.remove(collection);
+                }
+                Some((_, keys)) if keys.contains(&event_key(&event.payload)) => continue,
+                _ => {}
+            }
+            return Some(Ok(event));
+        }
+    }
+}
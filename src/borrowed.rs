@@ -0,0 +1,203 @@
+use crate::{schema::StreamEvent, Event};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// An event that borrows its strings from the JSON it was deserialized from, for consumers that only read a few
+/// fields of busy streams.
+///
+/// Only the fields below are deserialized; every other field is skipped without being allocated. Strings are
+/// borrowed unless they contain escape sequences. The owned [`StreamEvent`] remains the default, and can be
+/// deserialized from the same JSON with [`StreamEventRef::to_owned`] for the events that are kept.
+///
+/// Events can be deserialized from the text of a message, or from a payload that was already parsed into a
+/// [`Value`], such as the payloads checked by the `guardrails` module.
+/// ```
+/// # use opensea_stream::{borrowed::StreamEventRef, Event};
+/// # fn main() -> anyhow::Result<()> {
+/// let json = r#"{
+///     "event_type": "item_sold",
+///     "sent_at": "2022-07-19T18:45:11.000000+00:00",
+///     "payload": { "collection": { "slug": "wandernauts" }, "sale_price": "50000000000000000" }
+/// }"#;
+/// let event = StreamEventRef::from_str(json)?;
+/// assert_eq!(event.event_type, Event::ItemSold);
+/// assert_eq!(event.payload.collection(), "wandernauts");
+/// assert_eq!(event.payload.price(), Some("50000000000000000"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamEventRef<'a> {
+    /// Type of the event.
+    pub event_type: Event,
+    /// Timestamp of when this message was sent to the client.
+    pub sent_at: DateTime<Utc>,
+    /// Contents of the message.
+    pub payload: PayloadRef<'a>,
+    source: Source<'a>,
+}
+
+/// JSON that a [`StreamEventRef`] was deserialized from.
+#[derive(Debug, Clone, Copy)]
+enum Source<'a> {
+    Str(&'a str),
+    Value(&'a Value),
+}
+
+#[derive(Deserialize)]
+struct Raw<'a> {
+    event_type: Event,
+    sent_at: DateTime<Utc>,
+    #[serde(borrow)]
+    payload: PayloadRef<'a>,
+}
+
+impl<'a> StreamEventRef<'a> {
+    /// Deserializes an event from the text of a message.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(json: &'a str) -> serde_json::Result<Self> {
+        let raw: Raw = serde_json::from_str(json)?;
+        Ok(Self::new(raw, Source::Str(json)))
+    }
+
+    /// Deserializes an event from a message that was already parsed.
+    pub fn from_value(value: &'a Value) -> serde_json::Result<Self> {
+        let raw = Raw::deserialize(value)?;
+        Ok(Self::new(raw, Source::Value(value)))
+    }
+
+    fn new(raw: Raw<'a>, source: Source<'a>) -> Self {
+        Self {
+            event_type: raw.event_type,
+            sent_at: raw.sent_at,
+            payload: raw.payload,
+            source,
+        }
+    }
+
+    /// Deserializes the whole event from the same JSON, with owned strings.
+    pub fn to_owned(&self) -> serde_json::Result<StreamEvent> {
+        match self.source {
+            Source::Str(json) => serde_json::from_str(json),
+            Source::Value(value) => StreamEvent::deserialize(value),
+        }
+    }
+
+    /// Returns the timestamp of when the event happened, falling back to when it was sent if the payload carries none.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.payload.event_timestamp.unwrap_or(self.sent_at)
+    }
+}
+
+/// The fields of a [`Payload`](crate::schema::Payload) that most consumers read, borrowed. Fields not carried by
+/// the type of the event are `None`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PayloadRef<'a> {
+    /// Collection of the event.
+    #[serde(borrow)]
+    pub collection: CollectionRef<'a>,
+    /// The item, absent from collection-wide events.
+    #[serde(borrow, default)]
+    pub item: Option<ItemRef<'a>>,
+    /// Timestamp of when the event happened.
+    #[serde(default)]
+    pub event_timestamp: Option<DateTime<Utc>>,
+    /// Price of listings and offers, as a decimal string in the smallest unit of the payment token.
+    #[serde(borrow, default)]
+    pub base_price: Option<Cow<'a, str>>,
+    /// Price of sales, as a decimal string in the smallest unit of the payment token.
+    #[serde(borrow, default)]
+    pub sale_price: Option<Cow<'a, str>>,
+    /// Token that the price is paid in.
+    #[serde(borrow, default)]
+    pub payment_token: Option<PaymentTokenRef<'a>>,
+    /// Number of items.
+    #[serde(default)]
+    pub quantity: Option<u64>,
+    /// Creator of the order, or seller of a sale.
+    #[serde(borrow, default)]
+    pub maker: Option<AccountRef<'a>>,
+    /// Buyer of a sale.
+    #[serde(borrow, default)]
+    pub taker: Option<AccountRef<'a>>,
+    /// Sender of a transfer.
+    #[serde(borrow, default)]
+    pub from_account: Option<AccountRef<'a>>,
+    /// Recipient of a transfer.
+    #[serde(borrow, default)]
+    pub to_account: Option<AccountRef<'a>>,
+}
+
+impl PayloadRef<'_> {
+    /// Returns the slug of the collection.
+    pub fn collection(&self) -> &str {
+        &self.collection.slug
+    }
+
+    /// Returns the price of the order or sale, if any.
+    pub fn price(&self) -> Option<&str> {
+        self.sale_price.as_deref().or(self.base_price.as_deref())
+    }
+}
+
+/// A collection, borrowed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CollectionRef<'a> {
+    /// Slug of the collection.
+    #[serde(borrow)]
+    pub slug: Cow<'a, str>,
+}
+
+/// An item, borrowed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemRef<'a> {
+    /// Identifier, formatted as `chain/contract/token_id`.
+    #[serde(borrow)]
+    pub nft_id: Cow<'a, str>,
+    /// Link to the OpenSea page.
+    #[serde(borrow)]
+    pub permalink: Cow<'a, str>,
+    /// Basic metadata.
+    #[serde(borrow)]
+    pub metadata: MetadataRef<'a>,
+}
+
+/// Basic metadata of an item, borrowed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MetadataRef<'a> {
+    /// Name.
+    #[serde(borrow, default)]
+    pub name: Option<Cow<'a, str>>,
+    /// Image URL.
+    #[serde(borrow, default)]
+    pub image_url: Option<Cow<'a, str>>,
+}
+
+/// An account, borrowed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AccountRef<'a> {
+    /// Address of the account.
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+}
+
+/// A payment token, borrowed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PaymentTokenRef<'a> {
+    /// Symbol of the token.
+    #[serde(borrow)]
+    pub symbol: Cow<'a, str>,
+    /// Contract address of the token.
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+    /// Number of decimals of the token.
+    pub decimals: u64,
+    /// Price of the token in ETH, as a decimal string.
+    #[serde(borrow)]
+    pub eth_price: Cow<'a, str>,
+    /// Price of the token in USD, as a decimal string.
+    #[serde(borrow)]
+    pub usd_price: Cow<'a, str>,
+}
@@ -0,0 +1,806 @@
+//! Local bridge between the websocket of a [`SocketHandler`] and the OpenSea server.
+//!
+//! phyllo opens its own TCP and TLS connection to the endpoint it is given, so settings that affect the
+//! connection itself (such as a proxy, a TLS configuration, the API key of each reconnect or compression) cannot be
+//! passed to it.
+//! Instead, the socket is pointed at a listener on the loopback interface, and every connection accepted there is
+//! relayed to the real endpoint over a connection that is set up by this module.
+//!
+//! Any local process can connect to the listener, so the socket is given a random secret in the query of its
+//! endpoint, and connections that do not present it are refused rather than relayed with the API key.
+//!
+//! phyllo keeps the replies to joins to itself, so the bridge also remembers the joins that the socket sends and
+//! passes their replies on to the [`Client`](crate::Client), which reports rejected joins.
+
+#[cfg(feature = "proxy")]
+use crate::client::Proxy;
+#[cfg(feature = "key-rotation")]
+use crate::client::{KeyRejected, KeySelection};
+#[cfg(feature = "compression")]
+use crate::deflate;
+use crate::{
+    frame::{self, Message},
+    stats::Stats,
+    Collection,
+};
+#[cfg(feature = "proxy")]
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(feature = "compression")]
+use flate2::Decompress;
+use phyllo::socket::SocketHandler;
+use rustls::{pki_types::ServerName, ClientConfig, RootCertStore};
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(feature = "key-rotation")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+#[cfg(feature = "key-rotation")]
+use tokio::sync::mpsc;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    select,
+    sync::broadcast,
+};
+use tokio_rustls::TlsConnector;
+use tracing::warn;
+use url::Url;
+
+/// Largest HTTP head (request or response) that the bridge reads before giving up.
+const MAX_HEAD_LEN: usize = 16 * 1024;
+
+/// How often the bridge checks whether its socket is still alive.
+const ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Query parameter that carries the secret of the bridge.
+const SECRET_PARAM: &str = "bridge_secret";
+
+/// Number of join replies buffered for clients that have not received them yet.
+const JOIN_REPLY_BUFFER: usize = 64;
+
+/// Reply of the server to the join of a channel.
+#[derive(Debug, Clone)]
+pub(crate) struct JoinReply {
+    pub(crate) topic: String,
+    /// The response of the reply if the join was rejected, or `None` if it was accepted.
+    pub(crate) rejected: Option<Value>,
+}
+
+/// How the bridge connects to the endpoint.
+#[derive(Debug, Clone)]
+pub(crate) struct Bridge {
+    host: String,
+    port: u16,
+    secret: String,
+    tls: Option<Arc<ClientConfig>>,
+    #[cfg(feature = "proxy")]
+    proxy: Option<Proxy>,
+    #[cfg(feature = "key-rotation")]
+    keys: Option<Arc<Keys>>,
+    #[cfg(feature = "compression")]
+    compression: bool,
+    max_message_bytes: Option<usize>,
+    stats: Option<Stats>,
+    join_replies: broadcast::Sender<JoinReply>,
+}
+
+/// API keys that the bridge chooses from for every connection.
+#[cfg(feature = "key-rotation")]
+#[derive(Debug)]
+pub(crate) struct Keys {
+    tokens: Vec<String>,
+    selection: KeySelection,
+    current: AtomicUsize,
+    /// Index of a key whose handshake failed for another reason than the key, to be retried by the next connection;
+    /// `usize::MAX` if there is none.
+    retry: AtomicUsize,
+    rejected: Option<mpsc::UnboundedSender<KeyRejected>>,
+}
+
+#[cfg(feature = "key-rotation")]
+impl Keys {
+    /// Constructs a new `Keys`, starting with the first of `tokens`.
+    pub(crate) fn new(
+        tokens: Vec<String>,
+        selection: KeySelection,
+        rejected: Option<mpsc::UnboundedSender<KeyRejected>>,
+    ) -> Self {
+        Self {
+            tokens,
+            selection,
+            current: AtomicUsize::new(0),
+            retry: AtomicUsize::new(usize::MAX),
+            rejected,
+        }
+    }
+
+    /// Returns the index of the key to connect with.
+    fn select(&self) -> usize {
+        let retry = self.retry.swap(usize::MAX, Ordering::Relaxed);
+        if retry != usize::MAX {
+            return retry;
+        }
+        match self.selection {
+            KeySelection::RoundRobin => {
+                self.current.fetch_add(1, Ordering::Relaxed) % self.tokens.len()
+            }
+            KeySelection::Failover => self.current.load(Ordering::Relaxed) % self.tokens.len(),
+        }
+    }
+
+    /// Records that the server rejected the handshake made with a key.
+    fn reject(&self, index: usize, status: u16) {
+        warn!(index, status, "api key rejected");
+        if let KeySelection::Failover = self.selection {
+            // Only move on if no other connection has done so already.
+            let _ = self.current.compare_exchange(
+                index,
+                (index + 1) % self.tokens.len(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+        if let Some(tx) = &self.rejected {
+            let _ = tx.send(KeyRejected { index, status });
+        }
+    }
+
+    /// Records that a handshake made with a key failed for another reason than the key, so that the next connection
+    /// retries the same key.
+    fn retry(&self, index: usize, status: u16) {
+        warn!(index, status, "handshake failed");
+        self.retry.store(index, Ordering::Relaxed);
+    }
+}
+
+impl Bridge {
+    /// Constructs a new `Bridge` to `endpoint`. If `endpoint` uses `wss`, TLS is set up with `tls`, or with the root
+    /// certificates selected with the `rustls-tls-*` features if `tls` is `None`.
+    pub(crate) fn new(endpoint: &Url, tls: Option<Arc<ClientConfig>>) -> io::Result<Self> {
+        let host = endpoint
+            .host_str()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "endpoint has no host"))?
+            .to_owned();
+        let port = endpoint.port_or_known_default().unwrap_or(443);
+
+        let tls = match endpoint.scheme() {
+            "wss" => Some(tls.map(Ok).unwrap_or_else(default_tls_config)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            host,
+            port,
+            secret: secret()?,
+            tls,
+            #[cfg(feature = "proxy")]
+            proxy: None,
+            #[cfg(feature = "key-rotation")]
+            keys: None,
+            #[cfg(feature = "compression")]
+            compression: false,
+            max_message_bytes: None,
+            stats: None,
+            join_replies: broadcast::channel(JOIN_REPLY_BUFFER).0,
+        })
+    }
+
+    /// Returns the sender of the replies to the joins of every connection, which can be subscribed to.
+    pub(crate) fn join_replies(&self) -> broadcast::Sender<JoinReply> {
+        self.join_replies.clone()
+    }
+
+    /// Drops the messages of the server larger than `max` bytes instead of relaying them.
+    pub(crate) fn max_message_bytes(mut self, max: Option<usize>) -> Self {
+        self.max_message_bytes = max;
+        self
+    }
+
+    /// Records every connection in `stats`.
+    pub(crate) fn stats(mut self, stats: Option<Stats>) -> Self {
+        if let Some(stats) = &stats {
+            stats.track_connections();
+        }
+        self.stats = stats;
+        self
+    }
+
+    /// Replaces the API key of every connection with one of `keys`.
+    #[cfg(feature = "key-rotation")]
+    pub(crate) fn keys(mut self, keys: Option<Keys>) -> Self {
+        self.keys = keys.map(Arc::new);
+        self
+    }
+
+    /// Negotiates permessage-deflate with the endpoint if `compression` is set.
+    #[cfg(feature = "compression")]
+    pub(crate) fn compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Connects to the endpoint through `proxy`.
+    #[cfg(feature = "proxy")]
+    pub(crate) fn proxy(mut self, proxy: Option<Proxy>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Binds the local listener, returning it with the endpoint that the socket should connect to instead of `endpoint`.
+    pub(crate) async fn bind(&self, endpoint: &Url) -> io::Result<(TcpListener, Url)> {
+        let listener = TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
+        let mut local = local_endpoint(endpoint, listener.local_addr()?);
+        local
+            .query_pairs_mut()
+            .append_pair(SECRET_PARAM, &self.secret);
+        Ok((listener, local))
+    }
+
+    /// Relays connections accepted by `listener` until `socket` is no longer alive.
+    pub(crate) fn spawn(self, listener: TcpListener, socket: SocketHandler<Collection>) {
+        let bridge = Arc::new(self);
+        tokio::spawn(async move {
+            let mut alive = tokio::time::interval(ALIVE_INTERVAL);
+            loop {
+                select! {
+                    accepted = listener.accept() => {
+                        let local = match accepted {
+                            Ok((local, _)) => local,
+                            Err(e) => {
+                                warn!(error = ?e, "bridge could not accept connection");
+                                continue;
+                            }
+                        };
+                        if let Some(stats) = &bridge.stats {
+                            stats.record_connection();
+                        }
+                        let bridge = bridge.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = bridge.relay(local).await {
+                                warn!(error = ?e, "bridge connection failed");
+                            }
+                        });
+                    }
+                    _ = alive.tick() => {
+                        if !socket.alive().await {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Relays a single connection from the socket to the endpoint.
+    async fn relay(&self, mut local: TcpStream) -> io::Result<()> {
+        let (head, rest) = read_head(&mut local).await?;
+        let head = match take_secret(&head, &self.secret) {
+            Some(head) => head,
+            None => {
+                local
+                    .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")
+                    .await?;
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "connection did not present the bridge secret",
+                ));
+            }
+        };
+
+        let upstream = self.connect().await?;
+
+        // The websocket handshake names the local listener as its host, which the server would reject.
+        let head = rewrite_host(&head, &self.host, self.port);
+
+        #[cfg(feature = "key-rotation")]
+        let (head, key) = match &self.keys {
+            Some(keys) => {
+                let index = keys.select();
+                (rewrite_token(&head, &keys.tokens[index]), Some(index))
+            }
+            None => (head, None),
+        };
+
+        let mut upstream: Box<dyn Upstream> = match &self.tls {
+            Some(tls) => {
+                let domain = ServerName::try_from(self.host.clone())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                Box::new(
+                    TlsConnector::from(tls.clone())
+                        .connect(domain, upstream)
+                        .await?,
+                )
+            }
+            None => Box::new(upstream),
+        };
+        #[cfg(feature = "compression")]
+        let head = match self.compression {
+            true => deflate::offer(&head),
+            false => head,
+        };
+        upstream.write_all(&head).await?;
+        upstream.write_all(&rest).await?;
+
+        let (head, rest) = read_head(&mut upstream).await?;
+        let status = status(&head);
+
+        #[cfg(feature = "key-rotation")]
+        if let (Some(keys), Some(index)) = (&self.keys, key) {
+            // Only an authentication failure is the fault of the key; other statuses (rate limits, outages, ...)
+            // are retried with the same key.
+            match status {
+                101 => {}
+                401 | 403 => keys.reject(index, status),
+                _ => keys.retry(index, status),
+            }
+        }
+
+        if status != 101 {
+            local.write_all(&head).await?;
+            local.write_all(&rest).await?;
+            tokio::io::copy_bidirectional(&mut local, &mut upstream).await?;
+            return Ok(());
+        }
+
+        #[cfg(feature = "compression")]
+        let (head, inflater) = match self.compression {
+            true => match deflate::accept(&head) {
+                (head, true) => (head, Some(Decompress::new(false))),
+                (head, false) => (head, None),
+            },
+            false => (head, None),
+        };
+        local.write_all(&head).await?;
+
+        // Topics of the joins sent on this connection, by their reference.
+        let joins = Arc::new(Mutex::new(HashMap::new()));
+        let mut inbound = Inbound {
+            #[cfg(feature = "compression")]
+            inflater,
+            max_message_bytes: self.max_message_bytes,
+            joins: joins.clone(),
+            join_replies: self.join_replies.clone(),
+        };
+        let (local_read, local_write) = local.into_split();
+        let (upstream_read, upstream_write) = tokio::io::split(upstream);
+        let relay = tokio::spawn(frame::relay(
+            Cursor::new(rest).chain(upstream_read),
+            local_write,
+            move |message| inbound.handle(message),
+        ));
+        frame::forward(local_read, upstream_write, |payload| {
+            if let Some((reference, topic)) = join(payload) {
+                joins.lock().unwrap().insert(reference, topic);
+            }
+        })
+        .await?;
+        relay.await.map_err(io::Error::other)?
+    }
+
+    /// Opens a TCP connection to the endpoint.
+    #[cfg(not(feature = "proxy"))]
+    async fn connect(&self) -> io::Result<TcpStream> {
+        TcpStream::connect((self.host.as_str(), self.port)).await
+    }
+
+    /// Opens a TCP connection to the endpoint, through the proxy if there is one.
+    #[cfg(feature = "proxy")]
+    async fn connect(&self) -> io::Result<TcpStream> {
+        match &self.proxy {
+            None => TcpStream::connect((self.host.as_str(), self.port)).await,
+            Some(Proxy::Http { addr, auth }) => {
+                let mut stream = TcpStream::connect(addr.as_str()).await?;
+                http_connect(&mut stream, &self.host, self.port, auth.as_ref()).await?;
+                Ok(stream)
+            }
+            Some(Proxy::Socks5 { addr, auth }) => {
+                let mut stream = TcpStream::connect(addr.as_str()).await?;
+                socks5_connect(&mut stream, &self.host, self.port, auth.as_ref()).await?;
+                Ok(stream)
+            }
+        }
+    }
+}
+
+/// Builds the TLS configuration used when none is given, trusting the root certificates of the platform with
+/// `rustls-tls-native-roots` and those of [`webpki-roots`](https://crates.io/crates/webpki-roots) with
+/// `rustls-tls-webpki-roots`.
+fn default_tls_config() -> io::Result<Arc<ClientConfig>> {
+    #[allow(unused_mut)]
+    let mut roots = RootCertStore::empty();
+    #[cfg(feature = "rustls-tls-native-roots")]
+    {
+        let native = rustls_native_certs::load_native_certs();
+        for error in &native.errors {
+            warn!(error = %error, "could not load native root certificate");
+        }
+        roots.add_parsable_certificates(native.certs);
+    }
+    #[cfg(feature = "rustls-tls-webpki-roots")]
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+    if roots.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no root certificates: enable `rustls-tls-native-roots` or `rustls-tls-webpki-roots`, or set \
+             `ClientBuilder::tls_config`",
+        ));
+    }
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+/// Returns a random secret for the local listener.
+fn secret() -> io::Result<String> {
+    let mut bytes = [0; 16];
+    rustls::crypto::ring::default_provider()
+        .secure_random
+        .fill(&mut bytes)
+        .map_err(|_| io::Error::other("could not generate bridge secret"))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Replaces the scheme, host and port of `endpoint` with an unencrypted connection to `addr`.
+fn local_endpoint(endpoint: &Url, addr: SocketAddr) -> Url {
+    let mut local = endpoint.clone();
+    let _ = local.set_scheme("ws");
+    let _ = local.set_host(Some(&addr.ip().to_string()));
+    let _ = local.set_port(Some(addr.port()));
+    local
+}
+
+/// Handles the messages of the server before they are relayed to the socket.
+struct Inbound {
+    /// Inflater of the connection, if the server accepted permessage-deflate.
+    #[cfg(feature = "compression")]
+    inflater: Option<Decompress>,
+    max_message_bytes: Option<usize>,
+    /// Topics of the joins that were not replied to yet, by their reference.
+    joins: Arc<Mutex<HashMap<String, String>>>,
+    join_replies: broadcast::Sender<JoinReply>,
+}
+
+impl Inbound {
+    /// Returns the message to relay in place of `message`, if any.
+    fn handle(&mut self, #[allow(unused_mut)] mut message: Message) -> io::Result<Option<Message>> {
+        #[cfg(feature = "compression")]
+        if let Some(inflater) = &mut self.inflater {
+            deflate::inflate(inflater, &mut message)?;
+        }
+        if let Some(max) = self.max_message_bytes {
+            let len = message.payload.len();
+            if len > max {
+                warn!(len, max, "message dropped: too large");
+                return Ok(None);
+            }
+        }
+        if message.opcode == 0x1 {
+            if let Some(reply) = self.join_reply(&message.payload) {
+                // Nobody may be waiting for the reply.
+                let _ = self.join_replies.send(reply);
+            }
+        }
+        Ok(Some(message))
+    }
+}
+
+/// Reply of a Phoenix channel, the payload of a `phx_reply` message.
+#[derive(Deserialize)]
+struct Reply {
+    status: String,
+    #[serde(default)]
+    response: Value,
+}
+
+impl Inbound {
+    /// Returns the reply to a join in the text message `payload`, if it is one.
+    fn join_reply(&self, payload: &[u8]) -> Option<JoinReply> {
+        // Avoid parsing every event twice.
+        if !payload.windows(11).any(|w| w == b"\"phx_reply\"") {
+            return None;
+        }
+        let (_, reference, _, event, reply): (Value, Value, String, String, Reply) =
+            serde_json::from_slice(payload).ok()?;
+        if event != "phx_reply" {
+            return None;
+        }
+        let topic = self.joins.lock().unwrap().remove(&reference.to_string())?;
+        Some(JoinReply {
+            topic,
+            rejected: (reply.status != "ok").then_some(reply.response),
+        })
+    }
+}
+
+/// Returns the reference and topic of the join in the text message `payload` of the socket, if it is one.
+///
+/// Messages are `[join_ref, ref, topic, event, payload]`, and replies carry the `ref` of the message they answer.
+fn join(payload: &[u8]) -> Option<(String, String)> {
+    let (_, reference, topic, event, _): (Value, Value, String, String, Value) =
+        serde_json::from_slice(payload).ok()?;
+    (event == "phx_join").then(|| (reference.to_string(), topic))
+}
+
+/// Connection to the endpoint, with or without TLS.
+trait Upstream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S> Upstream for S where S: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Reads an HTTP head (up to and including the empty line), returning it and any bytes read past it.
+async fn read_head<S>(stream: &mut S) -> io::Result<(Vec<u8>, Vec<u8>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    let mut chunk = [0; 1024];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Ok((buf, rest));
+        }
+        if buf.len() > MAX_HEAD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "http head too long",
+            ));
+        }
+
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Returns the status code of an HTTP response head, or `0` if it has none.
+fn status(head: &[u8]) -> u16 {
+    String::from_utf8_lossy(head)
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Replaces the `Host` header of an HTTP request head.
+fn rewrite_host(head: &[u8], host: &str, port: u16) -> Vec<u8> {
+    let head = String::from_utf8_lossy(head);
+    head.split("\r\n")
+        .map(|line| match line.split_once(':') {
+            Some((name, _)) if name.eq_ignore_ascii_case("host") => match port {
+                443 | 80 => format!("Host: {}", host),
+                _ => format!("Host: {}:{}", host, port),
+            },
+            _ => line.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        .into_bytes()
+}
+
+/// Rewrites the query parameters in the request line of an HTTP request head with `f`. Returns `None` if the head
+/// has no request line or `f` returns `None`.
+fn rewrite_query<F>(head: &[u8], f: F) -> Option<Vec<u8>>
+where
+    F: FnOnce(Vec<(String, String)>) -> Option<Vec<(String, String)>>,
+{
+    let head = String::from_utf8_lossy(head);
+    let (line, headers) = head.split_once("\r\n").unwrap_or((&head, ""));
+    let mut parts = line.splitn(3, ' ');
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) => (method, target, version),
+        _ => return None,
+    };
+    let mut url = Url::parse(&format!("http://localhost{}", target)).ok()?;
+
+    let pairs = f(url.query_pairs().into_owned().collect())?;
+    match pairs.is_empty() {
+        true => url.set_query(None),
+        false => {
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+
+    let target = &url[url::Position::BeforePath..];
+    Some(format!("{} {} {}\r\n{}", method, target, version, headers).into_bytes())
+}
+
+/// Removes the secret of the bridge from the query of an HTTP request head, returning `None` if it is missing or
+/// does not match `secret`.
+fn take_secret(head: &[u8], secret: &str) -> Option<Vec<u8>> {
+    rewrite_query(head, |mut pairs| {
+        let index = pairs.iter().position(|(k, _)| k == SECRET_PARAM)?;
+        let (_, presented) = pairs.remove(index);
+        // Compared without returning early, so that the time taken does not reveal how much of the secret matched.
+        let matches = presented.len() == secret.len()
+            && presented
+                .bytes()
+                .zip(secret.bytes())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0;
+        matches.then_some(pairs)
+    })
+}
+
+/// Replaces the `token` query parameter in the request line of an HTTP request head.
+#[cfg(feature = "key-rotation")]
+fn rewrite_token(head: &[u8], token: &str) -> Vec<u8> {
+    rewrite_query(head, |pairs| {
+        Some(
+            pairs
+                .into_iter()
+                .map(|(k, v)| match k.as_str() {
+                    "token" => (k, token.to_owned()),
+                    _ => (k, v),
+                })
+                .collect(),
+        )
+    })
+    .unwrap_or_else(|| head.to_vec())
+}
+
+/// Opens a tunnel through an HTTP proxy with `CONNECT`.
+#[cfg(feature = "proxy")]
+async fn http_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: Option<&(String, String)>,
+) -> io::Result<()> {
+    let mut request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+        host = host,
+        port = port
+    );
+    if let Some((username, password)) = auth {
+        let credentials = STANDARD.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let (head, _) = read_head(stream).await?;
+    let head = String::from_utf8_lossy(&head);
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    match status.starts_with('2') {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("proxy refused CONNECT with status {}", status),
+        )),
+    }
+}
+
+/// Opens a tunnel through a SOCKS5 proxy ([RFC 1928](https://www.rfc-editor.org/rfc/rfc1928)), authenticating with
+/// a username and password ([RFC 1929](https://www.rfc-editor.org/rfc/rfc1929)) if given.
+#[cfg(feature = "proxy")]
+async fn socks5_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: Option<&(String, String)>,
+) -> io::Result<()> {
+    fn error(message: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::ConnectionRefused, message.to_owned())
+    }
+
+    /// Returns the length of a field, which SOCKS5 encodes in a single byte.
+    fn len(field: &str, value: &str) -> io::Result<u8> {
+        u8::try_from(value.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("socks5 {} is longer than 255 bytes", field),
+            )
+        })
+    }
+
+    let host_len = len("host", host)?;
+    let auth = match auth {
+        Some((username, password)) => Some((
+            (len("username", username)?, username),
+            (len("password", password)?, password),
+        )),
+        None => None,
+    };
+
+    // Greeting: offer "no authentication", and "username/password" if there are credentials.
+    match auth {
+        Some(_) => stream.write_all(&[5, 2, 0, 2]).await?,
+        None => stream.write_all(&[5, 1, 0]).await?,
+    }
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    match (reply[1], auth) {
+        (0, _) => {}
+        (2, Some(((username_len, username), (password_len, password)))) => {
+            let mut request = vec![1, username_len];
+            request.extend_from_slice(username.as_bytes());
+            request.push(password_len);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0 {
+                return Err(error("socks5 proxy rejected credentials"));
+            }
+        }
+        _ => {
+            return Err(error(
+                "socks5 proxy offered no acceptable authentication method",
+            ))
+        }
+    }
+
+    // Connect to the endpoint by domain name, so that it is resolved by the proxy.
+    let mut request = vec![5, 1, 0, 3, host_len];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(error("socks5 proxy could not connect"));
+    }
+    // Skip the bound address.
+    let len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => stream.read_u8().await? as usize,
+        _ => return Err(error("socks5 proxy replied with an unknown address type")),
+    };
+    let mut addr = vec![0; len + 2];
+    stream.read_exact(&mut addr).await?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "key-rotation"))]
+mod tests {
+    use super::*;
+
+    fn keys(selection: KeySelection) -> (Keys, mpsc::UnboundedReceiver<KeyRejected>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let tokens = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        (Keys::new(tokens, selection, Some(tx)), rx)
+    }
+
+    #[test]
+    fn failover_moves_on_from_rejected_keys_only() {
+        let (keys, mut rejected) = keys(KeySelection::Failover);
+        assert_eq!(keys.select(), 0);
+        keys.retry(0, 503);
+        assert_eq!(keys.select(), 0);
+        assert!(rejected.try_recv().is_err());
+
+        keys.reject(0, 401);
+        assert_eq!(keys.select(), 1);
+        assert_eq!(
+            rejected.try_recv().unwrap(),
+            KeyRejected {
+                index: 0,
+                status: 401
+            }
+        );
+    }
+
+    #[test]
+    fn round_robin_retries_failed_handshakes_with_the_same_key() {
+        let (keys, _rejected) = keys(KeySelection::RoundRobin);
+        assert_eq!(keys.select(), 0);
+        let failed = keys.select();
+        keys.retry(failed, 429);
+        assert_eq!(keys.select(), failed);
+        assert_eq!(keys.select(), 2);
+    }
+}
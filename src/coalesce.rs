@@ -0,0 +1,119 @@
+use crate::{ordering::Sequenced, schema::NftId, Event};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Coalescing of bursts of events about the same item, such as the metadata updates of a collection reveal.
+///
+/// The first event of a coalesced type about an item is held for the window; events of the same type about the
+/// same item that arrive during the window replace it, and the latest is delivered when the window ends. Events
+/// of other types are delivered immediately. Events without an item (collection and trait offers) are coalesced
+/// per collection.
+///
+/// The configuration is cheap to clone. Every stream has its own windows, but all clones share the counter of
+/// replaced events.
+/// ```no_run
+/// # use opensea_stream::{coalesce::Coalesce, Client, Collection, Network, SubscribeConfig};
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let coalesce = Coalesce::new(Duration::from_secs(5));
+///
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let config = SubscribeConfig::new().coalesce(coalesce.clone());
+/// let mut events = client.subscribe_with_config(Collection::All, config).await?;
+///
+/// while let Some(event) = events.recv().await {
+///     println!("{:?} ({} coalesced so far)", event?, coalesce.coalesced());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Coalesce {
+    window: Duration,
+    events: HashSet<Event>,
+    coalesced: Arc<AtomicU64>,
+}
+
+impl Coalesce {
+    /// Constructs a new `Coalesce` which coalesces [`Event::ItemMetadataUpdated`] events within `window`.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            events: HashSet::from([Event::ItemMetadataUpdated]),
+            coalesced: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Sets the event types that are coalesced.
+    pub fn events(mut self, events: impl IntoIterator<Item = Event>) -> Self {
+        self.events = events.into_iter().collect();
+        self
+    }
+
+    /// Returns the number of events that were replaced by a later event.
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+}
+
+/// Identifies the events that replace each other.
+type Key = (Event, String, Option<NftId>);
+
+/// State of a [`Coalesce`] for a single stream.
+#[derive(Debug)]
+pub(crate) struct Coalescer {
+    config: Coalesce,
+    /// Ends of the open windows, oldest first.
+    windows: VecDeque<(Instant, Key)>,
+    latest: HashMap<Key, Sequenced>,
+}
+
+impl Coalescer {
+    pub(crate) fn new(config: Coalesce) -> Self {
+        Self {
+            config,
+            windows: VecDeque::new(),
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Holds an event until the end of its window, or returns it if its type is not coalesced.
+    pub(crate) fn push(&mut self, event: Sequenced, now: Instant) -> Option<Sequenced> {
+        let payload = &event.event.payload;
+        if !self.config.events.contains(&payload.event()) {
+            return Some(event);
+        }
+
+        let key = (
+            payload.event(),
+            payload.collection().0.clone(),
+            payload.context().map(|context| context.item.nft_id.clone()),
+        );
+        if self.latest.insert(key.clone(), event).is_some() {
+            self.config.coalesced.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.windows.push_back((now + self.config.window, key));
+        }
+        None
+    }
+
+    /// Removes the event of the oldest window that ended at `now`. Otherwise, returns the time at which the next
+    /// window ends, or `None` if there are no open windows.
+    pub(crate) fn pop(&mut self, now: Instant) -> Result<Sequenced, Option<Instant>> {
+        match self.windows.front() {
+            Some((end, _)) if *end <= now => {
+                let (_, key) = self.windows.pop_front().unwrap();
+                Ok(self.latest.remove(&key).unwrap())
+            }
+            window => Err(window.map(|(end, _)| *end)),
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use crate::{
+    middleware::{self, Pipeline},
+    sinks::file::{FileSinkBuilder, Format},
+    Client, ClientBuilder, ClientError, Collection, Event, Network, SubscribeConfig, Subscription,
+};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use thiserror::Error;
+
+#[cfg(feature = "http")]
+use crate::enrich::ContractAddress;
+#[cfg(feature = "mqtt")]
+use crate::sinks::mqtt::MqttSinkBuilder;
+#[cfg(feature = "notify")]
+use crate::sinks::notify::{NotifySinkBuilder, Service};
+#[cfg(feature = "notify")]
+use url::Url;
+
+/// Environment variable holding the path of a config file that [`Config::from_env`] starts from.
+pub const CONFIG_VAR: &str = "OPENSEA_STREAM_CONFIG";
+
+/// Configuration of a client and its subscriptions, read from a config file or the environment.
+///
+/// Config files are TOML, or JSON if their extension is `.json`, with the same fields:
+/// ```toml
+/// api_key = "YOUR_API_KEY_HERE"
+/// network = "mainnet"               # or "testnet"; defaults to "mainnet"
+/// collections = ["wandernauts"]     # slugs; all collections if empty or omitted
+/// contracts = ["ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4"] # requires the `http` feature
+/// events = ["item_listed", "item_sold"] # all events if empty or omitted
+///
+/// [sinks.file]
+/// path = "events.ndjson"
+/// format = "ndjson"                 # or "csv"
+/// max_bytes = 104857600
+/// max_age_secs = 86400
+///
+/// [sinks.mqtt]                      # requires the `mqtt` feature
+/// host = "localhost"
+/// topic = "opensea/{collection}/{event_type}"
+///
+/// [[sinks.notify]]                  # requires the `notify` feature
+/// service = "discord"               # or "slack"
+/// webhook = "https://discord.com/api/webhooks/..."
+/// ```
+/// Sections of sinks whose feature is not enabled are ignored. The settings of sinks are only read; they are turned
+/// into builders with methods such as [`FileSinkConfig::builder`], and run by the application.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// OpenSea API key. Required, unless it is read from `OPENSEA_API_KEY` by [`Config::from_env`].
+    #[serde(default)]
+    pub api_key: String,
+    /// Network to connect to.
+    #[serde(default = "mainnet", deserialize_with = "network")]
+    pub network: Network,
+    /// Slugs of the collections to subscribe to. All collections are subscribed to if empty.
+    #[serde(default)]
+    pub collections: Vec<String>,
+    /// Contracts of collections to subscribe to, resolved to their collections when subscribing.
+    #[cfg(feature = "http")]
+    #[serde(default)]
+    pub contracts: Vec<ContractAddress>,
+    /// Event types to deliver. All events are delivered if empty.
+    #[serde(default)]
+    pub events: Vec<Event>,
+    /// Settings of sinks.
+    #[serde(default)]
+    pub sinks: SinksConfig,
+}
+
+/// Settings of the sinks of a [`Config`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SinksConfig {
+    /// Settings of a file sink.
+    #[serde(default)]
+    pub file: Option<FileSinkConfig>,
+    /// Settings of an MQTT sink.
+    #[cfg(feature = "mqtt")]
+    #[serde(default)]
+    pub mqtt: Option<MqttSinkConfig>,
+    /// Settings of webhook sinks.
+    #[cfg(feature = "notify")]
+    #[serde(default)]
+    pub notify: Vec<NotifySinkConfig>,
+}
+
+/// Settings of a [`FileSink`](crate::sinks::file::FileSink).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileSinkConfig {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Format of the records, `ndjson` (the default) or `csv`.
+    #[serde(default = "ndjson", deserialize_with = "format")]
+    pub format: Format,
+    /// Fields written for each event. See [`FileSinkBuilder::fields`].
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Size in bytes after which the file is rotated.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// Age in seconds after which the file is rotated.
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+}
+
+impl FileSinkConfig {
+    /// Returns a builder for the sink with these settings.
+    pub fn builder(&self) -> FileSinkBuilder {
+        let mut builder = FileSinkBuilder::new(&self.path)
+            .format(self.format)
+            .fields(self.fields.iter().cloned());
+        if let Some(max_bytes) = self.max_bytes {
+            builder = builder.max_bytes(max_bytes);
+        }
+        if let Some(max_age) = self.max_age_secs {
+            builder = builder.max_age(Duration::from_secs(max_age));
+        }
+        builder
+    }
+}
+
+/// Settings of an [`MqttSink`](crate::sinks::mqtt::MqttSink).
+#[cfg(feature = "mqtt")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttSinkConfig {
+    /// Host of the broker.
+    pub host: String,
+    /// Port of the broker. Defaults to 1883.
+    #[serde(default = "mqtt_port")]
+    pub port: u16,
+    /// Client ID to connect with. Defaults to `opensea-stream`.
+    #[serde(default = "mqtt_client_id")]
+    pub client_id: String,
+    /// Template of topics. Defaults to [`DEFAULT_TOPIC`](crate::sinks::mqtt::DEFAULT_TOPIC).
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// Quality of service, from 0 (the default) to 2.
+    #[serde(default, deserialize_with = "qos")]
+    pub qos: u8,
+    /// Whether the broker retains the last event of every topic.
+    #[serde(default)]
+    pub retain: bool,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttSinkConfig {
+    /// Returns a builder for the sink with these settings.
+    pub fn builder(&self) -> MqttSinkBuilder {
+        use rumqttc::{MqttOptions, QoS};
+
+        let options = MqttOptions::new(&self.client_id, &self.host, self.port);
+        // Values above 2 are rejected when the config is read.
+        let qos = match self.qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            _ => QoS::ExactlyOnce,
+        };
+        let builder = MqttSinkBuilder::new(options).qos(qos).retain(self.retain);
+        match &self.topic {
+            Some(topic) => builder.topic(topic),
+            None => builder,
+        }
+    }
+}
+
+/// Settings of a [`NotifySink`](crate::sinks::notify::NotifySink).
+#[cfg(feature = "notify")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifySinkConfig {
+    /// Service of the webhook, `discord` or `slack`.
+    #[serde(deserialize_with = "service")]
+    pub service: Service,
+    /// URL of the webhook.
+    pub webhook: Url,
+    /// Template of messages. Defaults to [`DEFAULT_TEMPLATE`](crate::sinks::notify::DEFAULT_TEMPLATE).
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+#[cfg(feature = "notify")]
+impl NotifySinkConfig {
+    /// Returns a builder for the sink with these settings.
+    pub fn builder(&self) -> NotifySinkBuilder {
+        let builder = NotifySinkBuilder::new(self.service, self.webhook.clone());
+        match &self.template {
+            Some(template) => builder.template(template),
+            None => builder,
+        }
+    }
+}
+
+/// Errors that can be encountered while reading a [`Config`].
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    #[error("could not read {}: {source}", path.display())]
+    Read {
+        /// Path of the config file.
+        path: PathBuf,
+        /// The error.
+        source: io::Error,
+    },
+    /// The config file is not valid.
+    #[error("invalid config: {0}")]
+    Invalid(String),
+    /// A required setting is missing.
+    #[error("{0} is not set")]
+    Missing(&'static str),
+    /// An environment variable has an invalid value.
+    #[error("invalid value `{value}` for {var}")]
+    InvalidVar {
+        /// Name of the variable.
+        var: &'static str,
+        /// The value.
+        value: String,
+    },
+    /// The client could not be created.
+    #[error("could not connect: {0}")]
+    Connect(io::Error),
+}
+
+impl Config {
+    /// Reads a config file, as JSON if its extension is `.json` and as TOML otherwise.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::read(path.as_ref())?.require_api_key("api_key")
+    }
+
+    /// Reads a config file without requiring an API key, which may still be set from the environment.
+    fn read(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        match path.extension().is_some_and(|ext| ext == "json") {
+            true => {
+                serde_json::from_str(&contents).map_err(|e| ConfigError::Invalid(e.to_string()))
+            }
+            false => {
+                toml::from_str(&contents).map_err(|e| ConfigError::Invalid(e.message().to_owned()))
+            }
+        }
+    }
+
+    /// Parses a TOML config.
+    pub fn from_toml(s: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<Self>(s)
+            .map_err(|e| ConfigError::Invalid(e.message().to_owned()))?
+            .require_api_key("api_key")
+    }
+
+    /// Parses a JSON config.
+    pub fn from_json(s: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str::<Self>(s)
+            .map_err(|e| ConfigError::Invalid(e.to_string()))?
+            .require_api_key("api_key")
+    }
+
+    /// Returns an error naming `setting` if the API key is not set.
+    fn require_api_key(self, setting: &'static str) -> Result<Self, ConfigError> {
+        match self.api_key.is_empty() {
+            true => Err(ConfigError::Missing(setting)),
+            false => Ok(self),
+        }
+    }
+
+    /// Reads the config from the environment.
+    ///
+    /// If [`CONFIG_VAR`] (`OPENSEA_STREAM_CONFIG`) is set, the config file at that path is read first. These
+    /// variables then override its settings:
+    /// - `OPENSEA_API_KEY`: the API key, which is required if the config file has none.
+    /// - `OPENSEA_NETWORK`: `mainnet` or `testnet`.
+    /// - `OPENSEA_COLLECTIONS`: comma-separated slugs of collections.
+    /// - `OPENSEA_CONTRACTS`: comma-separated contracts of collections, such as `ethereum/0x…`, with the `http`
+    ///   feature.
+    /// - `OPENSEA_EVENTS`: comma-separated event types, such as `item_listed,item_sold`.
+    ///
+    /// Sinks can only be configured in the config file.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let var = |name: &str| env::var(name).ok();
+        let mut config = match var(CONFIG_VAR) {
+            Some(path) => Self::read(Path::new(&path))?,
+            None => Self {
+                api_key: String::new(),
+                network: Network::Mainnet,
+                collections: Vec::new(),
+                #[cfg(feature = "http")]
+                contracts: Vec::new(),
+                events: Vec::new(),
+                sinks: SinksConfig::default(),
+            },
+        };
+
+        if let Some(value) = var("OPENSEA_API_KEY") {
+            config.api_key = value;
+        }
+        if let Some(value) = var("OPENSEA_NETWORK") {
+            config.network = parse_network(&value).ok_or(ConfigError::InvalidVar {
+                var: "OPENSEA_NETWORK",
+                value,
+            })?;
+        }
+        if let Some(value) = var("OPENSEA_COLLECTIONS") {
+            config.collections = list(&value).map(str::to_owned).collect();
+        }
+        #[cfg(feature = "http")]
+        if let Some(value) = var("OPENSEA_CONTRACTS") {
+            config.contracts = list(&value)
+                .map(ContractAddress::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|_| ConfigError::InvalidVar {
+                    var: "OPENSEA_CONTRACTS",
+                    value: value.clone(),
+                })?;
+        }
+        if let Some(value) = var("OPENSEA_EVENTS") {
+            config.events = list(&value)
+                .map(Event::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|_| ConfigError::InvalidVar {
+                    var: "OPENSEA_EVENTS",
+                    value: value.clone(),
+                })?;
+        }
+        config.require_api_key("OPENSEA_API_KEY")
+    }
+
+    /// Returns a builder for the client of this config.
+    pub fn client_builder(&self) -> ClientBuilder {
+        ClientBuilder::new(self.network, &self.api_key)
+    }
+
+    /// Returns the collections to subscribe to: [`Collection::All`] if none are configured.
+    ///
+    /// With the `http` feature, the configured contracts follow the slugs.
+    pub fn collections(&self) -> Vec<Collection> {
+        #[allow(unused_mut)]
+        let mut collections: Vec<_> = self
+            .collections
+            .iter()
+            .cloned()
+            .map(Collection::Collection)
+            .collect();
+        #[cfg(feature = "http")]
+        collections.extend(
+            self.contracts
+                .iter()
+                .copied()
+                .map(Collection::ContractAddress),
+        );
+        match collections.is_empty() {
+            true => vec![Collection::All],
+            false => collections,
+        }
+    }
+
+    /// Returns the configuration of subscriptions, which only delivers the configured event types.
+    pub fn subscribe_config(&self) -> SubscribeConfig {
+        let config = SubscribeConfig::new();
+        if self.events.is_empty() {
+            return config;
+        }
+        let events = self.events.clone();
+        let filter = middleware::filter(move |event| events.contains(&event.payload.event()));
+        config.middleware(Pipeline::new().layer(filter))
+    }
+
+    /// Subscribes `client` to every configured collection, in order, stopping at the first error.
+    pub async fn subscribe(&self, client: &mut Client) -> Result<Vec<Subscription>, ClientError> {
+        let mut subscriptions = Vec::new();
+        for collection in self.collections() {
+            subscriptions.push(
+                client
+                    .subscribe_with_config(collection, self.subscribe_config())
+                    .await?,
+            );
+        }
+        Ok(subscriptions)
+    }
+}
+
+/// Splits a comma-separated list, skipping empty items.
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn parse_network(s: &str) -> Option<Network> {
+    match s.to_ascii_lowercase().as_str() {
+        "mainnet" => Some(Network::Mainnet),
+        "testnet" => Some(Network::Testnet),
+        _ => None,
+    }
+}
+
+fn mainnet() -> Network {
+    Network::Mainnet
+}
+
+fn network<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Network, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_network(&s).ok_or_else(|| D::Error::custom(format!("unknown network `{}`", s)))
+}
+
+fn ndjson() -> Format {
+    Format::Ndjson
+}
+
+fn format<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Format, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "ndjson" => Ok(Format::Ndjson),
+        "csv" => Ok(Format::Csv),
+        s => Err(D::Error::custom(format!("unknown format `{}`", s))),
+    }
+}
+
+#[cfg(feature = "mqtt")]
+fn mqtt_port() -> u16 {
+    1883
+}
+
+#[cfg(feature = "mqtt")]
+fn mqtt_client_id() -> String {
+    "opensea-stream".to_owned()
+}
+
+#[cfg(feature = "mqtt")]
+fn qos<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    match u8::deserialize(deserializer)? {
+        qos @ 0..=2 => Ok(qos),
+        qos => Err(D::Error::custom(format!(
+            "invalid qos `{}`, expected 0, 1 or 2",
+            qos
+        ))),
+    }
+}
+
+#[cfg(feature = "notify")]
+fn service<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Service, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "discord" => Ok(Service::Discord),
+        "slack" => Ok(Service::Slack),
+        s => Err(D::Error::custom(format!("unknown service `{}`", s))),
+    }
+}
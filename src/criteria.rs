@@ -0,0 +1,257 @@
+use crate::schema::{CollectionOfferData, NftId, TraitCriteria, TraitOfferData};
+use ethers_core::{
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+use thiserror::Error;
+
+/// An attribute of an item, as described by the
+/// [metadata standards](https://docs.opensea.io/docs/metadata-standards#attributes).
+///
+/// Attributes deserialize from the `attributes` of token metadata; numeric and boolean values are compared as
+/// their JSON text (e.g. `5`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Attribute {
+    /// Type of the trait (e.g. `Background`).
+    pub trait_type: String,
+    /// Value of the trait (e.g. `Red`).
+    pub value: String,
+}
+
+impl Attribute {
+    /// Constructs a new `Attribute`.
+    pub fn new(trait_type: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            trait_type: trait_type.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Attribute {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            trait_type: String,
+            value: Value,
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+        let value = match raw.value {
+            Value::String(s) => s,
+            v => v.to_string(),
+        };
+        Ok(Self::new(raw.trait_type, value))
+    }
+}
+
+/// Token IDs that criteria apply to, in the encoding of OpenSea: `*` for every token, or a comma-separated list of
+/// IDs and inclusive ranges, such as `1,3,10:20`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenIds {
+    /// Every token of the contract.
+    All,
+    /// Tokens in any of the ranges.
+    Ranges(Vec<RangeInclusive<U256>>),
+}
+
+impl TokenIds {
+    /// Returns `true` if `id` is one of the tokens.
+    pub fn contains(&self, id: U256) -> bool {
+        match self {
+            TokenIds::All => true,
+            TokenIds::Ranges(ranges) => ranges.iter().any(|range| range.contains(&id)),
+        }
+    }
+}
+
+/// Error returned when encoded token IDs cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid token ids `{0}`")]
+pub struct InvalidTokenIds(String);
+
+impl FromStr for TokenIds {
+    type Err = InvalidTokenIds;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(TokenIds::All);
+        }
+        let id =
+            |id: &str| U256::from_dec_str(id.trim()).map_err(|_| InvalidTokenIds(s.to_owned()));
+        s.split(',')
+            .map(|part| match part.split_once(':') {
+                Some((start, end)) => Ok(id(start)?..=id(end)?),
+                None => id(part).map(|id| id..=id),
+            })
+            .collect::<Result<_, _>>()
+            .map(TokenIds::Ranges)
+    }
+}
+
+impl fmt::Display for TokenIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenIds::All => write!(f, "*"),
+            TokenIds::Ranges(ranges) => {
+                for (i, range) in ranges.iter().enumerate() {
+                    let sep = if i == 0 { "" } else { "," };
+                    match range.start() == range.end() {
+                        true => write!(f, "{}{}", sep, range.start())?,
+                        false => write!(f, "{}{}:{}", sep, range.start(), range.end())?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Criteria of an offer on any item of a collection that satisfies them, such as a collection or trait offer.
+///
+/// An item satisfies the criteria if it belongs to the contract, is one of the token IDs (if any), has every
+/// trait (if any), and is in the merkle tree of token IDs (if any). Criteria can be built from the payload of an
+/// offer, or by hand:
+/// ```
+/// # use opensea_stream::{criteria::{Attribute, Criteria}, schema::{Chain, NftId, TraitCriteria}};
+/// # fn main() -> anyhow::Result<()> {
+/// let contract = "0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4".parse()?;
+/// let criteria = Criteria::new(contract)
+///     .token_ids("1:1000".parse()?)
+///     .with_trait(TraitCriteria {
+///         trait_type: "Background".to_string(),
+///         trait_value: "Red".to_string(),
+///     });
+///
+/// let nft_id = NftId { network: Chain::Ethereum, address: contract, id: 7.into() };
+/// let traits: Vec<Attribute> = serde_json::from_str(r#"[{"trait_type":"Background","value":"Red"}]"#)?;
+/// assert!(criteria.matches(&nft_id, &traits));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Criteria {
+    contract: Address,
+    token_ids: TokenIds,
+    traits: Vec<TraitCriteria>,
+    merkle_root: Option<H256>,
+}
+
+impl Criteria {
+    /// Constructs new `Criteria` satisfied by every item of `contract`.
+    pub fn new(contract: Address) -> Self {
+        Self {
+            contract,
+            token_ids: TokenIds::All,
+            traits: Vec::new(),
+            merkle_root: None,
+        }
+    }
+
+    /// Only matches items with one of `token_ids`.
+    pub fn token_ids(mut self, token_ids: TokenIds) -> Self {
+        self.token_ids = token_ids;
+        self
+    }
+
+    /// Only matches items with the trait, in addition to any trait added before.
+    pub fn with_trait(mut self, criteria: TraitCriteria) -> Self {
+        self.traits.push(criteria);
+        self
+    }
+
+    /// Only matches items whose token ID is in the merkle tree with `root`, as used by Seaport criteria orders.
+    ///
+    /// Membership is checked with a proof; see [`Criteria::matches_with_proof`].
+    pub fn merkle_root(mut self, root: H256) -> Self {
+        self.merkle_root = Some(root);
+        self
+    }
+
+    /// Returns the contract of the items.
+    pub fn contract(&self) -> Address {
+        self.contract
+    }
+
+    /// Returns the traits that items must have.
+    pub fn traits(&self) -> &[TraitCriteria] {
+        &self.traits
+    }
+
+    /// Returns `true` if the item `nft_id` with attributes `traits` satisfies the criteria.
+    ///
+    /// Criteria with a merkle root never match without a proof; see [`Criteria::matches_with_proof`].
+    pub fn matches(&self, nft_id: &NftId, traits: &[Attribute]) -> bool {
+        self.merkle_root.is_none() && self.matches_without_root(nft_id, traits)
+    }
+
+    /// Returns `true` if the item `nft_id` with attributes `traits` satisfies the criteria, checking that its
+    /// token ID is in the merkle tree with `proof`, the hashes of its siblings from the leaf up.
+    ///
+    /// Leaves are the Keccak-256 hash of the token ID as a 32-byte big-endian word, and pairs are hashed in sorted
+    /// order, as Seaport verifies criteria proofs. Without a merkle root, the proof is ignored.
+    pub fn matches_with_proof(&self, nft_id: &NftId, traits: &[Attribute], proof: &[H256]) -> bool {
+        let in_tree = match self.merkle_root {
+            Some(root) => merkle_root(nft_id.id, proof) == root,
+            None => true,
+        };
+        in_tree && self.matches_without_root(nft_id, traits)
+    }
+
+    fn matches_without_root(&self, nft_id: &NftId, traits: &[Attribute]) -> bool {
+        nft_id.address == self.contract
+            && self.token_ids.contains(nft_id.id)
+            && self.traits.iter().all(|criteria| {
+                traits.iter().any(|attribute| {
+                    attribute.trait_type == criteria.trait_type
+                        && attribute.value == criteria.trait_value
+                })
+            })
+    }
+}
+
+impl From<&CollectionOfferData> for Criteria {
+    fn from(offer: &CollectionOfferData) -> Self {
+        Self::new(offer.asset_contract_criteria)
+    }
+}
+
+impl From<&TraitOfferData> for Criteria {
+    fn from(offer: &TraitOfferData) -> Self {
+        Self::new(offer.asset_contract_criteria).with_trait(offer.trait_criteria.clone())
+    }
+}
+
+impl CollectionOfferData {
+    /// Returns the criteria of the offer.
+    pub fn criteria(&self) -> Criteria {
+        self.into()
+    }
+}
+
+impl TraitOfferData {
+    /// Returns the criteria of the offer.
+    pub fn criteria(&self) -> Criteria {
+        self.into()
+    }
+}
+
+/// Computes the root of a merkle tree from a token ID and the proof of its leaf.
+fn merkle_root(id: U256, proof: &[H256]) -> H256 {
+    let mut word = [0; 32];
+    id.to_big_endian(&mut word);
+    let leaf = H256(keccak256(word));
+    proof.iter().fold(leaf, |hash, sibling| {
+        let (a, b) = match hash <= *sibling {
+            true => (hash, *sibling),
+            false => (*sibling, hash),
+        };
+        H256(keccak256([a.as_bytes(), b.as_bytes()].concat()))
+    })
+}
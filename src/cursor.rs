@@ -0,0 +1,394 @@
+use crate::schema::{Payload, StreamEvent};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::Infallible,
+    error::Error,
+    fs, io,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// Position of the latest processed event of a collection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    /// Timestamp of the latest processed event (see [`StreamEvent::timestamp`]).
+    pub timestamp: DateTime<Utc>,
+    /// Keys of the processed events that happened within the tolerance of `timestamp`, with their timestamps.
+    ///
+    /// Events arrive slightly out of order and several events can share a timestamp, so these are needed to tell
+    /// which of the recent events have already been seen.
+    pub keys: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// Storage for [`Cursor`]s, keyed by collection slug.
+///
+/// The bundled stores are [`MemoryStore`], [`FileStore`], `SledStore` (with the `sled` feature) and `RedisStore`
+/// (with the `redis` feature). Implement this trait to persist cursors somewhere else.
+pub trait CursorStore {
+    /// Error returned by the store.
+    type Error: Error;
+
+    /// Loads the cursor of a collection, if one has been saved.
+    fn load(&mut self, collection: &str) -> Result<Option<Cursor>, Self::Error>;
+
+    /// Saves the cursor of a collection, replacing any previous cursor.
+    fn save(&mut self, collection: &str, cursor: &Cursor) -> Result<(), Self::Error>;
+}
+
+/// A [`CursorStore`] that keeps cursors in memory. Cursors do not survive a restart.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore(HashMap<String, Cursor>);
+
+impl MemoryStore {
+    /// Constructs a new, empty `MemoryStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CursorStore for MemoryStore {
+    type Error = Infallible;
+
+    fn load(&mut self, collection: &str) -> Result<Option<Cursor>, Self::Error> {
+        Ok(self.0.get(collection).cloned())
+    }
+
+    fn save(&mut self, collection: &str, cursor: &Cursor) -> Result<(), Self::Error> {
+        self.0.insert(collection.to_owned(), cursor.clone());
+        Ok(())
+    }
+}
+
+/// A [`CursorStore`] that keeps one JSON file per collection in a directory.
+///
+/// Files are replaced atomically, so a crash never leaves a partially written cursor behind. Files are written
+/// synchronously; [`Resume`] batches saves (see [`Resume::flush_interval`]) so that this does not happen for every
+/// event.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Constructs a new `FileStore` in `dir`, creating the directory if it does not exist.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, collection: &str) -> PathBuf {
+        // `*` (for `Collection::All`) is not a valid file name on every platform.
+        let name = match collection {
+            "*" => "_all",
+            c => c,
+        };
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+impl CursorStore for FileStore {
+    type Error = io::Error;
+
+    fn load(&mut self, collection: &str) -> Result<Option<Cursor>, Self::Error> {
+        match fs::read(self.path(collection)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&mut self, collection: &str, cursor: &Cursor) -> Result<(), Self::Error> {
+        let path = self.path(collection);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(cursor)?)?;
+        fs::rename(tmp, path)
+    }
+}
+
+#[cfg(feature = "sled")]
+mod sled_store {
+    use super::{Cursor, CursorStore};
+    use std::io;
+
+    /// A [`CursorStore`] that keeps cursors in a tree of a [`sled`] database, as JSON.
+    #[derive(Debug, Clone)]
+    pub struct SledStore {
+        tree: sled::Tree,
+    }
+
+    impl SledStore {
+        /// Constructs a new `SledStore` in `tree`. Cursors are flushed to disk with the rest of the database.
+        pub fn new(tree: sled::Tree) -> Self {
+            Self { tree }
+        }
+    }
+
+    impl CursorStore for SledStore {
+        type Error = sled::Error;
+
+        fn load(&mut self, collection: &str) -> Result<Option<Cursor>, Self::Error> {
+            self.tree
+                .get(collection)?
+                .map(|bytes| serde_json::from_slice(&bytes).map_err(|e| io::Error::from(e).into()))
+                .transpose()
+        }
+
+        fn save(&mut self, collection: &str, cursor: &Cursor) -> Result<(), Self::Error> {
+            let bytes = serde_json::to_vec(cursor).map_err(io::Error::from)?;
+            self.tree.insert(collection, bytes)?;
+            Ok(())
+        }
+    }
+}
+#[cfg(feature = "sled")]
+pub use sled_store::SledStore;
+
+#[cfg(feature = "redis")]
+mod redis_store {
+    use super::{Cursor, CursorStore};
+    use redis::{Commands, ErrorKind, RedisError};
+
+    /// A [`CursorStore`] that keeps cursors in [Redis](https://redis.io), as JSON strings under
+    /// `<prefix><collection>`.
+    pub struct RedisStore {
+        connection: redis::Connection,
+        prefix: String,
+    }
+
+    impl RedisStore {
+        /// Constructs a new `RedisStore` on `connection`, with keys prefixed by `opensea-stream:cursor:`.
+        pub fn new(connection: redis::Connection) -> Self {
+            Self {
+                connection,
+                prefix: "opensea-stream:cursor:".to_owned(),
+            }
+        }
+
+        /// Sets the prefix of the keys of cursors.
+        pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+            self.prefix = prefix.into();
+            self
+        }
+    }
+
+    impl std::fmt::Debug for RedisStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("RedisStore")
+                .field("prefix", &self.prefix)
+                .finish_non_exhaustive()
+        }
+    }
+
+    fn invalid(e: serde_json::Error) -> RedisError {
+        (ErrorKind::TypeError, "invalid cursor", e.to_string()).into()
+    }
+
+    impl CursorStore for RedisStore {
+        type Error = RedisError;
+
+        fn load(&mut self, collection: &str) -> Result<Option<Cursor>, Self::Error> {
+            let key = format!("{}{}", self.prefix, collection);
+            let value: Option<String> = self.connection.get(key)?;
+            value
+                .map(|value| serde_json::from_str(&value).map_err(invalid))
+                .transpose()
+        }
+
+        fn save(&mut self, collection: &str, cursor: &Cursor) -> Result<(), Self::Error> {
+            let key = format!("{}{}", self.prefix, collection);
+            let value = serde_json::to_string(cursor).map_err(invalid)?;
+            self.connection.set(key, value)
+        }
+    }
+}
+#[cfg(feature = "redis")]
+pub use redis_store::RedisStore;
+
+/// Events that may have been missed while the consumer was not running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gap {
+    /// Slug of the collection.
+    pub collection: String,
+    /// Timestamp of the last event processed before the restart.
+    pub from: DateTime<Utc>,
+    /// Timestamp of the first new event processed after the restart.
+    pub to: DateTime<Utc>,
+}
+
+/// Outcome of passing an event through [`Resume::process`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The event has not been processed before.
+    New,
+    /// The event is the first new event of its collection after a restart. Events between the two timestamps
+    /// of the [`Gap`] may have been missed.
+    Resumed(Gap),
+    /// The event has already been processed, or is older than the tolerance allows to tell, and should be skipped.
+    Duplicate,
+}
+
+/// Records the latest processed events of every collection, so that a restarted consumer can skip events it has
+/// already processed and learn which time window it may have missed.
+///
+/// An event is a duplicate if an event with the same key was processed. Keys are remembered for the tolerance
+/// (one minute by default) before the latest processed event of the collection, so that events which arrive out of
+/// order by up to the tolerance are still delivered; events older than that are treated as duplicates.
+///
+/// Cursors are saved at most once per flush interval (one second by default), and by [`Resume::flush`]. Events
+/// processed since the last save may be processed again after a crash.
+/// ```no_run
+/// # use opensea_stream::cursor::{FileStore, Resume, Status};
+/// # use opensea_stream::{Client, Collection, Network};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut resume = Resume::new(FileStore::new("cursors")?);
+///
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let mut subscription = client.subscribe(Collection::All).await?;
+///
+/// while let Some(event) = subscription.recv().await {
+///     let event = event?;
+///     match resume.process(&event)? {
+///         Status::Duplicate => continue,
+///         Status::Resumed(gap) => eprintln!("may have missed events: {:?}", gap),
+///         Status::New => {}
+///     }
+///     println!("{:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Resume<S> {
+    store: S,
+    tolerance: Duration,
+    flush_interval: Duration,
+    cursors: HashMap<String, Option<Cursor>>,
+    /// Collections that have a saved cursor but no new event since it was loaded.
+    pending_gaps: HashSet<String>,
+    /// Collections whose cursor changed since it was last saved.
+    dirty: HashSet<String>,
+    last_flush: DateTime<Utc>,
+}
+
+impl<S> Resume<S>
+where
+    S: CursorStore,
+{
+    /// Constructs a new `Resume` backed by `store`.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            tolerance: Duration::from_secs(60),
+            flush_interval: Duration::from_secs(1),
+            cursors: HashMap::new(),
+            pending_gaps: HashSet::new(),
+            dirty: HashSet::new(),
+            last_flush: Utc::now(),
+        }
+    }
+
+    /// Sets how far before the latest processed event of a collection events are still told apart by their key.
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets how often changed cursors are saved. With an interval of zero, cursors are saved on every new event.
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Classifies an event against the saved cursor of its collection, then records the event if it is new.
+    pub fn process(&mut self, event: &StreamEvent) -> Result<Status, S::Error> {
+        let collection = &event.payload.collection().0;
+        let timestamp = event.timestamp();
+        let key = event_key(&event.payload);
+        let tolerance = chrono::Duration::from_std(self.tolerance).unwrap_or(chrono::Duration::MAX);
+
+        if !self.cursors.contains_key(collection) {
+            let cursor = self.store.load(collection)?;
+            if cursor.is_some() {
+                self.pending_gaps.insert(collection.clone());
+            }
+            self.cursors.insert(collection.clone(), cursor);
+        }
+        let cursor = self.cursors.get_mut(collection).unwrap();
+
+        let status = match cursor {
+            Some(c) if c.keys.contains_key(&key) => Status::Duplicate,
+            Some(c) if timestamp < c.timestamp - tolerance => Status::Duplicate,
+            Some(c) if self.pending_gaps.contains(collection) => Status::Resumed(Gap {
+                collection: collection.clone(),
+                from: c.timestamp,
+                to: timestamp,
+            }),
+            _ => Status::New,
+        };
+        if status == Status::Duplicate {
+            return Ok(status);
+        }
+        self.pending_gaps.remove(collection);
+
+        let cursor = cursor.get_or_insert_with(|| Cursor {
+            timestamp,
+            keys: BTreeMap::new(),
+        });
+        cursor.timestamp = cursor.timestamp.max(timestamp);
+        cursor.keys.insert(key, timestamp);
+        let oldest = cursor.timestamp - tolerance;
+        cursor.keys.retain(|_, at| *at >= oldest);
+        self.dirty.insert(collection.clone());
+
+        let now = Utc::now();
+        if (now - self.last_flush).to_std().unwrap_or_default() >= self.flush_interval {
+            self.flush_at(now)?;
+        }
+        Ok(status)
+    }
+
+    /// Saves every cursor that changed since it was last saved.
+    pub fn flush(&mut self) -> Result<(), S::Error> {
+        self.flush_at(Utc::now())
+    }
+
+    fn flush_at(&mut self, now: DateTime<Utc>) -> Result<(), S::Error> {
+        for collection in self.dirty.iter() {
+            if let Some(Some(cursor)) = self.cursors.get(collection) {
+                self.store.save(collection, cursor)?;
+            }
+        }
+        self.dirty.clear();
+        self.last_flush = now;
+        Ok(())
+    }
+
+    /// Saves every changed cursor, then returns the underlying store.
+    pub fn into_inner(mut self) -> Result<S, S::Error> {
+        self.flush()?;
+        Ok(self.store)
+    }
+}
+
+/// Identifies an event among the events of its collection that happened at the same time.
+pub(crate) fn event_key(payload: &Payload) -> String {
+    let id = match payload {
+        Payload::ItemListed(v) => format!("{:?}", v.order_hash),
+        // A sweep sells several items in one transaction.
+        Payload::ItemSold(v) => format!("{:?}/{}", v.transaction.hash, v.context.item.nft_id),
+        Payload::ItemTransferred(v) => {
+            format!("{:?}/{}", v.transaction.hash, v.context.item.nft_id)
+        }
+        Payload::ItemMetadataUpdated(v) => v.context.item.nft_id.to_string(),
+        Payload::ItemCancelled(v) => format!("{:?}", v.order_hash),
+        Payload::ItemReceivedOffer(v) => format!("{:?}", v.order_hash),
+        Payload::ItemReceivedBid(v) => format!("{:?}", v.order_hash),
+        Payload::CollectionOffer(v) => format!("{:?}", v.order_hash),
+        Payload::TraitOffer(v) => format!("{:?}", v.order_hash),
+    };
+    format!("{}:{}", payload.event(), id)
+}
@@ -0,0 +1,144 @@
+//! [permessage-deflate](https://www.rfc-editor.org/rfc/rfc7692) between the bridge and the OpenSea server.
+//!
+//! The websocket client of phyllo does not support compression, so the bridge offers the extension to the server
+//! itself, hides it from the socket, and inflates compressed messages from the server before relaying them (see
+//! [`frame::relay`](crate::frame::relay)). Messages from the socket are small and relayed uncompressed, which the
+//! extension allows.
+
+use crate::frame::{Message, MAX_MESSAGE_LEN};
+use flate2::{Decompress, FlushDecompress};
+use std::io;
+
+/// Adds the permessage-deflate offer to an HTTP request head.
+pub(crate) fn offer(head: &[u8]) -> Vec<u8> {
+    let head = head.strip_suffix(b"\r\n").unwrap_or(head);
+    [
+        head,
+        b"Sec-WebSocket-Extensions: permessage-deflate\r\n\r\n",
+    ]
+    .concat()
+}
+
+/// Removes the extensions header from an HTTP response head, returning the head and whether the server accepted
+/// permessage-deflate.
+pub(crate) fn accept(head: &[u8]) -> (Vec<u8>, bool) {
+    let head = String::from_utf8_lossy(head);
+    let mut accepted = false;
+    let head = head
+        .split("\r\n")
+        .filter(|line| match line.split_once(':') {
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("sec-websocket-extensions") => {
+                accepted |= value.contains("permessage-deflate");
+                false
+            }
+            _ => true,
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    (head.into_bytes(), accepted)
+}
+
+/// Inflates `message` if it is compressed.
+///
+/// The inflater is kept for the whole connection, as the server may refer back to earlier messages.
+pub(crate) fn inflate(inflater: &mut Decompress, message: &mut Message) -> io::Result<()> {
+    if message.compressed {
+        message.payload = inflate_message(inflater, &message.payload)?;
+        message.compressed = false;
+    }
+    Ok(())
+}
+
+/// Inflates the payload of a compressed message.
+fn inflate_message(inflater: &mut Decompress, payload: &[u8]) -> io::Result<Vec<u8>> {
+    // The server removes the end of the final deflate block from every message.
+    let input = [payload, &[0x00, 0x00, 0xff, 0xff]].concat();
+    let mut output = Vec::with_capacity(input.len() * 4);
+    let mut consumed = 0;
+
+    loop {
+        if output.len() == output.capacity() {
+            output.reserve(output.capacity());
+        }
+        let (total_in, len) = (inflater.total_in(), output.len());
+        inflater
+            .decompress_vec(&input[consumed..], &mut output, FlushDecompress::Sync)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        consumed += (inflater.total_in() - total_in) as usize;
+
+        if consumed == input.len() && output.len() < output.capacity() {
+            return Ok(output);
+        }
+        if output.len() > MAX_MESSAGE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message too long",
+            ));
+        }
+        if consumed < input.len() && inflater.total_in() == total_in && output.len() == len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "compressed message is truncated",
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frame::{encode, relay};
+    use flate2::{Compress, Compression, FlushCompress};
+
+    /// Compresses a message as the server does, without the end of the final deflate block.
+    fn deflate(compressor: &mut Compress, message: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(message.len() + 64);
+        compressor
+            .compress_vec(message, &mut output, FlushCompress::Sync)
+            .unwrap();
+        assert!(output.ends_with(&[0x00, 0x00, 0xff, 0xff]));
+        output.truncate(output.len() - 4);
+        output
+    }
+
+    #[test]
+    fn messages_are_inflated_with_the_window_of_earlier_messages() {
+        let mut compressor = Compress::new(Compression::default(), false);
+        let mut inflater = Decompress::new(false);
+        let message = br#"{"event":"item_listed","payload":{"collection":"wandernauts"}}"#;
+        for _ in 0..3 {
+            let payload = deflate(&mut compressor, message);
+            assert_eq!(inflate_message(&mut inflater, &payload).unwrap(), message);
+        }
+        assert!(inflate_message(&mut Decompress::new(false), &[0xff; 8]).is_err());
+    }
+
+    #[tokio::test]
+    async fn fragmented_compressed_messages_are_inflated() {
+        let mut compressor = Compress::new(Compression::default(), false);
+        let message = b"a message long enough to be split into several fragments";
+        let payload = deflate(&mut compressor, message);
+        let (first, second) = payload.split_at(payload.len() / 2);
+        let input = [
+            encode(false, true, 0x1, first),
+            encode(true, false, 0x0, second),
+            encode(true, false, 0x1, b"uncompressed"),
+        ]
+        .concat();
+
+        let mut inflater = Decompress::new(false);
+        let mut output = Vec::new();
+        relay(input.as_slice(), &mut output, |mut message| {
+            inflate(&mut inflater, &mut message)?;
+            Ok(Some(message))
+        })
+        .await
+        .unwrap();
+        let expected = [
+            encode(true, false, 0x1, message),
+            encode(true, false, 0x1, b"uncompressed"),
+        ]
+        .concat();
+        assert_eq!(output, expected);
+    }
+}
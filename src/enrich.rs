@@ -0,0 +1,550 @@
+use crate::{
+    schema::{Chain, NftId, StreamEvent},
+    Client, ClientError, Collection, EventSource, Network, Subscription,
+};
+use ethers_core::types::Address;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    sync::{mpsc, Semaphore},
+    time::{self, Instant},
+};
+use tracing::warn;
+use url::Url;
+
+/// Errors that can be encountered while fetching data from the OpenSea REST API.
+#[derive(Debug, Error)]
+pub enum EnrichError {
+    /// The request could not be sent, or the response could not be decoded.
+    #[error("http request failed")]
+    Http(#[from] reqwest::Error),
+    /// The endpoint URL could not be constructed.
+    #[error("invalid endpoint url")]
+    Url(#[from] url::ParseError),
+    /// The contract does not belong to a collection.
+    #[error("contract {0} does not belong to a collection")]
+    NoCollection(ContractAddress),
+}
+
+/// Errors that can be encountered while subscribing to a contract with [`Enricher::subscribe_to_contract`].
+#[derive(Debug, Error)]
+pub enum SubscribeContractError {
+    /// The collection of the contract could not be resolved.
+    #[error("could not resolve collection of contract")]
+    Resolve(#[from] EnrichError),
+    /// The collection could not be subscribed to.
+    #[error("could not subscribe to collection")]
+    Subscribe(#[from] ClientError),
+}
+
+/// A contract on a particular chain, which identifies a collection without its slug.
+///
+/// Contracts are written as `<chain>/<address>`, such as `ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4`, by
+/// [`Display`](std::fmt::Display), [`FromStr`] and serde.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContractAddress {
+    /// Chain the contract is deployed on.
+    pub chain: Chain,
+    /// Address of the contract.
+    pub address: Address,
+}
+
+impl ContractAddress {
+    /// Constructs a new `ContractAddress`.
+    pub fn new(chain: Chain, address: Address) -> Self {
+        Self { chain, address }
+    }
+}
+
+impl std::fmt::Display for ContractAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{:?}", self.chain, self.address)
+    }
+}
+
+/// Error returned when a string is not a [`ContractAddress`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid contract `{0}`: expected `<chain>/<address>`")]
+pub struct ParseContractAddressError(pub String);
+
+impl FromStr for ContractAddress {
+    type Err = ParseContractAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseContractAddressError(s.to_owned());
+        let (chain, address) = s.split_once('/').ok_or_else(error)?;
+        Ok(Self {
+            chain: chain.parse().map_err(|_| error())?,
+            address: address.parse().map_err(|_| error())?,
+        })
+    }
+}
+
+impl Serialize for ContractAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Statistics of a collection, as reported by the OpenSea REST API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CollectionStats {
+    /// All-time trading volume (in the native token of the chain).
+    #[serde(default)]
+    pub volume: f64,
+    /// All-time number of sales.
+    #[serde(default)]
+    pub sales: f64,
+    /// Average sale price.
+    #[serde(default)]
+    pub average_price: f64,
+    /// Number of unique owners.
+    #[serde(default)]
+    pub num_owners: u64,
+    /// Market cap.
+    #[serde(default)]
+    pub market_cap: f64,
+    /// Current floor price.
+    #[serde(default)]
+    pub floor_price: f64,
+    /// Symbol of the token the floor price is denominated in.
+    #[serde(default)]
+    pub floor_price_symbol: String,
+}
+
+/// Metadata of a token, as reported by the OpenSea REST API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NftMetadata {
+    /// Token ID.
+    pub identifier: String,
+    /// Slug of the collection the token belongs to.
+    pub collection: String,
+    /// Token standard (e.g. `erc721`, `erc1155`).
+    pub token_standard: Option<String>,
+    /// Name.
+    pub name: Option<String>,
+    /// Description.
+    pub description: Option<String>,
+    /// Image URL.
+    pub image_url: Option<String>,
+    /// URL to metadata.
+    pub metadata_url: Option<String>,
+    /// Traits.
+    #[serde(default)]
+    pub traits: Vec<NftTrait>,
+}
+
+/// A trait (attribute) of a token.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NftTrait {
+    /// Type of the trait (e.g. `Background`).
+    pub trait_type: String,
+    /// Value of the trait. This may be a string or number.
+    pub value: serde_json::Value,
+}
+
+/// A [`StreamEvent`] with supplementary data attached.
+///
+/// Supplementary data is `None` if it does not apply to the event or if fetching it failed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnrichedEvent {
+    /// The original event.
+    pub event: StreamEvent,
+    /// Statistics of the collection the event belongs to.
+    pub collection_stats: Option<CollectionStats>,
+    /// Metadata of the item the event belongs to.
+    pub nft: Option<NftMetadata>,
+}
+
+/// Returns the base URL of the REST API corresponding to `network`.
+pub(crate) fn rest_endpoint(network: Network) -> Url {
+    let endpoint = match network {
+        Network::Mainnet => "https://api.opensea.io/api/v2/",
+        Network::Testnet => "https://testnets-api.opensea.io/api/v2/",
+    };
+    Url::parse(endpoint).unwrap()
+}
+
+/// Builder for an [`Enricher`].
+#[derive(Debug, Clone)]
+pub struct EnricherBuilder {
+    endpoint: Url,
+    api_key: String,
+    cache_ttl: Duration,
+    min_interval: Duration,
+    collection_stats: bool,
+    nft: bool,
+    concurrency: usize,
+}
+
+impl EnricherBuilder {
+    /// Constructs a new `EnricherBuilder` for the REST API corresponding to `network`.
+    pub fn new(network: Network, api_key: &str) -> Self {
+        Self {
+            endpoint: rest_endpoint(network),
+            api_key: api_key.to_owned(),
+            cache_ttl: Duration::from_secs(60),
+            min_interval: Duration::from_millis(250),
+            collection_stats: true,
+            nft: true,
+            concurrency: 8,
+        }
+    }
+
+    /// Sets the base URL of the REST API. This should end with a `/`.
+    pub fn endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Sets how long fetched data is reused before it is fetched again.
+    pub fn cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Sets the minimum interval between two requests to the REST API.
+    pub fn min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Sets whether collection statistics are attached to events.
+    pub fn collection_stats(mut self, collection_stats: bool) -> Self {
+        self.collection_stats = collection_stats;
+        self
+    }
+
+    /// Sets whether token metadata is attached to events.
+    pub fn nft(mut self, nft: bool) -> Self {
+        self.nft = nft;
+        self
+    }
+
+    /// Sets the number of events that [`Enricher::spawn`] enriches at the same time. Defaults to 8.
+    ///
+    /// Requests are still paced by the minimum interval, so this mostly lets events whose data is cached overtake
+    /// the fetches of earlier events rather than wait for them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `concurrency` is 0.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        assert!(concurrency > 0, "concurrency must be at least 1");
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Builds the `Enricher`.
+    pub fn build(self) -> Enricher {
+        Enricher {
+            http: reqwest::Client::new(),
+            endpoint: self.endpoint,
+            api_key: self.api_key,
+            collection_stats_enabled: self.collection_stats,
+            nft_enabled: self.nft,
+            collection_stats: Cache::new(self.cache_ttl),
+            nfts: Cache::new(self.cache_ttl),
+            slugs: Mutex::new(HashMap::new()),
+            limiter: RateLimiter::new(self.min_interval),
+            concurrency: self.concurrency,
+        }
+    }
+}
+
+/// Fetches supplementary data for events from the [OpenSea REST API](https://docs.opensea.io/reference/api-overview).
+///
+/// Responses are cached, and requests are paced so that the API key is not throttled.
+#[derive(Debug)]
+pub struct Enricher {
+    http: reqwest::Client,
+    endpoint: Url,
+    api_key: String,
+    collection_stats_enabled: bool,
+    nft_enabled: bool,
+    collection_stats: Cache<String, CollectionStats>,
+    nfts: Cache<(Chain, String), NftMetadata>,
+    slugs: Mutex<HashMap<ContractAddress, String>>,
+    limiter: RateLimiter,
+    concurrency: usize,
+}
+
+impl Enricher {
+    /// Constructs a new [`EnricherBuilder`].
+    pub fn builder(network: Network, api_key: &str) -> EnricherBuilder {
+        EnricherBuilder::new(network, api_key)
+    }
+
+    /// Fetches the statistics of a collection.
+    pub async fn collection_stats(&self, slug: &str) -> Result<CollectionStats, EnrichError> {
+        if let Some(stats) = self.collection_stats.get(&slug.to_owned()) {
+            return Ok(stats);
+        }
+
+        #[derive(Deserialize)]
+        struct Response {
+            total: CollectionStats,
+        }
+
+        let url = self.endpoint.join(&format!("collections/{}/stats", slug))?;
+        let stats = self.fetch::<Response>(url).await?.total;
+        self.collection_stats.insert(slug.to_owned(), stats.clone());
+        Ok(stats)
+    }
+
+    /// Fetches the metadata of a token.
+    pub async fn nft(&self, nft_id: &NftId) -> Result<NftMetadata, EnrichError> {
+        let key = (
+            nft_id.network,
+            format!("{:?}/{}", nft_id.address, nft_id.id),
+        );
+        if let Some(nft) = self.nfts.get(&key) {
+            return Ok(nft);
+        }
+
+        #[derive(Deserialize)]
+        struct Response {
+            nft: NftMetadata,
+        }
+
+        let url = self.endpoint.join(&format!(
+            "chain/{}/contract/{:?}/nfts/{}",
+            nft_id.network, nft_id.address, nft_id.id
+        ))?;
+        let nft = self.fetch::<Response>(url).await?.nft;
+        self.nfts.insert(key, nft.clone());
+        Ok(nft)
+    }
+
+    /// Resolves the collection that a contract belongs to.
+    ///
+    /// Slugs are cached for the lifetime of the `Enricher`, as they do not change once a collection is created.
+    pub async fn collection_of(
+        &self,
+        contract: ContractAddress,
+    ) -> Result<Collection, EnrichError> {
+        if let Some(slug) = self.slugs.lock().unwrap().get(&contract) {
+            return Ok(Collection::Collection(slug.clone()));
+        }
+
+        #[derive(Deserialize)]
+        struct Response {
+            collection: Option<String>,
+        }
+
+        let url = self.endpoint.join(&format!(
+            "chain/{}/contract/{:?}",
+            contract.chain, contract.address
+        ))?;
+        let slug = self
+            .fetch::<Response>(url)
+            .await?
+            .collection
+            .ok_or(EnrichError::NoCollection(contract))?;
+        self.slugs.lock().unwrap().insert(contract, slug.clone());
+        Ok(Collection::Collection(slug))
+    }
+
+    /// Subscribes to all the events of the collection that a contract belongs to, resolving its slug
+    /// with [`Enricher::collection_of`] first.
+    pub async fn subscribe_to_contract(
+        &self,
+        client: &mut Client,
+        contract: ContractAddress,
+    ) -> Result<Subscription, SubscribeContractError> {
+        let collection = self.collection_of(contract).await?;
+        Ok(client.subscribe(collection).await?)
+    }
+
+    /// Attaches supplementary data to an event.
+    ///
+    /// Failures to fetch are not considered fatal, and leave the corresponding field empty.
+    pub async fn enrich(&self, event: StreamEvent) -> EnrichedEvent {
+        let collection_stats = match self.collection_stats_enabled {
+            true => self
+                .collection_stats(&event.payload.collection().0)
+                .await
+                .ok(),
+            false => None,
+        };
+
+        let nft = match (self.nft_enabled, event.payload.context()) {
+            (true, Some(context)) => self.nft(&context.item.nft_id).await.ok(),
+            _ => None,
+        };
+
+        EnrichedEvent {
+            event,
+            collection_stats,
+            nft,
+        }
+    }
+
+    /// Spawns a task that enriches every event received from `events`, returning a receiver for the enriched events.
+    ///
+    /// Up to the [concurrency](EnricherBuilder::concurrency) of the enricher, events are enriched at the same time,
+    /// and they are delivered in the order in which they were received. Since every fetch waits for the minimum
+    /// interval between requests, a source that yields more events of uncached items than that allows falls behind;
+    /// errors of the source, such as missed events, are logged as warnings. The task ends when either the source ends
+    /// or the returned receiver is dropped.
+    pub fn spawn(
+        self: Arc<Self>,
+        mut events: impl EventSource + 'static,
+        buffer: usize,
+    ) -> mpsc::Receiver<EnrichedEvent> {
+        let (tx, rx) = mpsc::channel(buffer);
+        // Enrichments in progress, in the order of their events. Each holds a permit until it is delivered.
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        let (pending_tx, mut pending_rx) = mpsc::channel(self.concurrency);
+
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!(error = %e, "enricher received an error, skipping it");
+                        continue;
+                    }
+                };
+
+                let Ok(permit) = permits.clone().acquire_owned().await else {
+                    break;
+                };
+                let enricher = self.clone();
+                let enriched = tokio::spawn(async move { enricher.enrich(event).await });
+                if pending_tx.send((enriched, permit)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tokio::spawn(async move {
+            while let Some((enriched, _permit)) = pending_rx.recv().await {
+                // Enriching does not panic, so the task can only fail if the runtime is shutting down.
+                let Ok(enriched) = enriched.await else {
+                    break;
+                };
+                if tx.send(enriched).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    async fn fetch<R>(&self, url: Url) -> Result<R, EnrichError>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        self.limiter.wait().await;
+
+        Ok(self
+            .http
+            .get(url)
+            .header("X-API-KEY", &self.api_key)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+/// Time-based cache of responses.
+///
+/// Stale entries are not returned, and are evicted by a sweep on insert at most once per `ttl`, so that the cache does
+/// not grow without bound.
+#[derive(Debug)]
+struct Cache<K, V> {
+    ttl: Duration,
+    state: Mutex<CacheState<K, V>>,
+}
+
+#[derive(Debug)]
+struct CacheState<K, V> {
+    entries: HashMap<K, (Instant, V)>,
+    swept_at: Instant,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Hash + Eq,
+    V: Clone,
+{
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                swept_at: Instant::now(),
+            }),
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        let state = self.state.lock().unwrap();
+        state
+            .entries
+            .get(key)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn insert(&self, key: K, value: V) {
+        let mut state = self.state.lock().unwrap();
+        if state.swept_at.elapsed() >= self.ttl {
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, (fetched_at, _)| fetched_at.elapsed() < ttl);
+            state.swept_at = Instant::now();
+        }
+        state.entries.insert(key, (Instant::now(), value));
+    }
+}
+
+/// Paces requests so that at most one is sent per `min_interval`.
+#[derive(Debug)]
+struct RateLimiter {
+    min_interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.min_interval;
+            slot
+        };
+        time::sleep_until(slot).await;
+    }
+}
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use std::fmt;
+use thiserror::Error;
+
+/// Error yielded by an [`EventStream`](crate::EventStream).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Error {
+    /// Events were dropped from the broadcast channel because the consumer fell behind.
+    #[error("missed {0} events because the consumer fell behind")]
+    MissedEvents(u64),
+    /// An event arrived after a later event of the same collection was delivered.
+    /// See [`Reorder`](crate::ordering::Reorder).
+    #[error("event {sequence} of {collection} was sent at {sent_at}, before the last delivered event at {latest}")]
+    OutOfOrder {
+        /// Slug of the collection of the event.
+        collection: String,
+        /// Sequence number of the event.
+        sequence: u64,
+        /// When the event was sent.
+        sent_at: DateTime<Utc>,
+        /// When the last delivered event of the collection was sent.
+        latest: DateTime<Utc>,
+    },
+    /// The channel of a [`Subscription`](crate::Subscription) was closed, and no more events will be received on
+    /// it. This is the last item of the subscription, unless it rejoins; see
+    /// [`SubscribeConfig::rejoin_on_close`](crate::SubscribeConfig::rejoin_on_close).
+    #[error("channel closed: {reason}")]
+    Closed {
+        /// Why the channel was closed.
+        reason: CloseReason,
+    },
+}
+
+/// Why the channel of a [`Subscription`](crate::Subscription) was closed, reported as [`Error::Closed`].
+///
+/// [`phyllo`] does not pass the close message on, so the reason is inferred from whether the socket is still
+/// running when the channel ends. Errors on the channel (`phx_error`) are not reported: `phyllo` rejoins the channel
+/// without ending it, so they never close a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CloseReason {
+    /// The server closed the channel (`phx_close`), for example because the subscription was revoked.
+    Server,
+    /// The socket of the client was closed, or gave up reconnecting.
+    Socket,
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloseReason::Server => write!(f, "closed by the server"),
+            CloseReason::Socket => write!(f, "socket closed"),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! Websocket frames of the connection between the bridge and the OpenSea server.
+//!
+//! The bridge relays the messages of the server one by one rather than as a byte stream, so that it can inflate,
+//! inspect and drop them before the socket of phyllo parses them. Messages from the socket are relayed as they are,
+//! frame by frame, so that the bridge can see which channels the socket joins.
+
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest message that is read before giving up, the same as the default of the websocket client.
+pub(crate) const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+/// A websocket frame.
+struct Frame {
+    fin: bool,
+    rsv1: bool,
+    opcode: u8,
+    /// Masking key of the frame, which frames sent by a client must have.
+    mask: Option<[u8; 4]>,
+    /// Payload of the frame, unmasked.
+    payload: Vec<u8>,
+}
+
+/// A data message of the server, reassembled from its frames.
+#[derive(Debug)]
+pub(crate) struct Message {
+    /// Opcode of the first frame; `0x1` for text and `0x2` for binary.
+    pub(crate) opcode: u8,
+    /// Whether the message is compressed with permessage-deflate.
+    pub(crate) compressed: bool,
+    pub(crate) payload: Vec<u8>,
+}
+
+/// Relays the frames of the server until it closes the connection, passing every data message through `handle`.
+///
+/// Control frames are relayed as they are, even between the fragments of a message. Data messages are relayed as a
+/// single frame, or not at all if `handle` returns `None`.
+pub(crate) async fn relay<R, W, F>(mut upstream: R, mut local: W, mut handle: F) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(Message) -> io::Result<Option<Message>>,
+{
+    // The message being received, if it is fragmented.
+    let mut fragmented: Option<Message> = None;
+
+    let result = loop {
+        let frame = match read_frame(&mut upstream).await {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        };
+
+        let message = match (frame.opcode, fragmented.take()) {
+            (0x8.., fragmented_message) => {
+                fragmented = fragmented_message;
+                Ok(Some(encode(
+                    frame.fin,
+                    frame.rsv1,
+                    frame.opcode,
+                    &frame.payload,
+                )))
+            }
+            (0x0, Some(mut message)) => {
+                if message.payload.len() + frame.payload.len() > MAX_MESSAGE_LEN {
+                    break Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "message too long",
+                    ));
+                }
+                message.payload.extend_from_slice(&frame.payload);
+                match frame.fin {
+                    true => handle_message(&mut handle, message),
+                    false => {
+                        fragmented = Some(message);
+                        continue;
+                    }
+                }
+            }
+            (0x1 | 0x2, None) => {
+                let message = Message {
+                    opcode: frame.opcode,
+                    compressed: frame.rsv1,
+                    payload: frame.payload,
+                };
+                match frame.fin {
+                    true => handle_message(&mut handle, message),
+                    false => {
+                        fragmented = Some(message);
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                break Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected frame",
+                ))
+            }
+        };
+
+        let bytes = match message {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => continue,
+            Err(e) => break Err(e),
+        };
+        if let Err(e) = local.write_all(&bytes).await {
+            break Err(e);
+        }
+    };
+
+    local.shutdown().await?;
+    result
+}
+
+/// Relays the frames of the socket to the server until the socket closes the connection, passing the payload of every
+/// unfragmented text message to `inspect`. Frames are relayed with the masking key they were sent with.
+pub(crate) async fn forward<R, W, F>(
+    mut local: R,
+    mut upstream: W,
+    mut inspect: F,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&[u8]),
+{
+    while let Some(frame) = read_frame(&mut local).await? {
+        if frame.fin && frame.opcode == 0x1 {
+            inspect(&frame.payload);
+        }
+        let mut bytes = encode(frame.fin, frame.rsv1, frame.opcode, &frame.payload);
+        if let Some(mask) = frame.mask {
+            let start = bytes.len() - frame.payload.len();
+            bytes[1] |= 0x80;
+            for (i, byte) in bytes[start..].iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+            bytes.splice(start..start, mask);
+        }
+        upstream.write_all(&bytes).await?;
+    }
+    upstream.shutdown().await
+}
+
+/// Passes a complete message through `handle`, returning the frame to relay, if any.
+fn handle_message<F>(handle: &mut F, message: Message) -> io::Result<Option<Vec<u8>>>
+where
+    F: FnMut(Message) -> io::Result<Option<Message>>,
+{
+    Ok(handle(message)?.map(|m| encode(true, m.compressed, m.opcode, &m.payload)))
+}
+
+/// Reads a frame, or returns `None` if the stream ends before one starts.
+async fn read_frame<R>(stream: &mut R) -> io::Result<Option<Frame>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut head = [0; 2];
+    match stream.read_exact(&mut head).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = match head[1] & 0x7f {
+        126 => stream.read_u16().await? as u64,
+        127 => stream.read_u64().await?,
+        len => len as u64,
+    };
+    if len > MAX_MESSAGE_LEN as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+    }
+    let mask = match head[1] & 0x80 != 0 {
+        true => Some(stream.read_u32().await?.to_be_bytes()),
+        false => None,
+    };
+
+    let mut payload = vec![0; len as usize];
+    stream.read_exact(&mut payload).await?;
+    if let Some(mask) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok(Some(Frame {
+        fin: head[0] & 0x80 != 0,
+        rsv1: head[0] & 0x40 != 0,
+        opcode: head[0] & 0x0f,
+        mask,
+        payload,
+    }))
+}
+
+/// Encodes an unmasked frame, as sent by a server.
+pub(crate) fn encode(fin: bool, rsv1: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push((fin as u8) << 7 | (rsv1 as u8) << 6 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn relay_all(frames: &[Vec<u8>]) -> io::Result<Vec<u8>> {
+        let input = frames.concat();
+        let mut output = Vec::new();
+        relay(input.as_slice(), &mut output, |message| {
+            // Drop the messages that say so.
+            Ok((message.payload != b"drop").then_some(message))
+        })
+        .await?;
+        Ok(output)
+    }
+
+    #[tokio::test]
+    async fn frames_roundtrip_with_every_length_form() {
+        for len in [0, 125, 126, 0xffff, 0x10000] {
+            let payload: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let bytes = encode(true, false, 0x2, &payload);
+            let header = match len {
+                0..=125 => 2,
+                126..=0xffff => 4,
+                _ => 10,
+            };
+            assert_eq!(bytes.len(), header + len);
+
+            let frame = read_frame(&mut bytes.as_slice()).await.unwrap().unwrap();
+            assert!(frame.fin && !frame.rsv1);
+            assert_eq!(frame.opcode, 0x2);
+            assert_eq!(frame.payload, payload);
+        }
+        assert!(read_frame(&mut [].as_slice()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn masked_frames_are_unmasked() {
+        let mask = [1, 2, 3, 4];
+        let mut bytes = vec![0x81, 0x80 | 5];
+        bytes.extend_from_slice(&mask);
+        bytes.extend(b"hello".iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+
+        let frame = read_frame(&mut bytes.as_slice()).await.unwrap().unwrap();
+        assert_eq!(frame.opcode, 0x1);
+        assert_eq!(frame.payload, b"hello");
+    }
+
+    #[tokio::test]
+    async fn overlong_frames_are_rejected() {
+        let mut bytes = vec![0x82, 127];
+        bytes.extend_from_slice(&(MAX_MESSAGE_LEN as u64 + 1).to_be_bytes());
+        let error = read_frame(&mut bytes.as_slice()).await.err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn forwarded_frames_keep_their_mask() {
+        let mask = [1, 2, 3, 4];
+        let mut masked = vec![0x81, 0x80 | 5];
+        masked.extend_from_slice(&mask);
+        masked.extend(b"hello".iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        let input = [masked.clone(), encode(true, false, 0x2, b"binary")].concat();
+
+        let mut output = Vec::new();
+        let mut inspected = Vec::new();
+        forward(input.as_slice(), &mut output, |payload| {
+            inspected.push(payload.to_vec())
+        })
+        .await
+        .unwrap();
+        assert_eq!(output, input);
+        assert_eq!(inspected, [b"hello".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn fragmented_messages_are_reassembled_around_control_frames() {
+        let output = relay_all(&[
+            encode(false, false, 0x1, b"a fragmented "),
+            encode(true, false, 0x9, b"ping"),
+            encode(true, false, 0x0, b"message"),
+            encode(true, false, 0x1, b"drop"),
+            encode(true, false, 0x2, b"unfragmented"),
+        ])
+        .await
+        .unwrap();
+        let expected = [
+            encode(true, false, 0x9, b"ping"),
+            encode(true, false, 0x1, b"a fragmented message"),
+            encode(true, false, 0x2, b"unfragmented"),
+        ]
+        .concat();
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn overlong_fragmented_messages_are_rejected() {
+        let fragment = vec![0; MAX_MESSAGE_LEN / 2 + 1];
+        let error = relay_all(&[
+            encode(false, true, 0x2, &fragment),
+            encode(false, false, 0x0, &fragment),
+        ])
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
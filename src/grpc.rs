@@ -0,0 +1,305 @@
+// `tonic::Status` is large, but it is the error type that gRPC services must return.
+#![allow(clippy::result_large_err)]
+
+use crate::{
+    schema::{self, Payload, StreamEvent},
+    Error, Event, EventSource,
+};
+use chrono::{DateTime, Utc};
+use proto::open_sea_stream_server::{OpenSeaStream, OpenSeaStreamServer};
+use std::{collections::HashSet, pin::Pin, sync::Arc};
+use tokio::{sync::broadcast, task::JoinHandle};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+use tonic::{Request, Response, Status};
+use tracing::warn;
+
+/// Protobuf messages and service definitions generated from `proto/opensea_stream.proto`.
+#[allow(missing_docs, clippy::all)]
+pub mod proto {
+    tonic::include_proto!("opensea_stream");
+}
+
+/// gRPC service that streams events to its callers.
+///
+/// Events are fed into the service from one or more subscriptions with [`StreamService::forward`], and every call to
+/// `Subscribe` receives the events matching its collection and event type filters. Events are converted to protobuf
+/// once, regardless of the number of callers.
+///
+/// A caller that falls more than the capacity of the service behind is sent [`Status::data_loss`], as is every caller
+/// when a forwarded source yields [`Error::MissedEvents`]. As gRPC ends a server stream with its first error status,
+/// this ends the stream of the caller, which has to call `Subscribe` again to receive further events.
+/// ```no_run
+/// # use opensea_stream::{grpc::StreamService, Client, Collection, Network};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let subscription = client.subscribe(Collection::All).await?;
+///
+/// let service = StreamService::new(1024);
+/// service.forward(subscription);
+///
+/// tonic::transport::Server::builder()
+///     .add_service(service.into_server())
+///     .serve("0.0.0.0:50051".parse()?)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamService {
+    tx: broadcast::Sender<Forwarded>,
+}
+
+/// Item fed into a [`StreamService`] by [`StreamService::forward`].
+#[derive(Debug, Clone)]
+enum Forwarded {
+    Event(Arc<proto::Event>),
+    /// Number of events that a forwarded source missed.
+    Missed(u64),
+}
+
+impl StreamService {
+    /// Constructs a new `StreamService`. `capacity` is the number of events buffered for each caller;
+    /// a caller that falls further behind misses events.
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    /// Spawns a task that feeds every event received from `events` into the service, until it ends.
+    ///
+    /// Missed events are reported to callers; other errors of the source are logged as warnings.
+    pub fn forward(&self, mut events: impl EventSource + 'static) -> JoinHandle<()> {
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                // No callers is not an error.
+                let _ = match event {
+                    Ok(event) => tx.send(Forwarded::Event(Arc::new(proto::Event::from(&event)))),
+                    Err(Error::MissedEvents(n)) => tx.send(Forwarded::Missed(n)),
+                    Err(e) => {
+                        warn!(error = %e, "grpc service received an error, skipping it");
+                        continue;
+                    }
+                };
+            }
+        })
+    }
+
+    /// Wraps the service in a server that can be added to a [`tonic::transport::Server`].
+    pub fn into_server(self) -> OpenSeaStreamServer<Self> {
+        OpenSeaStreamServer::new(self)
+    }
+}
+
+type ProtoEventStream = Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl OpenSeaStream for StreamService {
+    type SubscribeStream = ProtoEventStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<proto::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let request = request.into_inner();
+        let collections: HashSet<String> = request.collections.into_iter().collect();
+        let event_types = request
+            .event_types
+            .iter()
+            .map(|e| {
+                e.parse::<Event>()
+                    .map(|e| e.to_string())
+                    .map_err(|_| Status::invalid_argument(format!("unknown event type `{}`", e)))
+            })
+            .collect::<Result<HashSet<String>, Status>>()?;
+
+        let stream =
+            BroadcastStream::new(self.tx.subscribe()).filter_map(move |event| match event {
+                Ok(Forwarded::Event(event)) => ((collections.is_empty()
+                    || collections.contains(&event.collection))
+                    && (event_types.is_empty() || event_types.contains(&event.event_type)))
+                .then(|| Ok(event.as_ref().clone())),
+                Ok(Forwarded::Missed(n)) | Err(BroadcastStreamRecvError::Lagged(n)) => {
+                    Some(Err(Status::data_loss(format!("missed {} events", n))))
+                }
+            });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+fn timestamp(t: &DateTime<Utc>) -> String {
+    t.to_rfc3339()
+}
+
+impl From<&schema::Account> for proto::Account {
+    fn from(val: &schema::Account) -> Self {
+        Self {
+            address: format!("{:?}", val.address),
+        }
+    }
+}
+
+impl From<&schema::PaymentToken> for proto::PaymentToken {
+    fn from(val: &schema::PaymentToken) -> Self {
+        Self {
+            address: format!("{:?}", val.address),
+            decimals: val.decimals,
+            eth_price: val.eth_price,
+            name: val.name.clone(),
+            symbol: val.symbol.clone(),
+            usd_price: val.usd_price,
+        }
+    }
+}
+
+impl From<&schema::Transaction> for proto::Transaction {
+    fn from(val: &schema::Transaction) -> Self {
+        Self {
+            hash: format!("{:?}", val.hash),
+            timestamp: timestamp(&val.timestamp),
+        }
+    }
+}
+
+impl From<&schema::Context> for proto::Item {
+    fn from(val: &schema::Context) -> Self {
+        let item = &val.item;
+        Self {
+            nft_id: item.nft_id.to_string(),
+            chain: item.chain.to_string(),
+            permalink: item.permalink.to_string(),
+            name: item.metadata.name.clone(),
+            description: item.metadata.description.clone(),
+            image_url: item.metadata.image_url.as_ref().map(|u| u.to_string()),
+            animation_url: item.metadata.animation_url.as_ref().map(|u| u.to_string()),
+            metadata_url: item.metadata.metadata_url.as_ref().map(|u| u.to_string()),
+        }
+    }
+}
+
+impl From<&StreamEvent> for proto::Event {
+    fn from(val: &StreamEvent) -> Self {
+        use proto::event::Payload as P;
+
+        let payload = match &val.payload {
+            Payload::ItemListed(v) => P::ItemListed(proto::ItemListed {
+                item: Some((&v.context).into()),
+                event_timestamp: timestamp(&v.event_timestamp),
+                base_price: v.base_price.to_string(),
+                expiration_date: timestamp(&v.expiration_date),
+                is_private: v.is_private,
+                listing_date: timestamp(&v.listing_date),
+                listing_type: v.listing_type.as_ref().map(|l| l.to_string()),
+                maker: Some((&v.maker).into()),
+                order_hash: format!("{:?}", v.order_hash),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                taker: v.taker.as_ref().map(Into::into),
+            }),
+            Payload::ItemSold(v) => P::ItemSold(proto::ItemSold {
+                item: Some((&v.context).into()),
+                event_timestamp: timestamp(&v.event_timestamp),
+                closing_date: timestamp(&v.closing_date),
+                is_private: v.is_private,
+                listing_type: v.listing_type.as_ref().map(|l| l.to_string()),
+                maker: Some((&v.maker).into()),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                sale_price: v.sale_price.to_string(),
+                taker: Some((&v.taker).into()),
+                transaction: Some((&v.transaction).into()),
+            }),
+            Payload::ItemTransferred(v) => P::ItemTransferred(proto::ItemTransferred {
+                item: Some((&v.context).into()),
+                event_timestamp: timestamp(&v.event_timestamp),
+                transaction: Some((&v.transaction).into()),
+                from_account: Some((&v.from_account).into()),
+                to_account: Some((&v.to_account).into()),
+                quantity: v.quantity,
+            }),
+            Payload::ItemMetadataUpdated(v) => P::ItemMetadataUpdated(proto::ItemMetadataUpdated {
+                item: Some((&v.context).into()),
+                name: v.name.clone(),
+                description: v.description.clone(),
+                image_preview_url: v.image_preview_url.as_ref().map(|u| u.to_string()),
+                animation_url: v.animation_url.as_ref().map(|u| u.to_string()),
+                background_color: v.background_color.clone(),
+                metadata_url: v.metadata_url.as_ref().map(|u| u.to_string()),
+            }),
+            Payload::ItemCancelled(v) => P::ItemCancelled(proto::ItemCancelled {
+                item: Some((&v.context).into()),
+                event_timestamp: timestamp(&v.event_timestamp),
+                listing_type: v.listing_type.as_ref().map(|l| l.to_string()),
+                maker: Some((&v.maker).into()),
+                order_hash: format!("{:?}", v.order_hash),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                transaction: Some((&v.transaction).into()),
+            }),
+            Payload::ItemReceivedOffer(v) => P::ItemReceivedOffer(proto::ItemReceivedOffer {
+                item: Some((&v.context).into()),
+                event_timestamp: timestamp(&v.event_timestamp),
+                base_price: v.base_price.to_string(),
+                created_date: timestamp(&v.created_date),
+                expiration_date: timestamp(&v.expiration_date),
+                maker: Some((&v.maker).into()),
+                order_hash: format!("{:?}", v.order_hash),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                taker: v.taker.as_ref().map(Into::into),
+            }),
+            Payload::ItemReceivedBid(v) => P::ItemReceivedBid(proto::ItemReceivedOffer {
+                item: Some((&v.context).into()),
+                event_timestamp: timestamp(&v.event_timestamp),
+                base_price: v.base_price.to_string(),
+                created_date: timestamp(&v.created_date),
+                expiration_date: timestamp(&v.expiration_date),
+                maker: Some((&v.maker).into()),
+                order_hash: format!("{:?}", v.order_hash),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                taker: v.taker.as_ref().map(Into::into),
+            }),
+            Payload::CollectionOffer(v) => P::CollectionOffer(proto::CollectionOffer {
+                asset_contract_criteria: format!("{:?}", v.asset_contract_criteria),
+                event_timestamp: timestamp(&v.event_timestamp),
+                base_price: v.base_price.to_string(),
+                created_date: timestamp(&v.created_date),
+                expiration_date: timestamp(&v.expiration_date),
+                maker: Some((&v.maker).into()),
+                order_hash: format!("{:?}", v.order_hash),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                taker: v.taker.as_ref().map(Into::into),
+            }),
+            Payload::TraitOffer(v) => P::TraitOffer(proto::TraitOffer {
+                asset_contract_criteria: format!("{:?}", v.asset_contract_criteria),
+                trait_criteria: Some(proto::TraitCriteria {
+                    trait_type: v.trait_criteria.trait_type.clone(),
+                    trait_value: v.trait_criteria.trait_value.clone(),
+                }),
+                event_timestamp: timestamp(&v.event_timestamp),
+                base_price: v.base_price.to_string(),
+                created_date: timestamp(&v.created_date),
+                expiration_date: timestamp(&v.expiration_date),
+                maker: Some((&v.maker).into()),
+                order_hash: format!("{:?}", v.order_hash),
+                payment_token: Some((&v.payment_token).into()),
+                quantity: v.quantity,
+                taker: v.taker.as_ref().map(Into::into),
+            }),
+        };
+
+        Self {
+            event_type: val.payload.event().to_string(),
+            collection: val.payload.collection().0.clone(),
+            sent_at: timestamp(&val.sent_at),
+            payload: Some(payload),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{schema::StreamEvent, Collection, Event, EventStream};
+use phyllo::message::{Message, Payload};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    task, time,
+};
+use tracing::warn;
+
+/// A message of a subscription whose payload was not deserialized, as received by [`Guardrails::stream`].
+pub type RawMessage = Message<Collection, Event, Value, Value>;
+
+/// Why a payload was diverted to the dead-letter channel of [`Guardrails`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DeadLetterReason {
+    /// The payload is larger than the configured maximum, in bytes of compact JSON.
+    #[error("payload is larger than {max} bytes")]
+    TooLarge {
+        /// The configured maximum.
+        max: usize,
+    },
+    /// Deserializing the payload took longer than the configured maximum.
+    #[error("payload took longer than {0:?} to parse")]
+    TooSlow(Duration),
+    /// The payload is not a [`StreamEvent`].
+    #[error("invalid payload: {0}")]
+    Invalid(String),
+}
+
+/// A payload that was not delivered by [`Guardrails`].
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    /// Topic of the message.
+    pub collection: Collection,
+    /// Why the payload was not delivered.
+    pub reason: DeadLetterReason,
+    /// The payload.
+    pub payload: Value,
+}
+
+/// Counts of the payloads handled by [`Guardrails`], returned by [`Guardrails::metrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GuardrailMetrics {
+    /// Payloads that were delivered.
+    pub delivered: u64,
+    /// Payloads diverted because they were too large.
+    pub too_large: u64,
+    /// Payloads diverted because they took too long to parse.
+    pub too_slow: u64,
+    /// Payloads diverted because they are not events.
+    pub invalid: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    delivered: AtomicU64,
+    too_large: AtomicU64,
+    too_slow: AtomicU64,
+    invalid: AtomicU64,
+}
+
+/// Limits on the size and parse time of payloads, so that a pathological payload (such as one with a huge traits
+/// array) does not stall a stream.
+///
+/// Guarded subscriptions receive payloads as JSON values, and deserialize them into [`StreamEvent`]s in place of
+/// [`phyllo`]. Payloads that exceed a limit, or that are not events, are diverted to the dead-letter channel, if
+/// any, instead of being delivered. Parsing is moved to a blocking thread when a parse time limit is set, so a slow
+/// payload holds up the stream for at most that long.
+///
+/// The guardrails are cheap to clone; all clones share the same metrics and dead-letter channel.
+/// ```no_run
+/// # use opensea_stream::{client, guardrails::Guardrails, subscribe_guarded, Collection, Network, SubscribeConfig};
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let (tx, mut dead_letters) = tokio::sync::mpsc::unbounded_channel();
+/// let guardrails = Guardrails::new()
+///     .max_payload_bytes(256 * 1024)
+///     .max_parse_time(Duration::from_millis(50))
+///     .dead_letters(tx);
+///
+/// tokio::spawn(async move {
+///     while let Some(letter) = dead_letters.recv().await {
+///         eprintln!("{} payload diverted: {}", letter.collection, letter.reason);
+///     }
+/// });
+///
+/// let mut client = client(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let (_handler, mut events) =
+///     subscribe_guarded(&mut client, Collection::All, &guardrails, SubscribeConfig::new()).await?;
+/// while let Some(event) = events.recv().await {
+///     println!("{:?} ({:?})", event?, guardrails.metrics());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Guardrails {
+    max_payload_bytes: Option<usize>,
+    max_parse_time: Option<Duration>,
+    dead_letters: Option<mpsc::UnboundedSender<DeadLetter>>,
+    counters: Arc<Counters>,
+}
+
+impl Guardrails {
+    /// Constructs a new `Guardrails` without any limits, which only diverts payloads that are not events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Diverts payloads larger than `max` bytes of compact JSON.
+    ///
+    /// Payloads are measured by serializing them again, as the message they arrived in has already been parsed by
+    /// [`phyllo`]; the limit keeps a large payload from being deserialized into an event, not from being received.
+    pub fn max_payload_bytes(mut self, max: usize) -> Self {
+        self.max_payload_bytes = Some(max);
+        self
+    }
+
+    /// Diverts payloads that take longer than `max` to deserialize.
+    pub fn max_parse_time(mut self, max: Duration) -> Self {
+        self.max_parse_time = Some(max);
+        self
+    }
+
+    /// Sends every diverted payload to `tx`. Without a dead-letter channel, diverted payloads are dropped.
+    pub fn dead_letters(mut self, tx: mpsc::UnboundedSender<DeadLetter>) -> Self {
+        self.dead_letters = Some(tx);
+        self
+    }
+
+    /// Returns the counts of the payloads handled so far.
+    pub fn metrics(&self) -> GuardrailMetrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        GuardrailMetrics {
+            delivered: load(&self.counters.delivered),
+            too_large: load(&self.counters.too_large),
+            too_slow: load(&self.counters.too_slow),
+            invalid: load(&self.counters.invalid),
+        }
+    }
+
+    /// Spawns a task that parses the payloads of `receiver` within the limits, returning the stream they are
+    /// delivered on, with a buffer of 128 messages.
+    ///
+    /// Must be called from within a Tokio runtime. See [`subscribe_guarded`](crate::subscribe_guarded) to subscribe
+    /// to a collection with guardrails.
+    pub fn stream(&self, receiver: broadcast::Receiver<RawMessage>) -> EventStream {
+        EventStream::new(self.spawn(receiver, 128))
+    }
+
+    pub(crate) fn spawn(
+        &self,
+        mut receiver: broadcast::Receiver<RawMessage>,
+        buffer: usize,
+    ) -> broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>> {
+        let (tx, rx) = broadcast::channel(buffer);
+        let guardrails = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let message = match receiver.recv().await {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!(missed = n, "guardrails fell behind, skipping payloads");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let Some(Payload::Custom(payload)) = message.payload else {
+                    continue;
+                };
+                let Some(event) = guardrails.parse(&message.topic, payload).await else {
+                    continue;
+                };
+
+                let message = Message {
+                    join_ref: message.join_ref,
+                    reference: message.reference,
+                    topic: message.topic,
+                    event: message.event,
+                    payload: Some(Payload::Custom(event)),
+                };
+                // The stream was dropped.
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Deserializes a payload, or diverts it if it exceeds a limit or is not an event.
+    async fn parse(&self, collection: &Collection, payload: Value) -> Option<StreamEvent> {
+        if let Some(max) = self.max_payload_bytes {
+            if serde_json::to_writer(Limit(max), &payload).is_err() {
+                self.divert(
+                    &self.counters.too_large,
+                    collection,
+                    DeadLetterReason::TooLarge { max },
+                    payload,
+                );
+                return None;
+            }
+        }
+
+        let (payload, result) = match self.max_parse_time {
+            None => {
+                let result = StreamEvent::deserialize(&payload);
+                (payload, result)
+            }
+            Some(max) => {
+                let mut parse = task::spawn_blocking(move || {
+                    let result = StreamEvent::deserialize(&payload);
+                    (payload, result)
+                });
+                match time::timeout(max, &mut parse).await {
+                    Ok(Ok(parsed)) => parsed,
+                    // Parsing panicked, which serde_json does not do.
+                    Ok(Err(e)) => {
+                        warn!(error = %e, "parsing a payload failed");
+                        return None;
+                    }
+                    Err(_) => {
+                        // Divert the payload once parsing is done, without holding up the stream.
+                        let (guardrails, collection) = (self.clone(), collection.clone());
+                        tokio::spawn(async move {
+                            if let Ok((payload, _)) = parse.await {
+                                guardrails.divert(
+                                    &guardrails.counters.too_slow,
+                                    &collection,
+                                    DeadLetterReason::TooSlow(max),
+                                    payload,
+                                );
+                            }
+                        });
+                        return None;
+                    }
+                }
+            }
+        };
+
+        match result {
+            Ok(event) => {
+                self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                Some(event)
+            }
+            Err(e) => {
+                let reason = DeadLetterReason::Invalid(e.to_string());
+                self.divert(&self.counters.invalid, collection, reason, payload);
+                None
+            }
+        }
+    }
+
+    fn divert(
+        &self,
+        counter: &AtomicU64,
+        collection: &Collection,
+        reason: DeadLetterReason,
+        payload: Value,
+    ) {
+        counter.fetch_add(1, Ordering::Relaxed);
+        warn!(%collection, %reason, "payload diverted");
+        if let Some(tx) = &self.dead_letters {
+            let _ = tx.send(DeadLetter {
+                collection: collection.clone(),
+                reason,
+                payload,
+            });
+        }
+    }
+}
+
+/// Writer that discards bytes, failing once more than its limit was written.
+struct Limit(usize);
+
+impl Write for Limit {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 = self
+            .0
+            .checked_sub(buf.len())
+            .ok_or_else(|| io::Error::other("limit exceeded"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -6,42 +6,35 @@
 //!
 //! Events that happen on Solana (and thus carry Solana addresses) are not supported for now.
 //!
+//! With the `rustls-config` feature, [`Client::subscribe`] reports joins rejected by the server (for example, because
+//! of a misconfigured slug) as [`ClientError::Rejected`]. Without it, [`phyllo`] retries them with backoff without
+//! exposing the reply, so such a subscription receives no events. The `wasm` client reports them as
+//! `wasm::Error::Rejected`.
+//!
 //! # Example
 //! The following example prints all listings of items in the `wandernauts` collection as they are created.
 //! ```no_run
-//! # use opensea_stream::{client, schema, subscribe_to, Collection, Network};
-//! # use phyllo::message::Payload;
+//! # use opensea_stream::{schema, Client, Collection, Network};
 //! #[tokio::main]
 //! async fn main() -> anyhow::Result<()> {
-//!     let mut client = client(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+//!     let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
 //!
 //!     // Subscribe to a collection. Note that you must all subscribe to all events
 //!     // in the collection; filtering is your responsibility (see below).
-//!     let (handler, mut subscription) = subscribe_to(
-//!         &mut client,
-//!         Collection::Collection("wandernauts".to_string()),
-//!     )
-//!     .await?;
+//!     let mut subscription = client
+//!         .subscribe(Collection::Collection("wandernauts".to_string()))
+//!         .await?;
 //!
 //!     // To unsubscribe:
-//!     // handler.close().await?;
-//!
-//!     loop {
-//!         // The message received from the channel is a raw message of the Phoenix protocol.
-//!         // It may or may not contain a payload.
-//!         let event = match subscription.recv().await?.into_custom_payload() {
-//!             Some(v) => v,
-//!             None => {
-//!                 eprintln!("unexpected message");
-//!                 continue;
-//!             }
-//!         };
+//!     // subscription.unsubscribe().await?;
 //!
+//!     while let Some(event) = subscription.recv().await {
 //!         // Only print item listing events.
-//!         if let schema::Payload::ItemListed(listing) = event.payload {
+//!         if let schema::Payload::ItemListed(listing) = event?.payload {
 //!             println!("{:?}", listing);
 //!         }
 //!     }
+//!     Ok(())
 //! }
 //! ```
 //! # Features
@@ -51,99 +44,189 @@
 //! ```toml
 //! opensea-stream = { version = "0.1", default-features = false, features = ["rustls-tls-webpki-roots"] }
 //! ```
+//!
+//! To use the platform's TLS implementation (SChannel, Secure Transport or OpenSSL) through
+//! [`native-tls`](https://crates.io/crates/native-tls) instead, enable `native-tls` in place of the `rustls` features:
+//! ```toml
+//! opensea-stream = { version = "0.1", default-features = false, features = ["native-tls"] }
+//! ```
+//! `native-tls` cannot be enabled together with `rustls-tls-native-roots` or `rustls-tls-webpki-roots`.
+//!
+//! `wasm` enables the `wasm` module on `wasm32-unknown-unknown`, which receives events in the browser over its
+//! websocket. On that target, the `phyllo`-based client, `router` and `sinks` are unavailable, while `schema` and
+//! `cursor` (with a custom [`CursorStore`](cursor::CursorStore)) can be used as usual:
+//! ```toml
+//! opensea-stream = { version = "0.1", default-features = false, features = ["wasm"] }
+//! ```
+//!
+//! `unknown-fields` keeps the fields of payloads that are not part of the schema (such as fields added by OpenSea
+//! later) in an `extra` map on every payload struct, instead of dropping them; see `schema::Payload::extra`.
+//!
+//! `schemars` derives [`JsonSchema`](https://docs.rs/schemars) for the types of the `schema` module, and adds
+//! `schema::json_schema`, which returns the JSON schema of events for generating validators or clients in other languages.
+//!
+//! `ethers` enables the `seaport` module, which deserializes the Seaport order in the `protocol_data` of listings and
+//! offers (see `schema::Payload::protocol_data`) and encodes it as a `fulfillOrder` call, and conversions from schema
+//! types such as `schema::Account` to the [`ethers-core`](https://crates.io/crates/ethers-core) types of `ethers::types`
+//! (re-exported as `opensea_stream::ethers_core`), so that bots can go from an event to a transaction.
+//!
+//! `http` enables the `enrich` module, which attaches data from the OpenSea REST API (collection stats, token metadata) to events
+//! and subscribes to collections by contract address, and the `backfill` module, which fetches recent events of a collection
+//! from the REST API to deliver them ahead of live events, or to cold start several collections as one deduplicated,
+//! ordered stream. It also adds `Collection::ContractAddress`, which clients resolve to the collection of the contract
+//! when subscribing, with the enricher set by `ClientBuilder::enricher`.
+//!
+//! `notify` enables the `sinks::notify` module, which posts selected events (such as sales above a price) to Discord or
+//! Slack webhooks, formatted with a template. Rate limited and failed posts are retried.
+//!
+//! `mqtt` enables the `sinks::mqtt` module, which publishes events as JSON to an MQTT broker (through
+//! [`rumqttc`](https://crates.io/crates/rumqttc), re-exported as `opensea_stream::rumqttc`) on topics formatted with
+//! a template such as `opensea/{collection}/{event_type}`, for dashboards and other MQTT consumers.
+//!
+//! `config` enables the `config` module and `Client::from_env` / `Client::from_config`, which configure the client,
+//! its subscriptions (collections and event types) and sinks from environment variables (such as `OPENSEA_API_KEY`) or a
+//! TOML or JSON config file, so that deployments can change the stream without code changes.
+//!
+//! `sled` and `redis` enable `cursor::SledStore` and `cursor::RedisStore`, which keep the cursors of `cursor::Resume` in
+//! a [`sled`](https://crates.io/crates/sled) database or in [Redis](https://redis.io) instead of files.
+//!
+//! `proxy` enables `ClientBuilder::proxy`, which tunnels the websocket connection through an HTTP (`CONNECT`) or
+//! SOCKS5 proxy, optionally with a username and password.
+//!
+//! `rustls-config` enables `ClientBuilder::tls_config`, which takes a preconfigured `rustls::ClientConfig`
+//! (re-exported as `opensea_stream::rustls`) for certificate pinning, custom root certificates or client certificates,
+//! and `ClientBuilder::max_message_bytes`, which drops oversized messages before they are parsed. The socket connects
+//! through a local bridge, which also lets `Client::subscribe` report rejected joins.
+//!
+//! `key-rotation` enables `ClientBuilder::tokens`, which rotates between several API keys (round-robin or failover)
+//! when the socket reconnects, reporting keys rejected by the server.
+//!
+//! `compression` enables `ClientBuilder::compression`, which negotiates
+//! [permessage-deflate](https://www.rfc-editor.org/rfc/rfc7692) with the server to reduce the bandwidth of busy
+//! subscriptions (such as `Collection::All`) at the cost of the CPU time spent inflating messages.
+//!
+//! `unstable-phyllo` re-exports [`phyllo`](https://crates.io/crates/phyllo) as `opensea_stream::phyllo`, and enables the
+//! API that works on its socket and channel handles: `client`, `subscribe_to`, `subscribe_stream`, `subscribe_guarded`,
+//! `subscribe_many`, `unsubscribe`, `Subscriptions`, the `guardrails` module, `ClientBuilder::build`, `EventStream::new`,
+//! `EventStream::into_inner`, `Client::from_socket`, `Client::socket` and `Subscription::into_parts`. These are exempt
+//! from semantic versioning: they may change whenever `phyllo` is upgraded.
+//!
+//! `grpc` enables the `grpc` module, which serves events over gRPC (see `proto/opensea_stream.proto`) with per-call
+//! collection and event type filters.
+//!
+//! `cli` builds the `opensea-stream` binary, which prints events to standard output:
+//! ```sh
+//! cargo install opensea-stream --features cli
+//! OPENSEA_API_KEY=... opensea-stream listen --collection wandernauts --events listed,sold --format json
+//! ```
+//!
+//! `tui` also adds the `monitor` command, a terminal dashboard (built with [`ratatui`](https://crates.io/crates/ratatui))
+//! of live event counts, latest sales and floor prices per collection, which is a quick way to check that an API key
+//! and subscriptions work. Floors are the cheapest open listings seen since the monitor started:
+//! ```sh
+//! cargo install opensea-stream --features tui
+//! OPENSEA_API_KEY=... opensea-stream monitor --collection wandernauts --collection boredapeyachtclub
+//! ```
 
-use phyllo::{
-    channel::{ChannelBuilder, ChannelHandler},
-    error::RegisterChannelError,
-    message::Message,
-    socket::{SocketBuilder, SocketHandler},
-};
-use schema::StreamEvent;
-use serde_json::Value;
-use tokio::sync::broadcast;
-use url::Url;
-
+#[cfg(all(
+    feature = "native-tls",
+    any(
+        feature = "rustls-tls-native-roots",
+        feature = "rustls-tls-webpki-roots"
+    )
+))]
+compile_error!("`native-tls` cannot be enabled together with the `rustls` features; disable default features to use `native-tls`");
+
+#[cfg(feature = "ethers")]
+pub use ethers_core;
+#[cfg(all(feature = "unstable-phyllo", not(target_arch = "wasm32")))]
 pub use phyllo;
-
+#[cfg(feature = "mqtt")]
+pub use rumqttc;
+#[cfg(feature = "rustls-config")]
+pub use rustls;
+
+/// Detection of unusual listing rates and sale prices.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod anomaly;
+/// Recent events from the OpenSea REST API, delivered ahead of live events.
+#[cfg(feature = "http")]
+pub mod backfill;
+/// Events that borrow their strings from the JSON they were deserialized from.
+pub mod borrowed;
+#[cfg(feature = "rustls-config")]
+mod bridge;
+#[cfg(not(target_arch = "wasm32"))]
+mod client;
+/// Coalescing of bursts of events about the same item.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod coalesce;
+/// Configuring clients from the environment or a config file.
+#[cfg(feature = "config")]
+pub mod config;
+/// Matching items against the criteria of collection and trait offers.
+pub mod criteria;
+/// Persisting the position of processed events, to resume after a restart.
+pub mod cursor;
+#[cfg(feature = "compression")]
+mod deflate;
+/// Supplementary data for events from the OpenSea REST API.
+#[cfg(feature = "http")]
+pub mod enrich;
+mod error;
+#[cfg(feature = "rustls-config")]
+mod frame;
+/// gRPC server streaming events to other services.
+#[cfg(feature = "grpc")]
+pub mod grpc;
+/// Limits on the size and parse time of payloads.
+#[cfg(all(feature = "unstable-phyllo", not(target_arch = "wasm32")))]
+pub mod guardrails;
+/// Composable stages that events pass through before delivery.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod middleware;
+/// Tracking of open listings and offers.
+pub mod orderbook;
+/// Sequence numbers and per-collection ordering of events.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ordering;
 mod protocol;
+/// Limiting the rate at which events are delivered.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ratelimit;
+/// Dispatching of events to consumers by collection.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod router;
 /// Payload schema for messages received from the websocket.
 pub mod schema;
-
+/// Seaport orders of listings and offers, as ABI-encodable call structs.
+#[cfg(feature = "ethers")]
+pub mod seaport;
+/// Subscriptions spread over several sockets.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shard;
+/// Replaying recorded events.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod simulator;
+/// Destinations that events can be written to.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sinks;
+/// Statistics about received events.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stats;
+#[cfg(not(target_arch = "wasm32"))]
+mod stream;
+#[cfg(not(target_arch = "wasm32"))]
+mod subscribe;
+/// Client for the browser.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::*;
+pub use error::*;
 pub use protocol::*;
-
-/// Creates a client.
-pub async fn client(network: Network, token: &str) -> SocketHandler<Collection> {
-    let mut network: Url = Url::from(network);
-    network.query_pairs_mut().append_pair("token", token);
-    SocketBuilder::new(network).build().await
-}
-
-/// Subscribes to all the events of a particular [`Collection`].
-pub async fn subscribe_to(
-    socket: &mut SocketHandler<Collection>,
-    collection: Collection,
-) -> Result<
-    (
-        ChannelHandler<Collection, Event, Value, StreamEvent>,
-        broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>>,
-    ),
-    RegisterChannelError,
-> {
-    socket.channel(ChannelBuilder::new(collection)).await
-}
-
-/// Subscribes to all the events of a particular [`Collection`] using
-/// a custom configuration.
-pub async fn subscribe_to_with_config(
-    socket: &mut SocketHandler<Collection>,
-    channel_builder: ChannelBuilder<Collection>,
-) -> Result<
-    (
-        ChannelHandler<Collection, Event, Value, StreamEvent>,
-        broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>>,
-    ),
-    RegisterChannelError,
-> {
-    socket.channel(channel_builder).await
-}
-
-/* use opensea_stream_2::client;
-use opensea_stream_2::schema::Payload;
-use opensea_stream_2::subscribe_to;
-use opensea_stream_2::Collection;
-use opensea_stream_2::Network;
-use tokio_stream::{wrappers::BroadcastStream, StreamExt};
-
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let mut client = client(Network::Mainnet, "91ebfacb37984d9b926f59a045fb9a9c").await;
-
-    let (_, subscription) = subscribe_to(&mut client, Collection::All).await?;
-
-    let stream = BroadcastStream::new(subscription);
-
-    let stream = stream.filter_map(|event| {
-        let event = event.ok()?.into_custom_payload()?;
-        match event.payload {
-            Payload::CollectionOffer(event) => {
-                println!("got offer");
-                Some(Payload::CollectionOffer(event))
-            }
-            Payload::TraitOffer(event) => {
-                println!("got offer2");
-                Some(Payload::TraitOffer(event))
-            },
-            // Payload::ItemMetadataUpdated(event) => {Some(Payload::ItemMetadataUpdated(event))},
-            _ => None,
-        }
-    });
-
-    let mut stream = Box::pin(stream);
-
-    while let Some(event) = stream.next().await {
-        println!("{:?}", event);
-    }
-
-    Ok(())
-}
- */
+#[cfg(not(target_arch = "wasm32"))]
+pub use stream::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use subscribe::*;
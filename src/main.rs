@@ -0,0 +1,159 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use opensea_stream::{schema::StreamEvent, Client, Collection, Error, Event, Network};
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+#[cfg(feature = "tui")]
+mod monitor;
+
+/// Command line client for the OpenSea Stream API.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// OpenSea API key.
+    #[arg(long, env = "OPENSEA_API_KEY", hide_env_values = true)]
+    api_key: String,
+
+    /// Network to connect to.
+    #[arg(long, value_enum, default_value_t = NetworkArg::Mainnet)]
+    network: NetworkArg,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print events as they are received.
+    Listen {
+        /// Slug of a collection to subscribe to. May be repeated; all collections are subscribed to if omitted.
+        #[arg(long = "collection", short)]
+        collections: Vec<String>,
+
+        /// Comma-separated list of event types to print (e.g. `listed,sold`). All events are printed if omitted.
+        #[arg(long, short, value_delimiter = ',', value_parser = parse_event)]
+        events: Vec<Event>,
+
+        /// Output format.
+        #[arg(long, short, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Show a live dashboard of event counts, latest sales and floor prices per collection.
+    #[cfg(feature = "tui")]
+    Monitor {
+        /// Slug of a collection to subscribe to. May be repeated; all collections are subscribed to if omitted.
+        #[arg(long = "collection", short)]
+        collections: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum NetworkArg {
+    Mainnet,
+    Testnet,
+}
+
+impl From<NetworkArg> for Network {
+    fn from(val: NetworkArg) -> Self {
+        match val {
+            NetworkArg::Mainnet => Network::Mainnet,
+            NetworkArg::Testnet => Network::Testnet,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// One JSON object per line.
+    Json,
+    /// Indented JSON.
+    Pretty,
+    /// Rust debug representation.
+    Debug,
+    /// One human-readable line, e.g. `wandernauts #123 listed for 0.5 WETH by 0x6e3e…4fb4`.
+    Summary,
+}
+
+/// Parses an event type, accepting the `item_` prefix to be omitted (e.g. `listed` for `item_listed`).
+fn parse_event(s: &str) -> Result<Event, String> {
+    Event::from_str(s)
+        .or_else(|_| Event::from_str(&format!("item_{}", s)))
+        .map_err(|_| format!("unknown event type `{}`", s))
+}
+
+fn print(event: &StreamEvent, format: Format) -> serde_json::Result<()> {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string(event)?),
+        Format::Pretty => println!("{}", serde_json::to_string_pretty(event)?),
+        Format::Debug => println!("{:?}", event),
+        Format::Summary => println!("{}", event.payload),
+    }
+    Ok(())
+}
+
+/// Subscribes to `collections` (or all collections if empty), merging their events into a single channel.
+async fn subscribe(
+    cli: &Cli,
+    collections: Vec<String>,
+) -> anyhow::Result<(Client, mpsc::UnboundedReceiver<StreamEvent>)> {
+    let mut client = Client::connect(cli.network.into(), &cli.api_key).await;
+
+    let collections = match collections.is_empty() {
+        true => vec![Collection::All],
+        false => collections
+            .into_iter()
+            .map(Collection::Collection)
+            .collect(),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    for (collection, result) in client.subscribe_many(collections).await {
+        let mut subscription =
+            result.map_err(|e| anyhow::anyhow!("could not subscribe to {}: {}", collection, e))?;
+
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(event) = subscription.recv().await {
+                match event {
+                    Ok(event) => {
+                        if tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Err(Error::MissedEvents(n)) => eprintln!("missed {} events", n),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+        });
+    }
+
+    Ok((client, rx))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Command::Listen {
+            collections,
+            events,
+            format,
+        } => {
+            let (_client, mut rx) = subscribe(&cli, collections.clone()).await?;
+            while let Some(event) = rx.recv().await {
+                if events.is_empty() || events.contains(&event.payload.event()) {
+                    print(&event, *format)?;
+                }
+            }
+        }
+        #[cfg(feature = "tui")]
+        Command::Monitor { collections } => {
+            let network = cli.network.into();
+            let (_client, rx) = subscribe(&cli, collections.clone()).await?;
+            monitor::run(network, rx).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use crate::{schema::StreamEvent, stats::Stats};
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A stage that events pass through before they are delivered by an [`EventStream`](crate::EventStream).
+///
+/// A middleware receives each event together with the rest of the [`Pipeline`]. It can pass the event on
+/// (possibly modified) with [`Next::run`], or drop it by returning `None` without calling `next`.
+/// ```
+/// # use opensea_stream::{middleware::{EventMiddleware, Next}, schema::StreamEvent, Event};
+/// # use chrono::{DateTime, Utc};
+/// # use std::{collections::HashSet, sync::Mutex};
+/// /// Drops events that were already seen, identified by their type and the time they were sent.
+/// #[derive(Default)]
+/// struct Dedup(Mutex<HashSet<(Event, DateTime<Utc>)>>);
+///
+/// impl EventMiddleware for Dedup {
+///     async fn handle(&self, event: StreamEvent, next: Next<'_>) -> Option<StreamEvent> {
+///         if !self.0.lock().unwrap().insert((event.payload.event(), event.sent_at)) {
+///             return None;
+///         }
+///         next.run(event).await
+///     }
+/// }
+/// ```
+pub trait EventMiddleware: Send + Sync + 'static {
+    /// Handles an event, returning the event to deliver or `None` to drop it.
+    fn handle(
+        &self,
+        event: StreamEvent,
+        next: Next<'_>,
+    ) -> impl Future<Output = Option<StreamEvent>> + Send;
+}
+
+/// Object-safe version of [`EventMiddleware`], so that stages of different types can be stored together.
+trait DynMiddleware: Send + Sync {
+    fn handle<'a>(
+        &'a self,
+        event: StreamEvent,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Option<StreamEvent>>;
+}
+
+impl<M: EventMiddleware> DynMiddleware for M {
+    fn handle<'a>(
+        &'a self,
+        event: StreamEvent,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Option<StreamEvent>> {
+        Box::pin(EventMiddleware::handle(self, event, next))
+    }
+}
+
+/// The remaining stages of a [`Pipeline`], passed to [`EventMiddleware::handle`].
+pub struct Next<'a> {
+    stages: &'a [Arc<dyn DynMiddleware>],
+}
+
+impl<'a> Next<'a> {
+    /// Runs the remaining stages on `event`. If there are none, the event is returned as is.
+    pub fn run(self, event: StreamEvent) -> impl Future<Output = Option<StreamEvent>> + Send + 'a {
+        let stages = self.stages;
+        async move {
+            match stages.split_first() {
+                Some((stage, stages)) => stage.handle(event, Next { stages }).await,
+                None => Some(event),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+/// Stages that events are run through in order.
+///
+/// The pipeline is cheap to clone; all clones share the same stages.
+/// ```no_run
+/// # use opensea_stream::{middleware::{self, Pipeline}, stats::Stats, Client, Collection, Event, Network, SubscribeConfig};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let stats = Stats::new();
+/// let pipeline = Pipeline::new()
+///     .layer(stats.clone())
+///     .layer(middleware::filter(|event| event.payload.event() == Event::ItemListed));
+///
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let config = SubscribeConfig::new().middleware(pipeline);
+/// let mut events = client.subscribe_with_config(Collection::All, config).await?;
+///
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Arc<dyn DynMiddleware>>,
+}
+
+impl Pipeline {
+    /// Constructs a new `Pipeline` without any stages, which passes every event on.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage, which runs after the stages added before it.
+    pub fn layer(mut self, middleware: impl EventMiddleware) -> Self {
+        self.stages.push(Arc::new(middleware));
+        self
+    }
+
+    /// Returns `true` if the pipeline has no stages.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Runs `event` through every stage, returning the event to deliver or `None` if it was dropped.
+    pub async fn handle(&self, event: StreamEvent) -> Option<StreamEvent> {
+        Next {
+            stages: &self.stages,
+        }
+        .run(event)
+        .await
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+/// Middleware that only passes on events for which a predicate returns `true`. Returned by [`filter`].
+pub struct Filter<F>(F);
+
+impl<F> fmt::Debug for Filter<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Filter").finish()
+    }
+}
+
+/// Constructs a middleware that only passes on events for which `predicate` returns `true`.
+pub fn filter<F>(predicate: F) -> Filter<F>
+where
+    F: Fn(&StreamEvent) -> bool + Send + Sync + 'static,
+{
+    Filter(predicate)
+}
+
+impl<F> EventMiddleware for Filter<F>
+where
+    F: Fn(&StreamEvent) -> bool + Send + Sync + 'static,
+{
+    async fn handle(&self, event: StreamEvent, next: Next<'_>) -> Option<StreamEvent> {
+        if (self.0)(&event) {
+            next.run(event).await
+        } else {
+            None
+        }
+    }
+}
+
+/// Records every event that reaches this stage.
+impl EventMiddleware for Stats {
+    async fn handle(&self, event: StreamEvent, next: Next<'_>) -> Option<StreamEvent> {
+        self.record(&event);
+        next.run(event).await
+    }
+}
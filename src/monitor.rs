@@ -0,0 +1,260 @@
+//! Terminal dashboard of the `monitor` command.
+
+use chrono::{DateTime, Utc};
+use opensea_stream::{
+    orderbook::OrderBook,
+    schema::{Payload, StreamEvent},
+    Event, Network,
+};
+use ratatui::{
+    crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListItem, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, time};
+
+/// Number of sales kept for the list of latest sales.
+const SALES: usize = 50;
+
+/// Counts of the events of a collection.
+#[derive(Debug, Default)]
+struct Counts {
+    listed: u64,
+    sold: u64,
+    offers: u64,
+    transferred: u64,
+    cancelled: u64,
+    total: u64,
+}
+
+#[derive(Debug)]
+struct Sale {
+    at: DateTime<Utc>,
+    collection: String,
+    summary: String,
+}
+
+#[derive(Debug)]
+struct Monitor {
+    network: Network,
+    started: Instant,
+    connected: bool,
+    total: u64,
+    collections: HashMap<String, Counts>,
+    book: OrderBook,
+    sales: VecDeque<Sale>,
+}
+
+impl Monitor {
+    fn new(network: Network) -> Self {
+        Self {
+            network,
+            started: Instant::now(),
+            connected: true,
+            total: 0,
+            collections: HashMap::new(),
+            book: OrderBook::new(),
+            sales: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, event: StreamEvent) {
+        let collection = event.payload.collection().0.clone();
+        let counts = self.collections.entry(collection.clone()).or_default();
+        match event.payload.event() {
+            Event::ItemListed => counts.listed += 1,
+            Event::ItemSold => counts.sold += 1,
+            Event::ItemReceivedOffer
+            | Event::ItemReceivedBid
+            | Event::CollectionOffer
+            | Event::TraitOffer => counts.offers += 1,
+            Event::ItemTransferred => counts.transferred += 1,
+            Event::ItemCancelled => counts.cancelled += 1,
+            Event::ItemMetadataUpdated => {}
+        }
+        counts.total += 1;
+        self.total += 1;
+
+        self.book.apply(&event);
+        if let Payload::ItemSold(_) = &event.payload {
+            self.sales.push_front(Sale {
+                at: event.timestamp(),
+                collection,
+                summary: event.payload.to_string(),
+            });
+            self.sales.truncate(SALES);
+        }
+    }
+
+    /// Returns the cheapest open listing of a collection seen so far, formatted in its payment token.
+    fn floor(&self, slug: &str) -> String {
+        self.book
+            .snapshot(slug)
+            .listings
+            .first()
+            .map(|order| order.payment_token.format_amount(order.unit_price()))
+            .unwrap_or_else(|| "-".to_owned())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, collections, sales] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(12),
+        ])
+        .areas(frame.area());
+
+        let status = match self.connected {
+            true => "connected".green(),
+            false => "disconnected".red(),
+        };
+        let header_line = Line::from(vec![
+            format!("{:?} ", self.network).into(),
+            status,
+            format!(
+                " | {} events | {} collections | up {}s | q to quit",
+                self.total,
+                self.collections.len(),
+                self.started.elapsed().as_secs()
+            )
+            .into(),
+        ]);
+        frame.render_widget(
+            Paragraph::new(header_line).block(Block::bordered().title("opensea-stream")),
+            header,
+        );
+
+        // Busiest collections first, computing floors only for the rows that fit.
+        let mut busiest: Vec<_> = self.collections.iter().collect();
+        busiest.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(b.0)));
+        let rows = busiest
+            .into_iter()
+            .take(collections.height.saturating_sub(3).into())
+            .map(|(slug, counts)| {
+                Row::new([
+                    slug.clone(),
+                    counts.listed.to_string(),
+                    counts.sold.to_string(),
+                    counts.offers.to_string(),
+                    counts.transferred.to_string(),
+                    counts.cancelled.to_string(),
+                    counts.total.to_string(),
+                    self.floor(slug),
+                ])
+            });
+        let widths = [
+            Constraint::Fill(3),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(2),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new([
+                    "Collection",
+                    "Listed",
+                    "Sold",
+                    "Offers",
+                    "Transferred",
+                    "Cancelled",
+                    "Total",
+                    "Floor",
+                ])
+                .style(Style::new().bold()),
+            )
+            .block(Block::bordered().title("Collections"));
+        frame.render_widget(table, collections);
+
+        let items = self.sales.iter().map(|sale| {
+            ListItem::new(format!(
+                "{} {}: {}",
+                sale.at.format("%H:%M:%S"),
+                sale.collection,
+                sale.summary
+            ))
+        });
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title("Latest sales")),
+            sales,
+        );
+    }
+}
+
+/// Returns `true` if the key event should quit the monitor.
+fn quits(event: &TermEvent) -> bool {
+    let TermEvent::Key(key) = event else {
+        return false;
+    };
+    key.kind == KeyEventKind::Press
+        && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+            || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)))
+}
+
+/// Reads terminal events on a separate thread, as reading them blocks.
+fn terminal_events() -> mpsc::UnboundedReceiver<TermEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    thread::spawn(move || {
+        while !tx.is_closed() {
+            match event::poll(Duration::from_millis(100)) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        let _ = tx.send(event);
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+/// Shows the dashboard until the user quits, restoring the terminal afterwards.
+pub async fn run(
+    network: Network,
+    events: mpsc::UnboundedReceiver<StreamEvent>,
+) -> anyhow::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run_in(&mut terminal, Monitor::new(network), events).await;
+    ratatui::restore();
+    result
+}
+
+async fn run_in(
+    terminal: &mut DefaultTerminal,
+    mut monitor: Monitor,
+    mut events: mpsc::UnboundedReceiver<StreamEvent>,
+) -> anyhow::Result<()> {
+    let mut keys = terminal_events();
+    let mut redraw = time::interval(Duration::from_millis(250));
+
+    loop {
+        tokio::select! {
+            event = events.recv(), if monitor.connected => match event {
+                Some(event) => monitor.record(event),
+                None => monitor.connected = false,
+            },
+            key = keys.recv() => match key {
+                Some(key) if quits(&key) => return Ok(()),
+                Some(_) => {}
+                None => return Ok(()),
+            },
+            _ = redraw.tick() => {
+                terminal.draw(|frame| monitor.draw(frame))?;
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use crate::schema::{
+    unit_price, Account, Context, NftId, Payload, PaymentToken, StreamEvent, TraitCriteria,
+};
+use chrono::{DateTime, Utc};
+use ethers_core::types::{H256, U256};
+use std::collections::{HashMap, HashSet};
+
+/// Side of an [`Order`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// A listing, offering an item for sale.
+    Listing,
+    /// An offer (or bid), offering to buy an item.
+    Offer,
+}
+
+/// What an [`Order`] can be filled with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// A single item.
+    Item(NftId),
+    /// Any item of the collection.
+    Collection,
+    /// Any item of the collection with a trait.
+    Trait(TraitCriteria),
+}
+
+/// An open order, tracked by an [`OrderBook`].
+#[derive(Debug, Clone)]
+pub struct Order {
+    /// Hash id of the order.
+    pub order_hash: H256,
+    /// Side of the order.
+    pub side: Side,
+    /// What the order can be filled with.
+    pub target: Target,
+    /// Price of the order, for all `quantity` items. See `payment_token` for the actual value of each unit.
+    pub price: U256,
+    /// Token used for payment.
+    pub payment_token: PaymentToken,
+    /// Number of items.
+    pub quantity: u64,
+    /// Creator of the order.
+    pub maker: Account,
+    /// Timestamp of when the order was created.
+    pub created_date: DateTime<Utc>,
+    /// Timestamp of when the order expires.
+    pub expiration_date: DateTime<Utc>,
+}
+
+impl Order {
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.price, self.quantity)
+    }
+
+    /// Returns the order of a listing or offer event, or `None` for other events.
+    pub fn from_payload(payload: &Payload) -> Option<Self> {
+        let item = |v: &Context| Target::Item(v.item.nft_id.clone());
+        Some(match payload {
+            Payload::ItemListed(v) => Order {
+                order_hash: v.order_hash,
+                side: Side::Listing,
+                target: item(&v.context),
+                price: v.base_price,
+                payment_token: v.payment_token.clone(),
+                quantity: v.quantity,
+                maker: v.maker.clone(),
+                created_date: v.listing_date,
+                expiration_date: v.expiration_date,
+            },
+            Payload::ItemReceivedOffer(v) => Order {
+                order_hash: v.order_hash,
+                side: Side::Offer,
+                target: item(&v.context),
+                price: v.base_price,
+                payment_token: v.payment_token.clone(),
+                quantity: v.quantity,
+                maker: v.maker.clone(),
+                created_date: v.created_date,
+                expiration_date: v.expiration_date,
+            },
+            Payload::ItemReceivedBid(v) => Order {
+                order_hash: v.order_hash,
+                side: Side::Offer,
+                target: item(&v.context),
+                price: v.base_price,
+                payment_token: v.payment_token.clone(),
+                quantity: v.quantity,
+                maker: v.maker.clone(),
+                created_date: v.created_date,
+                expiration_date: v.expiration_date,
+            },
+            Payload::CollectionOffer(v) => Order {
+                order_hash: v.order_hash,
+                side: Side::Offer,
+                target: Target::Collection,
+                price: v.base_price,
+                payment_token: v.payment_token.clone(),
+                quantity: v.quantity,
+                maker: v.maker.clone(),
+                created_date: v.created_date,
+                expiration_date: v.expiration_date,
+            },
+            Payload::TraitOffer(v) => Order {
+                order_hash: v.order_hash,
+                side: Side::Offer,
+                target: Target::Trait(v.trait_criteria.clone()),
+                price: v.base_price,
+                payment_token: v.payment_token.clone(),
+                quantity: v.quantity,
+                maker: v.maker.clone(),
+                created_date: v.created_date,
+                expiration_date: v.expiration_date,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// Reason an [`Order`] was removed from an [`OrderBook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Removal {
+    /// The expiration date of the order passed.
+    Expired,
+    /// The order was cancelled.
+    Cancelled,
+    /// The item of the order was sold by or to the maker of the order.
+    Sold,
+}
+
+/// Change to an [`OrderBook`], returned by [`OrderBook::apply`] and [`OrderBook::expire`].
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// An order was added (or replaced an order with the same hash).
+    Added(Order),
+    /// An order was removed.
+    Removed(Order, Removal),
+}
+
+/// Orders of a single collection at a point in time, returned by [`OrderBook::snapshot`].
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    /// Listings, cheapest first by unit price.
+    pub listings: Vec<Order>,
+    /// Offers, highest first by unit price.
+    pub offers: Vec<Order>,
+}
+
+impl Snapshot {
+    /// Returns the orders that were added and removed between `earlier` and this snapshot.
+    pub fn diff(&self, earlier: &Snapshot) -> Diff {
+        let hashes = |snapshot: &Snapshot| -> HashSet<H256> {
+            snapshot.orders().map(|order| order.order_hash).collect()
+        };
+        let (before, after) = (hashes(earlier), hashes(self));
+
+        Diff {
+            added: self
+                .orders()
+                .filter(|order| !before.contains(&order.order_hash))
+                .cloned()
+                .collect(),
+            removed: earlier
+                .orders()
+                .filter(|order| !after.contains(&order.order_hash))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.listings.iter().chain(&self.offers)
+    }
+}
+
+/// Difference between two [`Snapshot`]s, returned by [`Snapshot::diff`].
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    /// Orders in the later snapshot only.
+    pub added: Vec<Order>,
+    /// Orders in the earlier snapshot only.
+    pub removed: Vec<Order>,
+}
+
+/// In-memory book of open listings and offers, per collection.
+///
+/// Orders are added from listing and offer events, and removed when they are cancelled, when their item is sold
+/// by or to their maker, or when their expiration date passes.
+/// ```no_run
+/// # use opensea_stream::{orderbook::OrderBook, Client, Collection, Network};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let collection = Collection::Collection("wandernauts".to_string());
+/// let mut events = client.subscribe(collection).await?;
+///
+/// let mut book = OrderBook::new();
+/// while let Some(event) = events.recv().await {
+///     book.apply(&event?);
+///     if let Some(floor) = book.snapshot("wandernauts").listings.first() {
+///         println!("floor: {}", floor.price);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    collections: HashMap<String, HashMap<H256, Order>>,
+}
+
+impl OrderBook {
+    /// Constructs a new, empty `OrderBook`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the book with an event, then expires orders at the current time.
+    pub fn apply(&mut self, event: &StreamEvent) -> Vec<Change> {
+        self.apply_at(event, Utc::now())
+    }
+
+    /// Updates the book with an event, then expires orders at `now`.
+    pub fn apply_at(&mut self, event: &StreamEvent, now: DateTime<Utc>) -> Vec<Change> {
+        let payload = &event.payload;
+        let slug = &payload.collection().0;
+        let mut changes = Vec::new();
+
+        if let Some(order) = Order::from_payload(payload) {
+            self.collections
+                .entry(slug.clone())
+                .or_default()
+                .insert(order.order_hash, order.clone());
+            changes.push(Change::Added(order));
+        } else if let Some(orders) = self.collections.get_mut(slug) {
+            match payload {
+                Payload::ItemCancelled(v) => {
+                    if let Some(order) = orders.remove(&v.order_hash) {
+                        changes.push(Change::Removed(order, Removal::Cancelled));
+                    }
+                }
+                Payload::ItemSold(v) => {
+                    let item = Target::Item(v.context.item.nft_id.clone());
+                    let parties = [v.maker.address, v.taker.address];
+                    changes.extend(remove_where(orders, Removal::Sold, |order| {
+                        order.target == item && parties.contains(&order.maker.address)
+                    }));
+                }
+                _ => {}
+            }
+        }
+
+        changes.extend(self.expire(now));
+        changes
+    }
+
+    /// Removes every order whose expiration date is not after `now`.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<Change> {
+        let changes = self
+            .collections
+            .values_mut()
+            .flat_map(|orders| {
+                remove_where(orders, Removal::Expired, |order| {
+                    order.expiration_date <= now
+                })
+            })
+            .collect();
+        self.collections.retain(|_, orders| !orders.is_empty());
+        changes
+    }
+
+    /// Returns the open orders of the collection with `slug`.
+    pub fn snapshot(&self, slug: &str) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        for order in self
+            .collections
+            .get(slug)
+            .into_iter()
+            .flat_map(|o| o.values())
+        {
+            match order.side {
+                Side::Listing => snapshot.listings.push(order.clone()),
+                Side::Offer => snapshot.offers.push(order.clone()),
+            }
+        }
+        snapshot.listings.sort_by_key(Order::unit_price);
+        snapshot
+            .offers
+            .sort_by_key(|order| std::cmp::Reverse(order.unit_price()));
+        snapshot
+    }
+
+    /// Returns the order with `order_hash`, if it is open.
+    pub fn get(&self, order_hash: &H256) -> Option<&Order> {
+        self.collections
+            .values()
+            .find_map(|orders| orders.get(order_hash))
+    }
+
+    /// Returns the total number of open orders.
+    pub fn len(&self) -> usize {
+        self.collections.values().map(HashMap::len).sum()
+    }
+
+    /// Returns `true` if there are no open orders.
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty()
+    }
+}
+
+/// Removes the orders matching `predicate`, returning them as changes.
+fn remove_where(
+    orders: &mut HashMap<H256, Order>,
+    removal: Removal,
+    predicate: impl Fn(&Order) -> bool,
+) -> Vec<Change> {
+    let hashes: Vec<_> = orders
+        .values()
+        .filter(|order| predicate(order))
+        .map(|order| order.order_hash)
+        .collect();
+    hashes
+        .into_iter()
+        .filter_map(|hash| orders.remove(&hash))
+        .map(|order| Change::Removed(order, removal))
+        .collect()
+}
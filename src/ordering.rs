@@ -0,0 +1,175 @@
+use crate::{schema::StreamEvent, Error};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// An event with the sequence number it was assigned when it was received, and its latency when it was delivered.
+///
+/// Sequence numbers start at zero and increase by one for every event received by an
+/// [`EventStream`](crate::EventStream), in the order the events arrived on the socket. Events dropped by the
+/// [`Pipeline`](crate::middleware::Pipeline), coalesced or dropped by the rate limit leave gaps.
+#[derive(Debug, Clone)]
+pub struct Sequenced {
+    /// Sequence number of the event.
+    pub sequence: u64,
+    /// Time at which the event was received, by the local clock.
+    pub received_at: DateTime<Utc>,
+    /// Time between the event happening (its `event_timestamp`) and it being delivered, including the time it was
+    /// held for ordering, coalescing or rate limiting. `None` for events without a timestamp.
+    ///
+    /// The local clock is corrected by how far it is behind the clock of OpenSea, estimated from events that appear
+    /// to be received before they were sent. A local clock that is ahead cannot be told apart from a slow
+    /// connection, and adds to the latency.
+    pub latency: Option<Duration>,
+    /// The event.
+    pub event: StreamEvent,
+}
+
+/// What a [`Reorder`] does with an event that arrives after a later event of the same collection was delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Late {
+    /// Yield [`Error::OutOfOrder`] instead of the event.
+    #[default]
+    Drop,
+    /// Yield [`Error::OutOfOrder`], then deliver the event anyway.
+    Deliver,
+}
+
+/// Per-collection ordering of the events of an [`EventStream`](crate::EventStream) by `sent_at`.
+///
+/// Reconnects and [`Shards`](crate::shard::Shards) can deliver the events of a collection out of order. Every event
+/// is held for the window, and events of a collection are released in the order they were sent, so that events
+/// which arrive up to the window late are put back in place. An event sent before the last delivered event of its
+/// collection is late, and yields [`Error::OutOfOrder`]; events of different collections are not ordered with
+/// respect to each other.
+///
+/// Events are ordered after they are run through the [`Pipeline`](crate::middleware::Pipeline) of the stream, and
+/// before they are coalesced or rate limited.
+/// ```no_run
+/// # use opensea_stream::{ordering::{Late, Reorder}, Client, Collection, Error, Network, SubscribeConfig};
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let config = SubscribeConfig::new().reorder(Reorder::new(Duration::from_secs(2)).late(Late::Drop));
+/// let mut events = client.subscribe_with_config(Collection::All, config).await?;
+///
+/// while let Some(event) = events.recv_sequenced().await {
+///     match event {
+///         Ok(event) => println!("#{}: {:?}", event.sequence, event.event),
+///         Err(Error::OutOfOrder { collection, sequence, .. }) => {
+///             eprintln!("#{} of {} is out of order, resyncing", sequence, collection)
+///         }
+///         Err(e) => return Err(e.into()),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reorder {
+    window: Duration,
+    late: Late,
+}
+
+impl Reorder {
+    /// Constructs a new `Reorder` which holds events for `window`, and drops late events.
+    ///
+    /// With a window of zero, events are not held, and only late events are detected.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            late: Late::default(),
+        }
+    }
+
+    /// Sets what happens to late events.
+    pub fn late(mut self, late: Late) -> Self {
+        self.late = late;
+        self
+    }
+}
+
+/// State of a [`Reorder`] for a single stream.
+#[derive(Debug)]
+pub(crate) struct Reorderer {
+    config: Reorder,
+    /// Ends of the holds of events, oldest first, with the collection and sequence number of the event.
+    windows: VecDeque<(Instant, String, u64)>,
+    /// Held events of every collection, by `sent_at` and then sequence number.
+    held: HashMap<String, Vec<Sequenced>>,
+    /// `sent_at` of the last delivered event of every collection.
+    latest: HashMap<String, DateTime<Utc>>,
+}
+
+impl Reorderer {
+    pub(crate) fn new(config: Reorder) -> Self {
+        Self {
+            config,
+            windows: VecDeque::new(),
+            held: HashMap::new(),
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Holds an event until the end of its window. If it is late, returns the error to yield, and the event to
+    /// deliver after it according to the [`Late`] policy.
+    pub(crate) fn push(
+        &mut self,
+        event: Sequenced,
+        now: Instant,
+    ) -> Option<(Error, Option<Sequenced>)> {
+        let collection = event.event.payload.collection().0.clone();
+        let sent_at = event.event.sent_at;
+        if let Some(latest) = self.latest.get(&collection).filter(|l| sent_at < **l) {
+            let error = Error::OutOfOrder {
+                collection,
+                sequence: event.sequence,
+                sent_at,
+                latest: *latest,
+            };
+            return Some(match self.config.late {
+                Late::Drop => (error, None),
+                Late::Deliver => (error, Some(event)),
+            });
+        }
+
+        self.windows
+            .push_back((now + self.config.window, collection.clone(), event.sequence));
+        let held = self.held.entry(collection).or_default();
+        let i = held.partition_point(|h| (h.event.sent_at, h.sequence) < (sent_at, event.sequence));
+        held.insert(i, event);
+        None
+    }
+
+    /// Removes the earliest held event of the collection of the oldest hold that ended at `now`. Otherwise,
+    /// returns the time at which the next hold ends, or `None` if no events are held.
+    pub(crate) fn pop(&mut self, now: Instant) -> Result<Sequenced, Option<Instant>> {
+        while let Some((end, collection, sequence)) = self.windows.front() {
+            if *end > now {
+                return Err(Some(*end));
+            }
+            let Some(held) = self.held.get_mut(collection) else {
+                self.windows.pop_front();
+                continue;
+            };
+            // The event was already released ahead of an event of its collection that was sent after it.
+            if !held.iter().any(|h| h.sequence == *sequence) {
+                self.windows.pop_front();
+                continue;
+            }
+
+            let event = held.remove(0);
+            if held.is_empty() {
+                self.held.remove(collection);
+            }
+            let collection = event.event.payload.collection().0.clone();
+            self.latest.insert(collection, event.event.sent_at);
+            return Ok(event);
+        }
+        Err(None)
+    }
+}
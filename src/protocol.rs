@@ -1,5 +1,7 @@
+#[cfg(feature = "http")]
+use crate::enrich::ContractAddress;
 use serde::{de::Error, Deserialize, Serialize};
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 use url::Url;
 
 /// A collection whose events can be subscribed to.
@@ -9,18 +11,23 @@ pub enum Collection {
     Collection(String),
     /// All possible collections.
     All,
+    /// Collection that a contract belongs to, whose slug is resolved with the OpenSea REST API when a
+    /// [`Client`](crate::Client) subscribes to it (see [`ClientBuilder::enricher`](crate::ClientBuilder::enricher)).
+    ///
+    /// This has no channel of its own: the functions of the `unstable-phyllo` feature, which join channels directly,
+    /// need the slug resolved first with [`Enricher::collection_of`](crate::enrich::Enricher::collection_of).
+    #[cfg(feature = "http")]
+    ContractAddress(ContractAddress),
 }
 
 impl Display for Collection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "collection:{}",
-            match &self {
-                Collection::Collection(c) => c,
-                Collection::All => "*",
-            }
-        )
+        match &self {
+            Collection::Collection(c) => write!(f, "collection:{}", c),
+            Collection::All => write!(f, "collection:*"),
+            #[cfg(feature = "http")]
+            Collection::ContractAddress(contract) => write!(f, "contract:{}", contract),
+        }
     }
 }
 
@@ -39,6 +46,13 @@ impl<'de> Deserialize<'de> for Collection {
         D: serde::Deserializer<'de>,
     {
         let s: String = Deserialize::deserialize(deserializer)?;
+        #[cfg(feature = "http")]
+        if let Some(contract) = s.strip_prefix("contract:") {
+            return contract
+                .parse()
+                .map(Collection::ContractAddress)
+                .map_err(D::Error::custom);
+        }
         let s = s
             .strip_prefix("collection:")
             .ok_or_else(|| D::Error::custom("expected collection:name"))?;
@@ -54,6 +68,7 @@ impl<'de> Deserialize<'de> for Collection {
 ///
 /// OpenSea provides two websockets for either `Mainnet` (production) networks for `Testnet` networks.
 /// See [`Chain`](crate::schema::Chain) for a full list of supported chains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Network {
     /// Mainnet (`Ethereum`, `Polygon`, `Klaytn`, `Solana`)
     Mainnet,
@@ -74,29 +89,82 @@ impl From<Network> for Url {
     }
 }
 
-/// Receivable events from the websocket.
-///
-/// This type belongs to the `event` field of [`Message`](phyllo::message::Message), not to be confused with
-/// [`Payload`](crate::schema::Payload).
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
-#[serde(rename_all = "snake_case")]
-pub enum Event {
+/// Declares [`Event`] along with the list of every event and its conversions from and to strings, so that adding an
+/// event cannot leave any of them out of date.
+macro_rules! events {
+    ($($(#[doc = $doc:literal])* $variant:ident => $name:literal,)*) => {
+        /// Receivable events from the websocket.
+        ///
+        /// This type belongs to the `event` field of [`Message`](phyllo::message::Message), not to be confused with
+        /// [`Payload`](crate::schema::Payload).
+        #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Event {
+            $($(#[doc = $doc])* $variant,)*
+        }
+
+        impl Event {
+            /// Every event, in the order of declaration.
+            ///
+            /// Every event has a [`Payload`](crate::schema::Payload) variant, which is checked at compile time by
+            /// [`Payload::event`](crate::schema::Payload::event), and a fixture in `tests/fixtures`, which is checked
+            /// by `tests/fixtures.rs`.
+            pub const ALL: &'static [Event] = &[$(Event::$variant,)*];
+        }
+
+        impl FromStr for Event {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($name => Ok(Event::$variant),)*
+                    _ => Err(()),
+                }
+            }
+        }
+
+        impl Display for Event {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{}",
+                    match self {
+                        $(Event::$variant => $name,)*
+                    }
+                )
+            }
+        }
+
+        // Generated here so that events and payloads cannot fall out of step: a payload without an event, or an
+        // event without a payload, does not compile.
+        impl crate::schema::Payload {
+            /// Returns the [`Event`] corresponding to this payload.
+            pub fn event(&self) -> Event {
+                match self {
+                    $(crate::schema::Payload::$variant(_) => Event::$variant,)*
+                }
+            }
+        }
+    };
+}
+
+events! {
     /// An item been listed for sale.
-    ItemListed,
+    ItemListed => "item_listed",
     /// An item has been sold.
-    ItemSold,
+    ItemSold => "item_sold",
     /// An item has been transferred from one wallet to another.
-    ItemTransferred,
+    ItemTransferred => "item_transferred",
     /// An item has had its metadata updated.
-    ItemMetadataUpdated,
+    ItemMetadataUpdated => "item_metadata_updated",
     /// An item has had its listing cancelled.
-    ItemCancelled,
+    ItemCancelled => "item_cancelled",
     /// An item has received an offer.
-    ItemReceivedOffer,
+    ItemReceivedOffer => "item_received_offer",
     /// An item has received a bid.
-    ItemReceivedBid,
+    ItemReceivedBid => "item_received_bid",
     /// An Collection has received a offer.
-    CollectionOffer,
+    CollectionOffer => "collection_offer",
     /// An Trait offer has been made.
-    TraitOffer,
+    TraitOffer => "trait_offer",
 }
@@ -0,0 +1,295 @@
+use crate::{ordering::Sequenced, schema::NftId, Event};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Rate at which a token bucket refills, and how many tokens it holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    per_second: f64,
+    burst: u32,
+}
+
+impl Quota {
+    /// Constructs a new `Quota` of `n` events per second, with a burst of `n` events.
+    pub fn per_second(n: u32) -> Self {
+        Self::new(n, Duration::from_secs(1))
+    }
+
+    /// Constructs a new `Quota` of `n` events per minute, with a burst of `n` events.
+    pub fn per_minute(n: u32) -> Self {
+        Self::new(n, Duration::from_secs(60))
+    }
+
+    /// Constructs a new `Quota` of `n` events per `period`, with a burst of `n` events.
+    ///
+    /// # Panics
+    /// Panics if `n` or `period` is zero.
+    pub fn new(n: u32, period: Duration) -> Self {
+        assert!(n > 0 && !period.is_zero(), "quota must be positive");
+        Self {
+            per_second: n as f64 / period.as_secs_f64(),
+            burst: n,
+        }
+    }
+
+    /// Sets the number of events that can be delivered at once after a quiet period.
+    ///
+    /// # Panics
+    /// Panics if `burst` is zero.
+    pub fn burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "burst must be positive");
+        self.burst = burst;
+        self
+    }
+}
+
+/// What a [`RateLimit`] does with events that arrive while no tokens are available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// Queue events until tokens are available, dropping new events once the queue holds this many.
+    Buffer(usize),
+    /// Drop events immediately.
+    Drop,
+    /// Queue events like [`Overflow::Buffer`], but replace a queued event of the same type and item (or
+    /// collection, for collection and trait offers) with the newer one.
+    Coalesce(usize),
+}
+
+/// Token-bucket rate limit on the events delivered by an [`EventStream`](crate::EventStream).
+///
+/// Events can be limited globally, per collection, or both; an event is delivered once a token is available in
+/// every bucket that applies to it. Events are limited after they are run through the
+/// [`Pipeline`](crate::middleware::Pipeline) of the stream, so events dropped by the pipeline do not use tokens.
+///
+/// The limit is cheap to clone. Every stream has its own buckets, but all clones share the counter of dropped
+/// events.
+/// ```no_run
+/// # use opensea_stream::{ratelimit::{Overflow, Quota, RateLimit}, Client, Collection, Network, SubscribeConfig};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let limit = RateLimit::new()
+///     .global(Quota::per_second(50))
+///     .per_collection(Quota::per_second(5).burst(20))
+///     .overflow(Overflow::Coalesce(1000));
+///
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let config = SubscribeConfig::new().rate_limit(limit.clone());
+/// let mut events = client.subscribe_with_config(Collection::All, config).await?;
+///
+/// while let Some(event) = events.recv().await {
+///     println!("{:?} ({} dropped so far)", event?, limit.dropped());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    global: Option<Quota>,
+    per_collection: Option<Quota>,
+    overflow: Overflow,
+    dropped: Arc<AtomicU64>,
+}
+
+impl RateLimit {
+    /// Constructs a new `RateLimit` without any quota, which buffers up to 1024 events.
+    pub fn new() -> Self {
+        Self {
+            global: None,
+            per_collection: None,
+            overflow: Overflow::Buffer(1024),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Limits the events of all collections together.
+    pub fn global(mut self, quota: Quota) -> Self {
+        self.global = Some(quota);
+        self
+    }
+
+    /// Limits the events of each collection separately.
+    pub fn per_collection(mut self, quota: Quota) -> Self {
+        self.per_collection = Some(quota);
+        self
+    }
+
+    /// Sets what happens to events that arrive while no tokens are available.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Returns the number of events that were dropped or replaced under this limit.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// State of a [`RateLimit`] for a single stream.
+#[derive(Debug)]
+pub(crate) struct Limiter {
+    limit: RateLimit,
+    global: Option<Bucket>,
+    collections: HashMap<String, Bucket>,
+    /// Events waiting for tokens, oldest first, and whether their tokens were already taken.
+    queue: VecDeque<(Sequenced, bool)>,
+}
+
+impl Limiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        let now = Instant::now();
+        Self {
+            global: limit.global.map(|quota| Bucket::new(quota, now)),
+            collections: HashMap::new(),
+            queue: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// Queues an event, or drops it according to the overflow policy.
+    pub(crate) fn push(&mut self, event: Sequenced, now: Instant) {
+        match self.limit.overflow {
+            Overflow::Drop => {
+                let slug = event.event.payload.collection().0.clone();
+                if self.wait(&slug, now).is_zero() {
+                    self.take(&slug, now);
+                    self.queue.push_back((event, true));
+                } else {
+                    self.drop_one();
+                }
+            }
+            Overflow::Buffer(cap) => self.enqueue(event, cap),
+            Overflow::Coalesce(cap) => {
+                let queued = self
+                    .queue
+                    .iter()
+                    .position(|(queued, _)| key(queued) == key(&event));
+                match queued {
+                    Some(i) => {
+                        self.queue[i].0 = event;
+                        self.drop_one();
+                    }
+                    None => self.enqueue(event, cap),
+                }
+            }
+        }
+    }
+
+    /// Removes the oldest queued event that can be delivered at `now`. Otherwise, returns the time at which a
+    /// queued event can be delivered, or `None` if the queue is empty.
+    pub(crate) fn pop(&mut self, now: Instant) -> Result<Sequenced, Option<Instant>> {
+        let mut next: Option<Duration> = None;
+        for i in 0..self.queue.len() {
+            let (event, paid) = &self.queue[i];
+            let wait = match paid {
+                true => Duration::ZERO,
+                false => {
+                    let slug = event.event.payload.collection().0.clone();
+                    let wait = self.wait(&slug, now);
+                    if wait.is_zero() {
+                        self.take(&slug, now);
+                    }
+                    wait
+                }
+            };
+            if wait.is_zero() {
+                return Ok(self.queue.remove(i).unwrap().0);
+            }
+            next = Some(next.map_or(wait, |next| next.min(wait)));
+        }
+        Err(next.map(|wait| now + wait))
+    }
+
+    fn enqueue(&mut self, event: Sequenced, cap: usize) {
+        if self.queue.len() < cap {
+            self.queue.push_back((event, false));
+        } else {
+            self.drop_one();
+        }
+    }
+
+    fn drop_one(&self) {
+        self.limit.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns how long until every bucket that applies to events of the collection with `slug` has a token.
+    fn wait(&mut self, slug: &str, now: Instant) -> Duration {
+        let global = self.global.as_mut().map(|b| b.wait(now));
+        let collection = self.collection(slug, now).map(|b| b.wait(now));
+        global.max(collection).unwrap_or_default()
+    }
+
+    fn take(&mut self, slug: &str, now: Instant) {
+        if let Some(bucket) = &mut self.global {
+            bucket.take(now);
+        }
+        if let Some(bucket) = self.collection(slug, now) {
+            bucket.take(now);
+        }
+    }
+
+    fn collection(&mut self, slug: &str, now: Instant) -> Option<&mut Bucket> {
+        let quota = self.limit.per_collection?;
+        Some(
+            self.collections
+                .entry(slug.to_owned())
+                .or_insert_with(|| Bucket::new(quota, now)),
+        )
+    }
+}
+
+/// Identifies the events that replace each other under [`Overflow::Coalesce`].
+fn key(event: &Sequenced) -> (Event, &str, Option<&NftId>) {
+    let payload = &event.event.payload;
+    let item = payload.context().map(|context| &context.item.nft_id);
+    (payload.event(), payload.collection().0.as_str(), item)
+}
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(quota: Quota, now: Instant) -> Self {
+        Self {
+            quota,
+            tokens: quota.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.quota.per_second).min(self.quota.burst as f64);
+        self.updated = now;
+    }
+
+    fn wait(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        match self.tokens >= 1.0 {
+            true => Duration::ZERO,
+            false => Duration::from_secs_f64((1.0 - self.tokens) / self.quota.per_second),
+        }
+    }
+
+    fn take(&mut self, now: Instant) {
+        self.refill(now);
+        self.tokens -= 1.0;
+    }
+}
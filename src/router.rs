@@ -0,0 +1,157 @@
+use crate::{schema::StreamEvent, EventSource};
+use std::{collections::HashMap, fmt};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Destination of events dispatched by a [`Router`].
+pub enum Route {
+    /// Events are sent to a bounded channel.
+    Channel(mpsc::Sender<StreamEvent>),
+    /// Events are sent to an unbounded channel.
+    UnboundedChannel(mpsc::UnboundedSender<StreamEvent>),
+    /// Events are passed to a handler function.
+    Handler(Box<dyn FnMut(StreamEvent) + Send>),
+}
+
+impl Route {
+    /// Constructs a route that passes events to `handler`.
+    pub fn handler<F>(handler: F) -> Self
+    where
+        F: FnMut(StreamEvent) + Send + 'static,
+    {
+        Self::Handler(Box::new(handler))
+    }
+
+    /// Delivers an event. If the receiving half of the route has been dropped, the event is returned.
+    async fn deliver(&mut self, event: StreamEvent) -> Result<(), StreamEvent> {
+        match self {
+            Route::Channel(tx) => tx.send(event).await.map_err(|e| e.0),
+            Route::UnboundedChannel(tx) => tx.send(event).map_err(|e| e.0),
+            Route::Handler(f) => {
+                f(event);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Route::Channel(tx) => f.debug_tuple("Channel").field(tx).finish(),
+            Route::UnboundedChannel(tx) => f.debug_tuple("UnboundedChannel").field(tx).finish(),
+            Route::Handler(_) => f.debug_tuple("Handler").finish(),
+        }
+    }
+}
+
+impl From<mpsc::Sender<StreamEvent>> for Route {
+    fn from(tx: mpsc::Sender<StreamEvent>) -> Self {
+        Route::Channel(tx)
+    }
+}
+
+impl From<mpsc::UnboundedSender<StreamEvent>> for Route {
+    fn from(tx: mpsc::UnboundedSender<StreamEvent>) -> Self {
+        Route::UnboundedChannel(tx)
+    }
+}
+
+/// Dispatches events to a [`Route`] depending on the slug of the collection they belong to.
+///
+/// This is intended to be used with a subscription to [`Collection::All`](crate::Collection::All). Each event is moved into exactly one route:
+/// the route registered for its collection if there is one, or the default route otherwise.
+/// Routes whose receiving half has been dropped are removed, and their events take the default route.
+/// ```no_run
+/// # use opensea_stream::{router::{Route, Router}, Client, Collection, Network};
+/// # use tokio::sync::mpsc;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let subscription = client.subscribe(Collection::All).await?;
+///
+/// let (wandernauts_tx, mut wandernauts_rx) = mpsc::channel(128);
+/// let router = Router::new()
+///     .route("wandernauts", wandernauts_tx)
+///     .default_route(Route::handler(|event| println!("{:?}", event.payload.event())));
+/// tokio::spawn(router.run(subscription));
+///
+/// while let Some(event) = wandernauts_rx.recv().await {
+///     println!("{:?}", event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Router {
+    routes: HashMap<String, Route>,
+    default: Option<Route>,
+}
+
+impl Router {
+    /// Constructs a new `Router` without any routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the route for events of the collection with `slug`, replacing any previous route.
+    pub fn route(mut self, slug: impl Into<String>, route: impl Into<Route>) -> Self {
+        self.routes.insert(slug.into(), route.into());
+        self
+    }
+
+    /// Sets the route for events of collections that have no route of their own.
+    pub fn default_route(mut self, route: impl Into<Route>) -> Self {
+        self.default = Some(route.into());
+        self
+    }
+
+    /// Dispatches an event to its route. If there is no route for the event, it is returned.
+    ///
+    /// If the receiving half of its route has been dropped, the route is removed and the event takes the default
+    /// route instead.
+    pub async fn dispatch(&mut self, event: StreamEvent) -> Result<(), StreamEvent> {
+        let slug = &event.payload.collection().0;
+
+        let event = match self.routes.get_mut(slug) {
+            Some(route) => match route.deliver(event).await {
+                Ok(()) => return Ok(()),
+                Err(event) => {
+                    self.routes.remove(&event.payload.collection().0);
+                    event
+                }
+            },
+            None => event,
+        };
+
+        match &mut self.default {
+            Some(route) => route.deliver(event).await.inspect_err(|_| {
+                self.default = None;
+            }),
+            None => Err(event),
+        }
+    }
+
+    /// Returns whether the router has no routes left, including the default route.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty() && self.default.is_none()
+    }
+
+    /// Dispatches every event received from `events` until it ends, or until every route has been removed.
+    ///
+    /// Events without a route are skipped. Errors of the source, such as missed events, are logged as warnings
+    /// with [`tracing`](https://crates.io/crates/tracing).
+    pub async fn run(mut self, mut events: impl EventSource) {
+        while !self.is_empty() {
+            let Some(event) = events.recv().await else {
+                break;
+            };
+            match event {
+                Ok(event) => {
+                    let _ = self.dispatch(event).await;
+                }
+                Err(e) => warn!(error = %e, "router received an error, skipping it"),
+            }
+        }
+    }
+}
@@ -1,15 +1,38 @@
+#[cfg(feature = "ethers")]
+use crate::seaport::ProtocolData;
 use crate::Event;
 use chrono::{DateTime, Utc};
 use ethers_core::{
     abi::Address,
     types::{H256, U256},
+    utils::format_units,
 };
+#[cfg(feature = "schemars")]
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{de::Error, Deserialize, Serialize};
+#[cfg(feature = "unknown-fields")]
+use serde_json::Value;
+#[cfg(feature = "unknown-fields")]
+use std::collections::HashMap;
 use std::{fmt, str::FromStr};
 use url::Url;
 
+/// Returns the JSON schema of [`StreamEvent`], including the schemas of every type it contains.
+///
+/// The schema describes messages as they are sent by OpenSea: addresses, hashes and large integers are strings,
+/// and timestamps are RFC 3339 strings.
+/// ```
+/// let schema = opensea_stream::schema::json_schema();
+/// println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+/// ```
+#[cfg(feature = "schemars")]
+pub fn json_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(StreamEvent)
+}
+
 /// Payload of a message received from the websocket.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct StreamEvent {
     /// Timestamp of when this message was sent to the client.
     pub sent_at: DateTime<Utc>,
@@ -19,11 +42,19 @@ pub struct StreamEvent {
     pub payload: Payload,
 }
 
+impl StreamEvent {
+    /// Returns the timestamp of when the event happened, falling back to when it was sent if the payload carries none.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.payload.event_timestamp().unwrap_or(self.sent_at)
+    }
+}
+
 /// Content of the message.
 ///
 /// This type corresponds to the JSON objects recieved [as described here](https://docs.opensea.io/reference/stream-api-event-schemas),
 /// not the event type used for the Phoenix protocol (see [`Event`]).
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[serde(tag = "event_type", content = "payload")]
 #[serde(rename_all = "snake_case")]
 pub enum Payload {
@@ -32,6 +63,8 @@ pub enum Payload {
     /// An item has been sold.
     ItemSold(ItemSoldData),
     /// An item has been transferred from one wallet to another.
+    ///
+    /// Transfers include mints and burns, which are transfers from and to the zero address.
     ItemTransferred(ItemTransferredData),
     /// An item has had its metadata updated.
     ItemMetadataUpdated(ItemMetadataUpdatedData),
@@ -49,24 +82,204 @@ pub enum Payload {
 
 impl From<Payload> for Event {
     fn from(val: Payload) -> Self {
-        match val {
-            Payload::ItemListed(_) => Event::ItemListed,
-            Payload::ItemSold(_) => Event::ItemSold,
-            Payload::ItemTransferred(_) => Event::ItemTransferred,
-            Payload::ItemMetadataUpdated(_) => Event::ItemMetadataUpdated,
-            Payload::ItemCancelled(_) => Event::ItemCancelled,
-            Payload::ItemReceivedOffer(_) => Event::ItemReceivedOffer,
-            Payload::ItemReceivedBid(_) => Event::ItemReceivedBid,
-            Payload::CollectionOffer(_) => Event::CollectionOffer,
-            Payload::TraitOffer(_) => Event::TraitOffer,
+        val.event()
+    }
+}
+
+impl Payload {
+    /// Returns the item context of this payload.
+    ///
+    /// Collection-wide events ([`Payload::CollectionOffer`] and [`Payload::TraitOffer`]) have no item and return `None`.
+    pub fn context(&self) -> Option<&Context> {
+        match self {
+            Payload::ItemListed(v) => Some(&v.context),
+            Payload::ItemSold(v) => Some(&v.context),
+            Payload::ItemTransferred(v) => Some(&v.context),
+            Payload::ItemMetadataUpdated(v) => Some(&v.context),
+            Payload::ItemCancelled(v) => Some(&v.context),
+            Payload::ItemReceivedOffer(v) => Some(&v.context),
+            Payload::ItemReceivedBid(v) => Some(&v.context),
+            Payload::CollectionOffer(_) | Payload::TraitOffer(_) => None,
+        }
+    }
+
+    /// Returns the collection this payload belongs to.
+    pub fn collection(&self) -> &Collection {
+        match self {
+            Payload::CollectionOffer(v) => &v.collection,
+            Payload::TraitOffer(v) => &v.collection,
+            _ => &self.context().unwrap().collection,
+        }
+    }
+
+    /// Returns the transaction of this payload.
+    ///
+    /// Only on-chain events ([`Payload::ItemSold`], [`Payload::ItemTransferred`] and [`Payload::ItemCancelled`])
+    /// carry a transaction; all other payloads return `None`.
+    pub fn transaction(&self) -> Option<&Transaction> {
+        match self {
+            Payload::ItemSold(v) => Some(&v.transaction),
+            Payload::ItemTransferred(v) => Some(&v.transaction),
+            Payload::ItemCancelled(v) => Some(&v.transaction),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of this payload that are not part of the schema.
+    ///
+    /// A non-empty map means that OpenSea has added fields that this version does not know about yet.
+    #[cfg(feature = "unknown-fields")]
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        match self {
+            Payload::ItemListed(v) => &v.extra,
+            Payload::ItemSold(v) => &v.extra,
+            Payload::ItemTransferred(v) => &v.extra,
+            Payload::ItemMetadataUpdated(v) => &v.extra,
+            Payload::ItemCancelled(v) => &v.extra,
+            Payload::ItemReceivedOffer(v) => &v.extra,
+            Payload::ItemReceivedBid(v) => &v.extra,
+            Payload::CollectionOffer(v) => &v.extra,
+            Payload::TraitOffer(v) => &v.extra,
+        }
+    }
+
+    /// Returns the Seaport order of this payload, if OpenSea included it.
+    ///
+    /// Only orders ([`Payload::ItemListed`] and the offer payloads) carry protocol data; all other payloads return
+    /// `None`.
+    #[cfg(feature = "ethers")]
+    pub fn protocol_data(&self) -> Option<&ProtocolData> {
+        match self {
+            Payload::ItemListed(v) => v.protocol_data.as_ref(),
+            Payload::ItemReceivedOffer(v) => v.protocol_data.as_ref(),
+            Payload::ItemReceivedBid(v) => v.protocol_data.as_ref(),
+            Payload::CollectionOffer(v) => v.protocol_data.as_ref(),
+            Payload::TraitOffer(v) => v.protocol_data.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the Seaport contract of the order of this payload, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    pub fn protocol_address(&self) -> Option<Address> {
+        match self {
+            Payload::ItemListed(v) => v.protocol_address,
+            Payload::ItemReceivedOffer(v) => v.protocol_address,
+            Payload::ItemReceivedBid(v) => v.protocol_address,
+            Payload::CollectionOffer(v) => v.protocol_address,
+            Payload::TraitOffer(v) => v.protocol_address,
+            _ => None,
+        }
+    }
+
+    /// Returns the timestamp of when the event happened.
+    ///
+    /// [`Payload::ItemMetadataUpdated`] carries no timestamp and returns `None`.
+    pub fn event_timestamp(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Payload::ItemListed(v) => Some(v.event_timestamp),
+            Payload::ItemSold(v) => Some(v.event_timestamp),
+            Payload::ItemTransferred(v) => Some(v.event_timestamp),
+            Payload::ItemMetadataUpdated(_) => None,
+            Payload::ItemCancelled(v) => Some(v.event_timestamp),
+            Payload::ItemReceivedOffer(v) => Some(v.event_timestamp),
+            Payload::ItemReceivedBid(v) => Some(v.event_timestamp),
+            Payload::CollectionOffer(v) => Some(v.event_timestamp),
+            Payload::TraitOffer(v) => Some(v.event_timestamp),
+        }
+    }
+}
+
+impl Payload {
+    /// Returns a one-line, human-readable summary of this payload, such as
+    /// `wandernauts #123 listed for 0.5 WETH by 0x6e3e…4fb4`. Same as the [`Display`](fmt::Display) output.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Payload::ItemListed(v) => write!(
+                f,
+                "{} listed for {} by {}",
+                item(&v.context, v.quantity),
+                v.payment_token.format_amount(v.base_price),
+                v.maker
+            ),
+            Payload::ItemSold(v) => write!(
+                f,
+                "{} sold for {} by {} to {}",
+                item(&v.context, v.quantity),
+                v.payment_token.format_amount(v.sale_price),
+                v.seller(),
+                v.buyer()
+            ),
+            Payload::ItemTransferred(v) => write!(
+                f,
+                "{} transferred from {} to {}",
+                item(&v.context, v.quantity),
+                v.from_account,
+                v.to_account
+            ),
+            Payload::ItemMetadataUpdated(v) => {
+                write!(f, "{} metadata updated", item(&v.context, 1))
+            }
+            Payload::ItemCancelled(v) => write!(
+                f,
+                "{} listing cancelled by {}",
+                item(&v.context, v.quantity),
+                v.maker
+            ),
+            Payload::ItemReceivedOffer(v) => write!(
+                f,
+                "{} received an offer of {} from {}",
+                item(&v.context, v.quantity),
+                v.payment_token.format_amount(v.base_price),
+                v.maker
+            ),
+            Payload::ItemReceivedBid(v) => write!(
+                f,
+                "{} received a bid of {} from {}",
+                item(&v.context, v.quantity),
+                v.payment_token.format_amount(v.base_price),
+                v.maker
+            ),
+            Payload::CollectionOffer(v) => write!(
+                f,
+                "{} received a collection offer of {} from {}",
+                v.collection.0,
+                v.payment_token.format_amount(v.base_price),
+                v.maker
+            ),
+            Payload::TraitOffer(v) => write!(
+                f,
+                "{} {}: {} received a trait offer of {} from {}",
+                v.collection.0,
+                v.trait_criteria.trait_type,
+                v.trait_criteria.trait_value,
+                v.payment_token.format_amount(v.base_price),
+                v.maker
+            ),
         }
     }
 }
 
+/// Formats the item of a context as `slug #id`, followed by the quantity if there is more than one.
+fn item(context: &Context, quantity: u64) -> String {
+    let item = format!("{} #{}", context.collection.0, context.item.nft_id.id);
+    match quantity {
+        0 | 1 => item,
+        n => format!("{} \u{d7}{}", item, n),
+    }
+}
+
 /// Context for a message (token and collection)
 ///
-/// This struct is present in every [`Payload`].
+/// This struct is present in every item-level [`Payload`] (see [`Payload::context`]).
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Context {
     /// Collection that the token belongs to.
     pub collection: Collection,
@@ -95,6 +308,23 @@ impl Serialize for Collection {
     }
 }
 
+#[cfg(feature = "schemars")]
+impl JsonSchema for Collection {
+    fn schema_name() -> String {
+        "Collection".to_owned()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Inner {
+            slug: String,
+        }
+
+        Inner::json_schema(gen)
+    }
+}
+
 impl<'de> Deserialize<'de> for Collection {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -111,6 +341,7 @@ impl<'de> Deserialize<'de> for Collection {
 
 /// Context about an item.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Item {
     /// Identifier.
     pub nft_id: NftId,
@@ -123,7 +354,7 @@ pub struct Item {
 }
 
 /// Identifier of the NFT.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NftId {
     /// Chain the item is on.
     pub network: Chain,
@@ -138,7 +369,25 @@ impl Serialize for NftId {
     where
         S: serde::Serializer,
     {
-        format!("{}/{:?}/{}", self.network, self.address, self.id).serialize(serializer)
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl fmt::Display for NftId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{:?}/{}", self.network, self.address, self.id)
+    }
+}
+
+/// Serialized as `network/address/id`, e.g. `ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4/1`.
+#[cfg(feature = "schemars")]
+impl JsonSchema for NftId {
+    fn schema_name() -> String {
+        "NftId".to_owned()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
     }
 }
 
@@ -178,10 +427,13 @@ impl<'de> Deserialize<'de> for NftId {
 
 mod chain {
     #![allow(deprecated)]
+    #[cfg(feature = "schemars")]
+    use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
     /// Network an item is on.
-    #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "schemars", derive(JsonSchema))]
     #[serde(tag = "name", rename_all = "lowercase")]
     #[non_exhaustive]
     pub enum Chain {
@@ -219,6 +471,7 @@ impl FromStr for Chain {
             "solana" => Ok(Chain::Solana),
             #[allow(deprecated)]
             "rinkeby" => Ok(Chain::Rinkeby),
+            "goerli" => Ok(Chain::Goerli),
             "mumbai" => Ok(Chain::Mumbai),
             "baobab" => Ok(Chain::Baobab),
             _ => Err(()),
@@ -250,6 +503,7 @@ impl fmt::Display for Chain {
 ///
 /// This is fetched directly from an item's metadata according to [metadata standards](https://docs.opensea.io/docs/metadata-standards).
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Metadata {
     /// Name.
     pub name: Option<String>,
@@ -265,39 +519,66 @@ pub struct Metadata {
 
 /// Payload data for [`Payload::ItemListed`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemListedData {
     /// Context
     #[serde(flatten)]
     pub context: Context,
     /// Timestamp of when the listing was created.
     pub event_timestamp: DateTime<Utc>,
-    /// Starting price of the listing. See `payment_token` for the actual value of each unit.
+    /// Starting price of the listing, for all `quantity` items (see [`Self::unit_price`]). See `payment_token` for
+    /// the actual value of each unit.
     #[serde(with = "u256_fromstr_radix_10")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub base_price: U256,
     /// Expiration date.
     pub expiration_date: DateTime<Utc>,
-    /// Whether the listing is private.
+    /// Whether the listing is private. Private listings can only be fulfilled by `taker`.
+    #[serde(default)]
     pub is_private: bool,
     /// Timestamp of when the listing was created.
     pub listing_date: DateTime<Utc>,
     /// Type of listing. `None` indicates the listing is a buyout.
     pub listing_type: Option<ListingType>,
     /// Creator of the listing.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Hash id of the listing.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub order_hash: H256,
     /// Token accepted for payment.
     pub payment_token: PaymentToken,
     /// Number of items on sale. This is always `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
-    /// Buyer of the listing.
-    #[serde(with = "address_fromjson_opt", default)]
-    pub taker: Option<Address>,
+    /// Designated buyer of the listing. This is only present for private listings.
+    #[serde(default)]
+    pub taker: Option<Account>,
+    /// Seaport order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    pub protocol_data: Option<ProtocolData>,
+    /// Address of the Seaport contract of the order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub protocol_address: Option<Address>,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ItemListedData {
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.base_price, self.quantity)
+    }
 }
 
 /// Payload data for [`Payload::ItemSold`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemSoldData {
     /// Context
     #[serde(flatten)]
@@ -312,24 +593,48 @@ pub struct ItemSoldData {
     /// Type of listing. `None` indicates the listing was a buyout.
     pub listing_type: Option<ListingType>,
     /// Creator of the listing.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Token used for payment.
     pub payment_token: PaymentToken,
     /// Number of items bought. This is always `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
-    /// Purchase price. See `payment_token` for the actual value of each unit.
+    /// Purchase price, for all `quantity` items (see [`Self::unit_price`]). See `payment_token` for the actual
+    /// value of each unit.
     #[serde(with = "u256_fromstr_radix_10")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub sale_price: U256,
     /// Buyer/winner of the listing.
-    #[serde(with = "address_fromjson")]
-    pub taker: Address,
+    pub taker: Account,
     /// Transaction for the purchase.
     pub transaction: Transaction,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ItemSoldData {
+    /// Returns the account that sold the item.
+    pub fn seller(&self) -> &Account {
+        &self.maker
+    }
+
+    /// Returns the account that bought the item.
+    pub fn buyer(&self) -> &Account {
+        &self.taker
+    }
+
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.sale_price, self.quantity)
+    }
 }
 
 /// Payload data for [`Payload::ItemTransferred`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemTransferredData {
     /// Context
     #[serde(flatten)]
@@ -340,17 +645,22 @@ pub struct ItemTransferredData {
     /// Transaction of the transfer.
     pub transaction: Transaction,
     /// Address the item was transferred from.
-    #[serde(with = "address_fromjson")]
-    pub from_account: Address,
+    pub from_account: Account,
     /// Address the item was transferred to.
-    #[serde(with = "address_fromjson")]
-    pub to_account: Address,
+    pub to_account: Account,
     /// Number of items transferred. This is always `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// Payload data for [`Payload::ItemMetadataUpdated`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemMetadataUpdatedData {
     /// Context
     #[serde(flatten)]
@@ -371,10 +681,15 @@ pub struct ItemMetadataUpdatedData {
     /// New traits. This appears to be bugged for now, and will always be empty.
     #[serde(default)]
     pub traits: Vec<serde_json::Value>,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// Payload data for [`Payload::ItemCancelled`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemCancelledData {
     /// Context
     #[serde(flatten)]
@@ -385,20 +700,27 @@ pub struct ItemCancelledData {
     /// Type of listing. `None` indicates the listing would've been a buyout.
     pub listing_type: Option<ListingType>,
     /// Creator of the cancellation order.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Hash id of the listing.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub order_hash: H256,
     /// Token accepted for payment.
     pub payment_token: PaymentToken,
     /// Number of items in listing. This is always `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
     /// Transaction for the cancellation.
     pub transaction: Transaction,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 /// Payload data for [`Payload::ItemReceivedOffer`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemReceivedOfferData {
     /// Context
     #[serde(flatten)]
@@ -406,29 +728,54 @@ pub struct ItemReceivedOfferData {
 
     /// Timestamp of when the offer was received.
     pub event_timestamp: DateTime<Utc>,
-    /// Offer price. See `payment_token` for the actual value of each unit.
+    /// Offer price, for all `quantity` items (see [`Self::unit_price`]). See `payment_token` for the actual
+    /// value of each unit.
     #[serde(with = "u256_fromstr_radix_10")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub base_price: U256,
     /// Timestamp of when the offer was created.
     pub created_date: DateTime<Utc>,
     /// Timestamp of when the offer will expire.
     pub expiration_date: DateTime<Utc>,
     /// Creator of the offer.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Hash id of the listing.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub order_hash: H256,
     /// Token offered for payment.
     pub payment_token: PaymentToken,
     /// Number of items on the offer. This is always `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
     /// Taker of the offer.
-    #[serde(with = "address_fromjson_opt", default)]
-    pub taker: Option<Address>,
+    #[serde(default)]
+    pub taker: Option<Account>,
+    /// Seaport order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    pub protocol_data: Option<ProtocolData>,
+    /// Address of the Seaport contract of the order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub protocol_address: Option<Address>,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ItemReceivedOfferData {
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.base_price, self.quantity)
+    }
 }
 
 /// Payload data for [`Payload::ItemReceivedBid`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ItemReceivedBidData {
     /// Context
     #[serde(flatten)]
@@ -436,102 +783,184 @@ pub struct ItemReceivedBidData {
 
     /// Timestamp of when the bid was received.
     pub event_timestamp: DateTime<Utc>,
-    /// Bid price. See `payment_token` for the actual value of each unit.
+    /// Bid price, for all `quantity` items (see [`Self::unit_price`]). See `payment_token` for the actual
+    /// value of each unit.
     #[serde(with = "u256_fromstr_radix_10")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub base_price: U256,
     /// Timestamp of when the bid was created.
     pub created_date: DateTime<Utc>,
     /// Timestamp of when the bid will expire.
     pub expiration_date: DateTime<Utc>,
     /// Creator of the bid.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Hash id of the listing.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub order_hash: H256,
     /// Token offered for payment.
     pub payment_token: PaymentToken,
     /// Number of items on the offer. This is always `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
     /// Taker of the bid.
-    #[serde(with = "address_fromjson_opt", default)]
-    pub taker: Option<Address>,
+    #[serde(default)]
+    pub taker: Option<Account>,
+    /// Seaport order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    pub protocol_data: Option<ProtocolData>,
+    /// Address of the Seaport contract of the order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub protocol_address: Option<Address>,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ItemReceivedBidData {
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.base_price, self.quantity)
+    }
 }
 
 /// Payload data for [`Payload::CollectionOffer`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CollectionOfferData {
     /// Address of NFT contract.
     #[serde(with = "address_fromjson")]
+    #[cfg_attr(feature = "schemars", schemars(with = "address_fromjson::Inner"))]
     pub asset_contract_criteria: Address,
     /// Collection slug.
     pub collection: Collection,
     /// Timestamp of when the offer was received.
     pub event_timestamp: DateTime<Utc>,
-    /// Offer price. See `payment_token` for the actual value of each unit.
+    /// Offer price, for all `quantity` items (see [`Self::unit_price`]). See `payment_token` for the actual
+    /// value of each unit.
     #[serde(with = "u256_fromstr_radix_10")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub base_price: U256,
     /// Timestamp of when the offer was created.
     pub created_date: DateTime<Utc>,
     /// Timestamp of when the offer will expire.
     pub expiration_date: DateTime<Utc>,
     /// Creator of the offer.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Hash id of the listing.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub order_hash: H256,
     /// Token offered for payment.
     pub payment_token: PaymentToken,
-    /// Number of items on the offer. This is always `1` for ERC-721 tokens.
+    /// Number of items the offer is for; unlike item offers, this can be more than `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
     /// Taker of the offer.
-    #[serde(with = "address_fromjson_opt", default)]
-    pub taker: Option<Address>,
+    #[serde(default)]
+    pub taker: Option<Account>,
+    /// Seaport order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    pub protocol_data: Option<ProtocolData>,
+    /// Address of the Seaport contract of the order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub protocol_address: Option<Address>,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl CollectionOfferData {
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.base_price, self.quantity)
+    }
 }
 
 /// Payload data for [`Payload::TraitOffer`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TraitOfferData {
     /// Address of NFT contract.
     #[serde(with = "address_fromjson")]
+    #[cfg_attr(feature = "schemars", schemars(with = "address_fromjson::Inner"))]
     pub asset_contract_criteria: Address,
     /// Collection slug.
     pub collection: Collection,
-    /// Trait Criteria
+    /// Trait the offer is being made on.
     pub trait_criteria: TraitCriteria,
     /// Timestamp of when the offer was received.
     pub event_timestamp: DateTime<Utc>,
-    /// Offer price. See `payment_token` for the actual value of each unit.
+    /// Offer price, for all `quantity` items (see [`Self::unit_price`]). See `payment_token` for the actual
+    /// value of each unit.
     #[serde(with = "u256_fromstr_radix_10")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub base_price: U256,
     /// Timestamp of when the offer was created.
     pub created_date: DateTime<Utc>,
     /// Timestamp of when the offer will expire.
     pub expiration_date: DateTime<Utc>,
     /// Creator of the offer.
-    #[serde(with = "address_fromjson")]
-    pub maker: Address,
+    pub maker: Account,
     /// Hash id of the listing.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub order_hash: H256,
     /// Token offered for payment.
     pub payment_token: PaymentToken,
-    /// Number of items on the offer. This is always `1` for ERC-721 tokens.
+    /// Number of items the offer is for; unlike item offers, this can be more than `1` for ERC-721 tokens.
+    #[serde(with = "u64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub quantity: u64,
     /// Taker of the offer.
-    #[serde(with = "address_fromjson_opt", default)]
-    pub taker: Option<Address>,
+    #[serde(default)]
+    pub taker: Option<Account>,
+    /// Seaport order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    pub protocol_data: Option<ProtocolData>,
+    /// Address of the Seaport contract of the order, if OpenSea included it.
+    #[cfg(feature = "ethers")]
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub protocol_address: Option<Address>,
+    /// Fields that are not part of the schema, such as fields added by OpenSea after this version.
+    #[cfg(feature = "unknown-fields")]
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-/// Trait Criteria
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl TraitOfferData {
+    /// Returns the price of a single item, rounded down.
+    pub fn unit_price(&self) -> U256 {
+        unit_price(self.base_price, self.quantity)
+    }
+}
+
+/// Trait that a [`TraitOfferData`] is bidding on.
+///
+/// An item satisfies the criteria if it has an attribute with the matching `trait_type` and `trait_value`
+/// (as described by the [metadata standards](https://docs.opensea.io/docs/metadata-standards#attributes)).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TraitCriteria {
-    /// Trait Type
-    pub trait_name: String,
-    /// Value
+    /// Type of the trait (e.g. `Background`).
     pub trait_type: String,
+    /// Value of the trait (e.g. `Red`).
+    #[serde(rename = "trait_name", alias = "trait_value")]
+    pub trait_value: String,
 }
 
 /// Auctioning system used by the listing.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ListingType {
     /// [English](https://en.wikipedia.org/wiki/English_auction) (ascending).
@@ -558,7 +987,13 @@ mod address_fromjson {
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     #[derive(Serialize, Deserialize)]
-    struct Inner {
+    #[cfg_attr(
+        feature = "schemars",
+        derive(schemars::JsonSchema),
+        schemars(rename = "AssetContractCriteria")
+    )]
+    pub(super) struct Inner {
+        #[cfg_attr(feature = "schemars", schemars(with = "String"))]
         address: Address,
     }
 
@@ -577,35 +1012,28 @@ mod address_fromjson {
     }
 }
 
-mod address_fromjson_opt {
-    use ethers_core::abi::Address;
-    use serde::{Deserialize, Deserializer, Serialize, Serializer};
-
-    #[derive(Serialize, Deserialize)]
-    struct Inner {
-        address: Address,
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Address>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let inner: Option<Inner> = Deserialize::deserialize(deserializer)?;
-        Ok(inner.map(|i| i.address))
-    }
+/// An account on OpenSea.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Account {
+    /// Wallet address.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub address: Address,
+}
 
-    pub fn serialize<S>(value: &Option<Address>, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        value.map(|v| Inner { address: v }).serialize(serializer)
+/// Formatted as the abbreviated address (e.g. `0x6e3e…4fb4`).
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.address)
     }
 }
 
 /// Details of a transaction
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Transaction {
     /// Transaction hash
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub hash: H256,
     /// Timestamp of transaction
     pub timestamp: DateTime<Utc>,
@@ -613,13 +1041,16 @@ pub struct Transaction {
 
 /// Token used for payment.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct PaymentToken {
     /// Contract address
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub address: Address,
     /// Granularity of the token
     pub decimals: u64,
     /// Price of token (denominated in ETH)
     #[serde(with = "f64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub eth_price: f64,
     /// Name
     pub name: String,
@@ -627,9 +1058,56 @@ pub struct PaymentToken {
     pub symbol: String,
     /// Price of token (denominated in USD)
     #[serde(with = "f64_fromstring")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     pub usd_price: f64,
 }
 
+impl PaymentToken {
+    /// Formats an amount in the smallest unit of this token as a decimal number followed by the symbol,
+    /// e.g. `0.5 WETH`.
+    pub fn format_amount(&self, amount: U256) -> String {
+        let decimals = u32::try_from(self.decimals).unwrap_or(u32::MAX);
+        match format_units(amount, decimals) {
+            Ok(value) if value.contains('.') => {
+                let value = value.trim_end_matches('0').trim_end_matches('.');
+                format!("{} {}", value, self.symbol)
+            }
+            Ok(value) => format!("{} {}", value, self.symbol),
+            Err(_) => format!("{} {}", amount, self.symbol),
+        }
+    }
+}
+
+#[cfg(feature = "ethers")]
+impl From<&Account> for Address {
+    fn from(account: &Account) -> Self {
+        account.address
+    }
+}
+
+#[cfg(feature = "ethers")]
+impl From<&PaymentToken> for Address {
+    fn from(token: &PaymentToken) -> Self {
+        token.address
+    }
+}
+
+/// Converts to the contract address and token ID of the item.
+#[cfg(feature = "ethers")]
+impl From<&NftId> for (Address, U256) {
+    fn from(nft_id: &NftId) -> Self {
+        (nft_id.address, nft_id.id)
+    }
+}
+
+/// Converts to the transaction hash.
+#[cfg(feature = "ethers")]
+impl From<&Transaction> for H256 {
+    fn from(transaction: &Transaction) -> Self {
+        transaction.hash
+    }
+}
+
 // h/t: meetmangukiya (https://gist.github.com/meetmangukiya/40cad17bcb7d3196d33b072a3500fac7)
 mod u256_fromstr_radix_10 {
     use super::*;
@@ -668,6 +1146,40 @@ mod u256_fromstr_radix_10 {
     }
 }
 
+/// Divides the price of an order by its quantity. A quantity of `0` is treated as `1`.
+pub(crate) fn unit_price(total: U256, quantity: u64) -> U256 {
+    total / U256::from(quantity.max(1))
+}
+
+/// Accepts quantities sent as strings as well as numbers, and serializes them as numbers.
+mod u64_fromstring {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum StringInt {
+            Str(String),
+            U64(u64),
+        }
+
+        match StringInt::deserialize(deserializer)? {
+            StringInt::Str(s) => s.parse().map_err(D::Error::custom),
+            StringInt::U64(n) => Ok(n),
+        }
+    }
+
+    pub fn serialize<S>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(*value)
+    }
+}
+
 mod f64_fromstring {
     use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
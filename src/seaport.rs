@@ -0,0 +1,427 @@
+use ethers_core::{
+    abi::{
+        self, AbiArrayType, AbiDecode, AbiEncode, AbiError, AbiType, InvalidOutputType, ParamType,
+        Token, Tokenizable, TokenizableItem,
+    },
+    types::{Address, Bytes, H256, U256},
+};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Seaport order of a listing or offer, as included in the `protocol_data` of its payload.
+///
+/// The stream usually omits the signature of orders; it can be fetched from the fulfillment endpoints of the
+/// OpenSea REST API and set on the [`Order`] before fulfilling it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ProtocolData {
+    /// Parameters of the order.
+    pub parameters: OrderParameters,
+    /// Signature of the offerer, if included.
+    #[serde(default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub signature: Option<Bytes>,
+}
+
+impl ProtocolData {
+    /// Returns the order, with an empty signature if the signature was not included.
+    pub fn order(&self) -> Order {
+        Order {
+            parameters: self.parameters.clone(),
+            signature: self.signature.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Parameters of a Seaport order.
+///
+/// Item and order types are the values of the Seaport enums: items are native tokens (0), ERC-20 (1), ERC-721 (2),
+/// ERC-1155 (3), ERC-721 with criteria (4) or ERC-1155 with criteria (5); orders are full open (0), partial open
+/// (1), full restricted (2) or partial restricted (3).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OrderParameters {
+    /// Account that created the order.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub offerer: Address,
+    /// Zone that can restrict fulfillment.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub zone: Address,
+    /// Items offered by the offerer.
+    pub offer: Vec<OfferItem>,
+    /// Items that must be received for the order to be fulfilled.
+    pub consideration: Vec<ConsiderationItem>,
+    /// Type of the order.
+    pub order_type: u8,
+    /// Timestamp from which the order can be fulfilled.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub start_time: U256,
+    /// Timestamp until which the order can be fulfilled.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub end_time: U256,
+    /// Value passed to the zone.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub zone_hash: H256,
+    /// Salt of the order.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub salt: U256,
+    /// Conduit that offered items are transferred through.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub conduit_key: H256,
+    /// Number of consideration items when the order was signed.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub total_original_consideration_items: U256,
+    /// Counter of the offerer when the order was signed.
+    ///
+    /// The counter is part of the signed order components, but not of the parameters passed to Seaport, so it is
+    /// not ABI encoded, and is zero in decoded parameters.
+    #[serde(with = "uint", default)]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub counter: U256,
+}
+
+impl OrderParameters {
+    /// Returns the amount of native tokens (ETH on Ethereum) to send to fulfill the order: the sum of the larger of
+    /// the start and end amount of every native consideration item. Seaport refunds any excess.
+    pub fn native_amount(&self) -> U256 {
+        self.consideration
+            .iter()
+            .filter(|item| item.item_type == 0)
+            .fold(U256::zero(), |total, item| {
+                total.saturating_add(item.start_amount.max(item.end_amount))
+            })
+    }
+}
+
+/// Item offered by the offerer of a Seaport order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OfferItem {
+    /// Type of the item.
+    pub item_type: u8,
+    /// Contract of the item. Zero for native tokens.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub token: Address,
+    /// Token ID of the item, or the merkle root of token IDs for items with criteria.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub identifier_or_criteria: U256,
+    /// Amount at the start time.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub start_amount: U256,
+    /// Amount at the end time.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub end_amount: U256,
+}
+
+/// Item that must be received for a Seaport order to be fulfilled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ConsiderationItem {
+    /// Type of the item.
+    pub item_type: u8,
+    /// Contract of the item. Zero for native tokens.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub token: Address,
+    /// Token ID of the item, or the merkle root of token IDs for items with criteria.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub identifier_or_criteria: U256,
+    /// Amount at the start time.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub start_amount: U256,
+    /// Amount at the end time.
+    #[serde(with = "uint")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub end_amount: U256,
+    /// Account that receives the item.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub recipient: Address,
+}
+
+/// A signed Seaport order, as passed to `fulfillOrder`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    /// Parameters of the order.
+    pub parameters: OrderParameters,
+    /// Signature of the offerer. May be empty if the order was validated on chain.
+    pub signature: Bytes,
+}
+
+/// Call to `fulfillOrder` of Seaport, which fulfills an order in full.
+///
+/// The call is ABI encoded with [`AbiEncode::encode`], as the data of a transaction to the Seaport contract (the
+/// `protocol_address` of the payload) with a value of [`OrderParameters::native_amount`]:
+/// ```
+/// # use opensea_stream::{ethers_core::{abi::AbiEncode, types::{Address, TransactionRequest, H256}}, seaport::{FulfillOrderCall, ProtocolData}};
+/// # fn fulfill(protocol_address: Address, protocol_data: &ProtocolData) -> TransactionRequest {
+/// let call = FulfillOrderCall {
+///     order: protocol_data.order(),
+///     fulfiller_conduit_key: H256::zero(),
+/// };
+/// TransactionRequest::new()
+///     .to(protocol_address)
+///     .value(protocol_data.parameters.native_amount())
+///     .data(call.encode())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FulfillOrderCall {
+    /// The order to fulfill.
+    pub order: Order,
+    /// Conduit that the items of the fulfiller are transferred through, or zero to approve Seaport directly.
+    pub fulfiller_conduit_key: H256,
+}
+
+impl FulfillOrderCall {
+    /// Returns the function selector of `fulfillOrder`.
+    pub fn selector() -> [u8; 4] {
+        let params = [Order::param_type(), H256::param_type()];
+        abi::short_signature("fulfillOrder", &params)
+    }
+}
+
+impl AbiEncode for FulfillOrderCall {
+    fn encode(self) -> Vec<u8> {
+        let tokens = [
+            self.order.into_token(),
+            self.fulfiller_conduit_key.into_token(),
+        ];
+        [&Self::selector()[..], &abi::encode(&tokens)].concat()
+    }
+}
+
+impl AbiDecode for FulfillOrderCall {
+    fn decode(bytes: impl AsRef<[u8]>) -> Result<Self, AbiError> {
+        let bytes = bytes.as_ref();
+        let data = bytes
+            .strip_prefix(&Self::selector()[..])
+            .ok_or(AbiError::WrongSelector)?;
+        let mut tokens = abi::decode(&[Order::param_type(), H256::param_type()], data)?.into_iter();
+        let (Some(order), Some(key)) = (tokens.next(), tokens.next()) else {
+            return Err(InvalidOutputType("expected two arguments".to_owned()).into());
+        };
+        Ok(Self {
+            order: Order::from_token(order)?,
+            fulfiller_conduit_key: H256::from_token(key)?,
+        })
+    }
+}
+
+impl AbiType for OfferItem {
+    fn param_type() -> ParamType {
+        ParamType::Tuple(vec![
+            u8::param_type(),
+            Address::param_type(),
+            U256::param_type(),
+            U256::param_type(),
+            U256::param_type(),
+        ])
+    }
+}
+
+impl AbiArrayType for OfferItem {}
+
+impl Tokenizable for OfferItem {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let (item_type, token, identifier_or_criteria, start_amount, end_amount) =
+            Tokenizable::from_token(token)?;
+        Ok(Self {
+            item_type,
+            token,
+            identifier_or_criteria,
+            start_amount,
+            end_amount,
+        })
+    }
+
+    fn into_token(self) -> Token {
+        (
+            self.item_type,
+            self.token,
+            self.identifier_or_criteria,
+            self.start_amount,
+            self.end_amount,
+        )
+            .into_token()
+    }
+}
+
+impl TokenizableItem for OfferItem {}
+
+impl AbiType for ConsiderationItem {
+    fn param_type() -> ParamType {
+        ParamType::Tuple(vec![
+            u8::param_type(),
+            Address::param_type(),
+            U256::param_type(),
+            U256::param_type(),
+            U256::param_type(),
+            Address::param_type(),
+        ])
+    }
+}
+
+impl AbiArrayType for ConsiderationItem {}
+
+impl Tokenizable for ConsiderationItem {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let (item_type, token, identifier_or_criteria, start_amount, end_amount, recipient) =
+            Tokenizable::from_token(token)?;
+        Ok(Self {
+            item_type,
+            token,
+            identifier_or_criteria,
+            start_amount,
+            end_amount,
+            recipient,
+        })
+    }
+
+    fn into_token(self) -> Token {
+        (
+            self.item_type,
+            self.token,
+            self.identifier_or_criteria,
+            self.start_amount,
+            self.end_amount,
+            self.recipient,
+        )
+            .into_token()
+    }
+}
+
+impl TokenizableItem for ConsiderationItem {}
+
+impl AbiType for OrderParameters {
+    fn param_type() -> ParamType {
+        ParamType::Tuple(vec![
+            Address::param_type(),
+            Address::param_type(),
+            Vec::<OfferItem>::param_type(),
+            Vec::<ConsiderationItem>::param_type(),
+            u8::param_type(),
+            U256::param_type(),
+            U256::param_type(),
+            H256::param_type(),
+            U256::param_type(),
+            H256::param_type(),
+            U256::param_type(),
+        ])
+    }
+}
+
+impl Tokenizable for OrderParameters {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let (
+            offerer,
+            zone,
+            offer,
+            consideration,
+            order_type,
+            start_time,
+            end_time,
+            zone_hash,
+            salt,
+            conduit_key,
+            total_original_consideration_items,
+        ) = Tokenizable::from_token(token)?;
+        Ok(Self {
+            offerer,
+            zone,
+            offer,
+            consideration,
+            order_type,
+            start_time,
+            end_time,
+            zone_hash,
+            salt,
+            conduit_key,
+            total_original_consideration_items,
+            counter: U256::zero(),
+        })
+    }
+
+    fn into_token(self) -> Token {
+        (
+            self.offerer,
+            self.zone,
+            self.offer,
+            self.consideration,
+            self.order_type,
+            self.start_time,
+            self.end_time,
+            self.zone_hash,
+            self.salt,
+            self.conduit_key,
+            self.total_original_consideration_items,
+        )
+            .into_token()
+    }
+}
+
+impl AbiType for Order {
+    fn param_type() -> ParamType {
+        ParamType::Tuple(vec![OrderParameters::param_type(), Bytes::param_type()])
+    }
+}
+
+impl Tokenizable for Order {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let (parameters, signature) = Tokenizable::from_token(token)?;
+        Ok(Self {
+            parameters,
+            signature,
+        })
+    }
+
+    fn into_token(self) -> Token {
+        (self.parameters, self.signature).into_token()
+    }
+}
+
+/// (De)serializes a `U256` from a JSON number, a decimal string or a `0x`-prefixed hexadecimal string, as OpenSea
+/// uses all three in protocol data. Serialized as a decimal string.
+mod uint {
+    use ethers_core::types::U256;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S>(value: &U256, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<U256, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Number(n) => n
+                .as_u64()
+                .map(U256::from)
+                .ok_or_else(|| Error::custom(format!("invalid integer `{}`", n))),
+            Value::String(s) => {
+                let parsed = match s.strip_prefix("0x") {
+                    Some(hex) => U256::from_str_radix(hex, 16).ok(),
+                    None => U256::from_dec_str(&s).ok(),
+                };
+                parsed.ok_or_else(|| Error::custom(format!("invalid integer `{}`", s)))
+            }
+            other => Err(Error::custom(format!("expected an integer, got {}", other))),
+        }
+    }
+}
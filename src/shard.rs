@@ -0,0 +1,408 @@
+use crate::{
+    client::register_error, schema::StreamEvent, subscribe::leave, Client, ClientError, Collection,
+    Event, EventStream, LagPolicy, Network,
+};
+use phyllo::{
+    channel::{ChannelBuilder, ChannelHandler},
+    message::Message,
+};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        watch,
+    },
+    task::JoinHandle,
+    time,
+};
+use tracing::warn;
+
+/// Number of points of every socket on the hash ring.
+const POINTS_PER_SOCKET: u32 = 64;
+
+type StreamMessage = Message<Collection, Event, Value, StreamEvent>;
+
+/// Subscriptions spread over several sockets, with their events merged into one stream.
+///
+/// Collections are assigned to sockets by consistent hashing of their slug, so that adding or losing a socket only
+/// moves the collections of that socket. [`Collection::All`] is a single channel, and is always on a single socket.
+///
+/// When a socket dies (for example, after it gave up reconnecting), [`Shards::rebalance`] subscribes to its
+/// collections again on the remaining sockets. [`Shards::supervise`] does so as soon as a dead socket is noticed.
+///
+/// Events that the merged stream misses, including those missed while forwarding the events of a subscription to
+/// it, are handled according to the [`LagPolicy`] of the shards.
+/// ```no_run
+/// # use opensea_stream::{shard::Shards, Collection, Network};
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut shards = Shards::connect(Network::Mainnet, "YOUR_API_KEY_HERE", 4).await;
+/// for slug in ["wandernauts", "boredapeyachtclub", "azuki"] {
+///     shards.subscribe(Collection::Collection(slug.to_string())).await?;
+/// }
+///
+/// let mut events = shards.stream();
+/// tokio::spawn(async move { shards.supervise(Duration::from_secs(5)).await });
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Shards {
+    clients: Vec<Client>,
+    ring: Ring,
+    subscriptions: HashMap<Collection, Subscription>,
+    tx: broadcast::Sender<StreamMessage>,
+    broadcast_buffer: usize,
+    lag_policy: LagPolicy,
+    /// Total number of events missed by the forward tasks.
+    missed: watch::Sender<u64>,
+}
+
+#[derive(Debug)]
+struct Subscription {
+    shard: usize,
+    handler: ChannelHandler<Collection, Event, Value, StreamEvent>,
+    forward: JoinHandle<()>,
+}
+
+impl Shards {
+    /// Constructs a new `Shards` over the sockets of `clients`, with a buffer of 128 messages for the merged stream
+    /// and each subscription.
+    ///
+    /// Subscriptions of the shards are not subscriptions of the clients: they are not listed by
+    /// [`Client::subscriptions`], and are not recorded in [`Client::stats`].
+    ///
+    /// # Panics
+    /// Panics if `clients` is empty.
+    pub fn new(clients: Vec<Client>) -> Self {
+        assert!(!clients.is_empty(), "shards need at least one socket");
+        Self {
+            ring: Ring::new(clients.len()),
+            clients,
+            subscriptions: HashMap::new(),
+            tx: broadcast::channel(128).0,
+            broadcast_buffer: 128,
+            lag_policy: LagPolicy::default(),
+            missed: watch::channel(0).0,
+        }
+    }
+
+    /// Opens `n` sockets to `network` with [`Client::connect`] and constructs a new `Shards` over them.
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    pub async fn connect(network: Network, token: &str, n: usize) -> Self {
+        let mut clients = Vec::with_capacity(n);
+        for _ in 0..n {
+            clients.push(Client::connect(network, token).await);
+        }
+        Self::new(clients)
+    }
+
+    /// Sets the buffer size of the merged stream and of the broadcast channel of each subscription.
+    ///
+    /// This should be set before subscribing, as events of earlier subscriptions are not delivered to the new merged
+    /// stream.
+    pub fn broadcast_buffer(mut self, broadcast_buffer: usize) -> Self {
+        self.broadcast_buffer = broadcast_buffer;
+        self.tx = broadcast::channel(broadcast_buffer).0;
+        self
+    }
+
+    /// Sets what merged streams do when events are missed, by them or while forwarding the events of a
+    /// subscription to them.
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+
+    /// Subscribes to all the events of a particular [`Collection`] on the socket it is assigned to.
+    ///
+    /// Subscribing to a collection that is already subscribed to does nothing.
+    pub async fn subscribe(&mut self, collection: Collection) -> Result<(), ClientError> {
+        #[cfg(feature = "http")]
+        let collection = match self.clients.first() {
+            Some(client) => client.resolve(collection).await?,
+            None => collection,
+        };
+        if self.subscriptions.contains_key(&collection) {
+            return Ok(());
+        }
+        let shard = self.ring.get(&collection.to_string());
+        self.join(collection, shard).await
+    }
+
+    /// Unsubscribes from a [`Collection`]. See [`Subscription::unsubscribe`](crate::Subscription::unsubscribe).
+    pub async fn unsubscribe(&mut self, collection: &Collection) -> Result<(), ClientError> {
+        let subscription = self
+            .subscriptions
+            .remove(collection)
+            .ok_or_else(|| ClientError::NotSubscribed(collection.clone()))?;
+        subscription.forward.abort();
+        leave(subscription.handler).await
+    }
+
+    /// Returns the events of every subscription, merged into one stream.
+    pub fn stream(&self) -> EventStream {
+        EventStream::from_receiver(self.tx.subscribe())
+            .lag_policy(self.lag_policy)
+            .upstream_missed(self.missed.subscribe())
+    }
+
+    /// Returns the index of the socket that `collection` is subscribed on, if it is subscribed to.
+    pub fn shard_of(&self, collection: &Collection) -> Option<usize> {
+        self.subscriptions.get(collection).map(|s| s.shard)
+    }
+
+    /// Returns the collections that are subscribed to.
+    pub fn collections(&self) -> impl Iterator<Item = &Collection> {
+        self.subscriptions.keys()
+    }
+
+    /// Returns the clients, in the order they were given.
+    pub fn clients(&self) -> &[Client] {
+        &self.clients
+    }
+
+    /// Removes the sockets that are no longer alive from the ring, and subscribes to their collections again on the
+    /// remaining sockets, returning the collections that could not be subscribed to again.
+    ///
+    /// Collections that could not be moved are no longer subscribed to. If no socket is alive, nothing is moved.
+    pub async fn rebalance(&mut self) -> Vec<(Collection, ClientError)> {
+        for (shard, client) in self.clients.iter().enumerate() {
+            if self.ring.contains(shard) && !client.alive().await {
+                warn!(shard, "socket is no longer alive, moving its collections");
+                self.ring.remove(shard);
+            }
+        }
+        if self.ring.is_empty() {
+            return Vec::new();
+        }
+
+        let moved: Vec<_> = self
+            .subscriptions
+            .iter()
+            .filter(|(_, s)| !self.ring.contains(s.shard))
+            .map(|(collection, _)| collection.clone())
+            .collect();
+
+        let mut failed = Vec::new();
+        for collection in moved {
+            if let Some(subscription) = self.subscriptions.remove(&collection) {
+                subscription.forward.abort();
+            }
+            let shard = self.ring.get(&collection.to_string());
+            if let Err(e) = self.join(collection.clone(), shard).await {
+                failed.push((collection, e));
+            }
+        }
+        failed
+    }
+
+    /// Checks every `interval` whether sockets died, and [rebalances](Shards::rebalance) their collections onto the
+    /// remaining sockets, until no socket is alive.
+    ///
+    /// Collections that could not be subscribed to again are logged as warnings with
+    /// [`tracing`](https://crates.io/crates/tracing), and are no longer subscribed to.
+    pub async fn supervise(&mut self, interval: Duration) {
+        let mut check = time::interval(interval);
+        check.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+        while !self.ring.is_empty() {
+            check.tick().await;
+            for (collection, e) in self.rebalance().await {
+                warn!(%collection, error = %e, "could not move collection to another socket");
+            }
+        }
+        warn!("no socket is alive, stopping supervision");
+    }
+
+    /// Joins the channel of `collection` on a socket, forwarding its messages to the merged stream.
+    async fn join(&mut self, collection: Collection, shard: usize) -> Result<(), ClientError> {
+        let channel_builder =
+            ChannelBuilder::new(collection.clone()).broadcast_buffer(self.broadcast_buffer);
+        let (handler, mut receiver) = self.clients[shard]
+            .socket
+            .channel(channel_builder)
+            .await
+            .map_err(|e| register_error(e, &collection))?;
+
+        let tx = self.tx.clone();
+        let missed = self.missed.clone();
+        let forward = tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    // Sending fails while no stream is receiving, which is not an error.
+                    Ok(message) => {
+                        let _ = tx.send(message);
+                    }
+                    // Merged streams report these according to their lag policy.
+                    Err(RecvError::Lagged(n)) => missed.send_modify(|missed| *missed += n),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        self.subscriptions.insert(
+            collection,
+            Subscription {
+                shard,
+                handler,
+                forward,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Drop for Shards {
+    fn drop(&mut self) {
+        for subscription in self.subscriptions.values() {
+            subscription.forward.abort();
+        }
+    }
+}
+
+/// Consistent hash ring of sockets.
+#[derive(Debug)]
+struct Ring {
+    points: BTreeMap<u64, usize>,
+}
+
+impl Ring {
+    fn new(n: usize) -> Self {
+        let points = (0..n)
+            .flat_map(|shard| {
+                (0..POINTS_PER_SOCKET).map(move |i| (hash(&format!("{}-{}", shard, i)), shard))
+            })
+            .collect();
+        Self { points }
+    }
+
+    /// Returns the socket of `key`. The ring must not be empty.
+    fn get(&self, key: &str) -> usize {
+        let hash = hash(key);
+        let (_, shard) = self
+            .points
+            .range(hash..)
+            .next()
+            .or_else(|| self.points.iter().next())
+            .expect("hash ring is empty");
+        *shard
+    }
+
+    fn contains(&self, shard: usize) -> bool {
+        self.points.values().any(|s| *s == shard)
+    }
+
+    fn remove(&mut self, shard: usize) {
+        self.points.retain(|_, s| *s != shard);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// Hashes a key onto the ring, stably across processes and versions unlike the hasher of the standard library.
+///
+/// 64-bit FNV-1a alone barely changes the high bits for keys that only differ in their last bytes (such as
+/// `0-1` and `0-2`), which would put all the points of a socket next to each other, so it is followed by the
+/// finalizer of MurmurHash3.
+fn hash(key: &str) -> u64 {
+    let mut hash = fnv1a(key);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+/// 64-bit FNV-1a.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Vec<String> {
+        (0..1000).map(|i| format!("collection:{}", i)).collect()
+    }
+
+    #[test]
+    fn placement_is_consistent() {
+        let (a, b) = (Ring::new(4), Ring::new(4));
+        for key in keys() {
+            assert_eq!(a.get(&key), b.get(&key));
+            assert!(a.get(&key) < 4);
+        }
+        // The hash is fixed, so placement does not change between processes either.
+        assert_eq!(fnv1a("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash("collection:wandernauts"), 0xb0b5_37e5_9299_b3bd);
+    }
+
+    #[test]
+    fn keys_are_spread_over_every_socket() {
+        let ring = Ring::new(4);
+        let mut counts = [0; 4];
+        for key in keys() {
+            counts[ring.get(&key)] += 1;
+        }
+        assert!(counts.iter().all(|&n| n > 150), "{:?}", counts);
+    }
+
+    #[test]
+    fn only_keys_of_a_removed_socket_move() {
+        let mut ring = Ring::new(4);
+        let before: Vec<_> = keys().iter().map(|key| ring.get(key)).collect();
+        ring.remove(2);
+        assert!(!ring.contains(2));
+        assert!(ring.contains(1));
+
+        for (key, before) in keys().iter().zip(before) {
+            let after = ring.get(key);
+            assert_ne!(after, 2);
+            if before != 2 {
+                assert_eq!(after, before, "{} moved", key);
+            }
+        }
+    }
+
+    #[test]
+    fn removing_every_socket_empties_the_ring() {
+        let mut ring = Ring::new(2);
+        ring.remove(0);
+        assert!(!ring.is_empty());
+        assert!(keys().iter().all(|key| ring.get(key) == 1));
+        ring.remove(1);
+        assert!(ring.is_empty());
+    }
+
+    #[tokio::test]
+    async fn events_missed_while_forwarding_are_reported_by_merged_streams() {
+        let (tx, rx) = broadcast::channel::<StreamMessage>(4);
+        let (missed, _) = watch::channel(0);
+        // Events missed before the stream was created are not reported.
+        missed.send_modify(|missed| *missed += 2);
+        let mut stream = EventStream::from_receiver(rx).upstream_missed(missed.subscribe());
+        missed.send_modify(|missed| *missed += 3);
+        drop(tx);
+
+        assert!(matches!(
+            stream.recv().await,
+            Some(Err(crate::Error::MissedEvents(3)))
+        ));
+        assert!(stream.recv().await.is_none());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{schema::StreamEvent, Collection, EventStream};
+use phyllo::message::{Event as MessageEvent, Message, Payload};
+use std::{
+    fs,
+    future::Future,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{broadcast, watch};
+
+/// Source of time for a [`Simulator`].
+pub trait Clock: Send + Sync + 'static {
+    /// Waits for `duration` to pass.
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send;
+}
+
+/// A [`Clock`] that uses [`tokio::time`].
+///
+/// Tokio's time can itself be paused and advanced in tests (see `tokio::time::pause`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// A [`Clock`] that only moves when it is advanced, for deterministic replays.
+///
+/// The clock is cheap to clone; all clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<watch::Sender<Duration>>,
+}
+
+impl ManualClock {
+    /// Constructs a new `ManualClock` at time zero.
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(watch::Sender::new(Duration::ZERO)),
+        }
+    }
+
+    /// Moves the clock forward by `duration`, waking up sleeps that have completed.
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+
+    /// Returns the time that has passed since the clock was constructed.
+    pub fn elapsed(&self) -> Duration {
+        *self.now.borrow()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    async fn sleep(&self, duration: Duration) {
+        let mut now = self.now.subscribe();
+        let until = *now.borrow() + duration;
+        // The sender is kept alive by `self`, so this cannot fail.
+        let _ = now.wait_for(|now| *now >= until).await;
+    }
+}
+
+/// Replays recorded events as an [`EventStream`], with their original inter-arrival times or at a multiple of
+/// their original speed.
+///
+/// Recordings are in the capture format written by [`FileSink`](crate::sinks::file::FileSink) with
+/// [`Format::Ndjson`](crate::sinks::file::Format::Ndjson) and no fields set: one [`StreamEvent`] per line.
+/// Events are replayed in the order they were recorded, and the time between two events is the difference of
+/// their `sent_at`. Unlike a live subscription, a replay waits for the consumer instead of dropping events.
+/// ```no_run
+/// # use opensea_stream::simulator::Simulator;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut events = Simulator::from_capture("events.ndjson")?.speed(10.0).run();
+///
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Simulator<C = TokioClock> {
+    events: Vec<StreamEvent>,
+    speed: f64,
+    buffer: usize,
+    clock: C,
+}
+
+impl Simulator {
+    /// Constructs a new `Simulator` which replays `events` at their original speed.
+    pub fn new(events: Vec<StreamEvent>) -> Self {
+        Self {
+            events,
+            speed: 1.0,
+            buffer: 128,
+            clock: TokioClock,
+        }
+    }
+
+    /// Constructs a new `Simulator` which replays the events of a capture file at their original speed.
+    ///
+    /// Empty lines are skipped; any other line that is not an event is an error.
+    pub fn from_capture(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut events = Vec::new();
+        for line in BufReader::new(fs::File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                events.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self::new(events))
+    }
+}
+
+impl<C: Clock> Simulator<C> {
+    /// Sets the speed of the replay as a multiple of the original speed: `2.0` replays twice as fast, and
+    /// `f64::INFINITY` replays without waiting.
+    ///
+    /// # Panics
+    /// Panics if `speed` is not positive.
+    pub fn speed(mut self, speed: f64) -> Self {
+        assert!(speed > 0.0, "speed must be positive");
+        self.speed = speed;
+        self
+    }
+
+    /// Sets the buffer size of the broadcast channel of the stream.
+    pub fn buffer(mut self, buffer: usize) -> Self {
+        self.buffer = buffer;
+        self
+    }
+
+    /// Sets the clock that the replay waits on.
+    pub fn clock<D: Clock>(self, clock: D) -> Simulator<D> {
+        Simulator {
+            events: self.events,
+            speed: self.speed,
+            buffer: self.buffer,
+            clock,
+        }
+    }
+
+    /// Spawns a task that replays the events, returning the stream they are delivered on.
+    ///
+    /// The stream ends after the last event. Must be called from within a Tokio runtime.
+    pub fn run(self) -> EventStream {
+        let (tx, rx) = broadcast::channel(self.buffer);
+
+        tokio::spawn(async move {
+            let mut previous = None;
+            for event in self.events {
+                if let Some(previous) = previous.replace(event.sent_at) {
+                    let gap = (event.sent_at - previous).to_std().unwrap_or_default();
+                    if self.speed.is_finite() && !gap.is_zero() {
+                        self.clock.sleep(gap.div_f64(self.speed)).await;
+                    }
+                }
+
+                // Wait for the consumer rather than dropping events.
+                while tx.len() >= self.buffer && tx.receiver_count() > 0 {
+                    tokio::task::yield_now().await;
+                }
+
+                let collection = Collection::Collection(event.payload.collection().0.clone());
+                let message = Message::new(
+                    0,
+                    0,
+                    collection,
+                    MessageEvent::Event(event.payload.event()),
+                    Some(Payload::Custom(event)),
+                );
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        EventStream::from_receiver(rx)
+    }
+}
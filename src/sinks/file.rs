@@ -0,0 +1,293 @@
+use super::select;
+use crate::{schema::StreamEvent, EventSource};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Number of events that [`FileSink::run`] queues for the file while it is being written to.
+const QUEUE: usize = 1024;
+
+/// Format of the records written by a [`FileSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// One JSON object per line.
+    Ndjson,
+    /// Comma-separated values, with a header row at the start of every file.
+    Csv,
+}
+
+/// Builder for a [`FileSink`].
+#[derive(Debug, Clone)]
+pub struct FileSinkBuilder {
+    path: PathBuf,
+    format: Format,
+    fields: Vec<String>,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+}
+
+impl FileSinkBuilder {
+    /// Constructs a new `FileSinkBuilder` which appends newline-delimited JSON to the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format: Format::Ndjson,
+            fields: Vec::new(),
+            max_bytes: None,
+            max_age: None,
+        }
+    }
+
+    /// Sets the format of the records.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the fields written for each event, as dot-separated paths into the JSON representation of a
+    /// [`StreamEvent`] (e.g. `event_type`, `payload.collection.slug`, `payload.base_price`).
+    ///
+    /// Fields missing from an event are written as `null` (or an empty column).
+    /// If no fields are set, newline-delimited JSON contains the whole event and CSV contains [`DEFAULT_CSV_FIELDS`].
+    pub fn fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Rotates the file once it has grown past `max_bytes`.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Rotates the file once it has been written to for longer than `max_age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Opens the file, creating it if it does not exist.
+    pub fn build(self) -> io::Result<FileSink> {
+        let fields = match (self.fields.is_empty(), self.format) {
+            (true, Format::Csv) => DEFAULT_CSV_FIELDS.iter().map(|f| f.to_string()).collect(),
+            _ => self.fields,
+        };
+
+        let (writer, written) = open(&self.path)?;
+        let mut sink = FileSink {
+            path: self.path,
+            format: self.format,
+            fields,
+            max_bytes: self.max_bytes,
+            max_age: self.max_age,
+            writer,
+            written,
+            records: 0,
+            opened_at: Instant::now(),
+        };
+        if sink.written == 0 {
+            sink.write_header()?;
+        }
+        Ok(sink)
+    }
+}
+
+/// Fields written by a CSV [`FileSink`] if none are configured.
+pub const DEFAULT_CSV_FIELDS: &[&str] = &[
+    "sent_at",
+    "event_type",
+    "payload.collection.slug",
+    "payload.item.nft_id",
+    "payload.event_timestamp",
+];
+
+/// Appends events to a file, rotating it according to its size or age.
+///
+/// When a file is rotated, it is renamed to include the time of rotation (`events.ndjson` becomes
+/// `events.20220719T184203123.ndjson`, or `events.20220719T184203123-1.ndjson` if that name is taken) and a new file
+/// is started at the original path.
+/// Writes are buffered; call [`FileSink::flush`] to ensure they reach the disk. [`FileSink::write`] blocks on the
+/// file system, so in async code use [`FileSink::run`], which writes on the blocking thread pool of Tokio.
+/// ```no_run
+/// # use opensea_stream::sinks::file::{FileSinkBuilder, Format};
+/// # use std::time::Duration;
+/// # fn main() -> std::io::Result<()> {
+/// let sink = FileSinkBuilder::new("sales.csv")
+///     .format(Format::Csv)
+///     .fields(["sent_at", "payload.collection.slug", "payload.sale_price"])
+///     .max_age(Duration::from_secs(60 * 60))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    format: Format,
+    fields: Vec<String>,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    writer: BufWriter<File>,
+    written: u64,
+    records: u64,
+    opened_at: Instant,
+}
+
+impl FileSink {
+    /// Constructs a new [`FileSinkBuilder`].
+    pub fn builder(path: impl Into<PathBuf>) -> FileSinkBuilder {
+        FileSinkBuilder::new(path)
+    }
+
+    /// Appends an event, rotating the file beforehand if required.
+    pub fn write(&mut self, event: &StreamEvent) -> io::Result<()> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+
+        let event = serde_json::to_value(event)?;
+        let mut line = match self.format {
+            Format::Ndjson if self.fields.is_empty() => serde_json::to_string(&event)?,
+            Format::Ndjson => {
+                let record: Map<String, Value> = self
+                    .fields
+                    .iter()
+                    .map(|f| (f.clone(), select(&event, f).cloned().unwrap_or(Value::Null)))
+                    .collect();
+                serde_json::to_string(&record)?
+            }
+            Format::Csv => csv_row(self.fields.iter().map(|f| match select(&event, f) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                Some(v) => v.to_string(),
+            })),
+        };
+        line.push('\n');
+
+        self.write_line(&line)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Flushes buffered writes to the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Writes every event received from `events` until it ends, or writing fails.
+    ///
+    /// Events are written on the blocking thread pool of Tokio (see [`tokio::task::spawn_blocking`]), so that the
+    /// runtime is not blocked by the file system. Errors of the source, such as missed events, are logged as warnings.
+    /// The file is flushed before returning.
+    pub async fn run(mut self, mut events: impl EventSource) -> io::Result<()> {
+        let (tx, mut rx) = mpsc::channel::<StreamEvent>(QUEUE);
+        let writer = tokio::task::spawn_blocking(move || {
+            while let Some(event) = rx.blocking_recv() {
+                self.write(&event)?;
+            }
+            self.flush()
+        });
+
+        while let Some(event) = events.recv().await {
+            match event {
+                Ok(event) => {
+                    // The writer only stops early if writing failed, which it returns below.
+                    if tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!(error = %e, "file sink received an error, skipping it"),
+            }
+        }
+        drop(tx);
+        writer.await.map_err(io::Error::other)?
+    }
+
+    fn should_rotate(&self) -> bool {
+        // An existing file that is already too large is rotated, but a file is never rotated for age before it has
+        // received any events.
+        self.max_bytes.is_some_and(|max| self.written >= max)
+            || self
+                .max_age
+                .is_some_and(|max| self.records > 0 && self.opened_at.elapsed() >= max)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        fs::rename(&self.path, rotated_path(&self.path))?;
+
+        let (writer, written) = open(&self.path)?;
+        self.writer = writer;
+        self.written = written;
+        self.records = 0;
+        self.opened_at = Instant::now();
+        self.write_header()
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        match self.format {
+            Format::Ndjson => Ok(()),
+            Format::Csv => {
+                let mut header = csv_row(self.fields.iter().cloned());
+                header.push('\n');
+                self.write_line(&header)
+            }
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Opens a file for appending, returning it with its current length.
+fn open(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let len = file.metadata()?.len();
+    Ok((BufWriter::new(file), len))
+}
+
+/// Inserts the current time before the extension of `path`, followed by a counter if a file already has that name.
+fn rotated_path(path: &Path) -> PathBuf {
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%S%3f").to_string();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().map(|ext| ext.to_string_lossy());
+    (0..)
+        .map(|n| {
+            let suffix = match n {
+                0 => timestamp.clone(),
+                n => format!("{}-{}", timestamp, n),
+            };
+            let name = match &ext {
+                Some(ext) => format!("{}.{}.{}", stem, suffix, ext),
+                None => format!("{}.{}", stem, suffix),
+            };
+            path.with_file_name(name)
+        })
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Joins values into a CSV row, quoting them where required.
+fn csv_row(values: impl Iterator<Item = String>) -> String {
+    values
+        .map(|v| match v.contains([',', '"', '\n', '\r']) {
+            true => format!("\"{}\"", v.replace('"', "\"\"")),
+            false => v,
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,75 @@
+use serde_json::Value;
+
+/// Appending events to newline-delimited JSON or CSV files.
+pub mod file;
+/// Publishing events to an MQTT broker.
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+/// Posting events to Discord or Slack webhooks.
+#[cfg(feature = "notify")]
+pub mod notify;
+
+/// Looks up a dot-separated path in a JSON value.
+fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, key| v.get(key))
+}
+
+/// Replaces the placeholders of `template` with fields of `event`, passing every replacement through `escape`.
+#[cfg(any(feature = "mqtt", feature = "notify"))]
+fn render(template: &str, event: &Value, escape: impl Fn(String) -> String) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let (brace, after) = (&rest[start..=start], &rest[start + 1..]);
+
+        if let Some(after) = after.strip_prefix(brace) {
+            out.push_str(brace);
+            rest = after;
+        } else if let (true, Some(end)) = (brace == "{", after.find('}')) {
+            out.push_str(&escape(placeholder(&after[..end], event)));
+            rest = &after[end + 1..];
+        } else {
+            out.push_str(brace);
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(any(feature = "mqtt", feature = "notify"))]
+fn placeholder(placeholder: &str, event: &Value) -> String {
+    use ethers_core::{types::U256, utils::format_units};
+
+    let (path, modifier) = match placeholder.split_once('|') {
+        Some((path, modifier)) => (path.trim(), Some(modifier.trim())),
+        None => (placeholder.trim(), None),
+    };
+
+    let value = match select(event, path) {
+        None | Some(Value::Null) => return String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+    };
+
+    let decimals = modifier.and_then(|m| m.strip_prefix("units:"));
+    match decimals.and_then(|d| d.parse::<u32>().ok()) {
+        Some(decimals) => U256::from_dec_str(&value)
+            .ok()
+            .and_then(|amount| format_units(amount, decimals).ok())
+            .map(|s| trim_zeros(&s).to_owned())
+            .unwrap_or(value),
+        None => value,
+    }
+}
+
+/// Trims trailing zeros of the fractional part of a decimal number.
+#[cfg(any(feature = "mqtt", feature = "notify"))]
+fn trim_zeros(s: &str) -> &str {
+    match s.contains('.') {
+        true => s.trim_end_matches('0').trim_end_matches('.'),
+        false => s,
+    }
+}
@@ -0,0 +1,169 @@
+use super::render;
+use crate::{schema::StreamEvent, EventSource};
+use rumqttc::{AsyncClient, ClientError, MqttOptions, QoS};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Topic template used by an [`MqttSink`] if none is configured.
+pub const DEFAULT_TOPIC: &str = "opensea/{collection}/{event_type}";
+
+/// Time to wait before reconnecting after the connection to the broker fails.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Builder for an [`MqttSink`].
+#[derive(Debug, Clone)]
+pub struct MqttSinkBuilder {
+    options: MqttOptions,
+    topic: String,
+    qos: QoS,
+    retain: bool,
+    capacity: usize,
+}
+
+impl MqttSinkBuilder {
+    /// Constructs a new `MqttSinkBuilder` which connects to the broker with `options`, and publishes every event to
+    /// [`DEFAULT_TOPIC`] at most once, without retaining it.
+    pub fn new(options: MqttOptions) -> Self {
+        Self {
+            options,
+            topic: DEFAULT_TOPIC.to_owned(),
+            qos: QoS::AtMostOnce,
+            retain: false,
+            capacity: 128,
+        }
+    }
+
+    /// Sets the template of topics.
+    ///
+    /// Placeholders are the same as in the templates of `NotifySink`: dot-separated paths into the JSON
+    /// representation of a [`StreamEvent`] in braces, such as `{payload.item.chain.name}`, with `{collection}` as a
+    /// shorthand for the slug of the collection. `/`, `+` and `#` in replacements are replaced with `_`, so that a field cannot add topic levels
+    /// or wildcards.
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = topic.into();
+        self
+    }
+
+    /// Sets the quality of service of published events.
+    pub fn qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// Sets whether the broker retains the last event of every topic for new subscribers.
+    pub fn retain(mut self, retain: bool) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    /// Sets how many events can be waiting to be sent to the broker before publishing waits.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Builds the `MqttSink`, spawning the task that keeps the connection to the broker.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn build(self) -> MqttSink {
+        let (client, mut event_loop) = AsyncClient::new(self.options, self.capacity);
+        let connection = tokio::spawn(async move {
+            loop {
+                // The event loop reconnects on the next poll after an error.
+                if let Err(e) = event_loop.poll().await {
+                    warn!(error = %e, "mqtt connection failed, reconnecting");
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        });
+        MqttSink {
+            client,
+            connection,
+            topic: self.topic,
+            qos: self.qos,
+            retain: self.retain,
+        }
+    }
+}
+
+/// Publishes events to an MQTT broker as JSON, on topics formatted with a template.
+///
+/// The connection to the broker is kept by a background task, which reconnects after errors (logged with
+/// [`tracing`](https://crates.io/crates/tracing)); events published while disconnected are queued up to the
+/// configured capacity. Events still queued when the sink is dropped are lost.
+/// ```no_run
+/// # use opensea_stream::{rumqttc::{MqttOptions, QoS}, sinks::mqtt::MqttSinkBuilder, Client, Collection, Network};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let sink = MqttSinkBuilder::new(MqttOptions::new("opensea-stream", "localhost", 1883))
+///     .topic("opensea/{payload.item.chain.name}/{collection}/{event_type}")
+///     .qos(QoS::AtLeastOnce)
+///     .build();
+///
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let subscription = client.subscribe(Collection::All).await?;
+/// sink.run(subscription).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MqttSink {
+    client: AsyncClient,
+    connection: JoinHandle<()>,
+    topic: String,
+    qos: QoS,
+    retain: bool,
+}
+
+impl MqttSink {
+    /// Constructs a new [`MqttSinkBuilder`].
+    pub fn builder(options: MqttOptions) -> MqttSinkBuilder {
+        MqttSinkBuilder::new(options)
+    }
+
+    /// Formats the topic of an event with the template of the sink.
+    pub fn topic(&self, event: &StreamEvent) -> String {
+        topic(&self.topic, event)
+    }
+
+    /// Publishes an event, waiting if the queue of the connection is full.
+    pub async fn publish(&self, event: &StreamEvent) -> Result<(), ClientError> {
+        let payload = serde_json::to_vec(event).unwrap_or_default();
+        self.client
+            .publish(self.topic(event), self.qos, self.retain, payload)
+            .await
+    }
+
+    /// Publishes every event received from `events`, until it ends.
+    ///
+    /// Errors of the source, such as missed events, are logged as warnings.
+    pub async fn run(self, mut events: impl EventSource) -> Result<(), ClientError> {
+        while let Some(event) = events.recv().await {
+            match event {
+                Ok(event) => self.publish(&event).await?,
+                Err(e) => warn!(error = %e, "mqtt sink received an error, skipping it"),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MqttSink {
+    fn drop(&mut self) {
+        self.connection.abort();
+    }
+}
+
+/// Formats the topic of an event with `template`.
+fn topic(template: &str, event: &StreamEvent) -> String {
+    let mut value = serde_json::to_value(event).unwrap_or_default();
+    if let Value::Object(fields) = &mut value {
+        let slug = event.payload.collection().0.clone();
+        fields.insert("collection".to_owned(), Value::String(slug));
+    }
+    render(template, &value, |level| {
+        level.replace(['/', '+', '#'], "_")
+    })
+}
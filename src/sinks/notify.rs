@@ -0,0 +1,246 @@
+use super::render;
+use crate::{
+    schema::{Payload, StreamEvent},
+    EventSource,
+};
+use ethers_core::types::{Address, U256};
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use serde_json::json;
+use std::{fmt, time::Duration};
+use tracing::warn;
+use url::Url;
+
+/// Service that a [`NotifySink`] posts to, which determines the format of the webhook request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    /// A Discord [webhook](https://discord.com/developers/docs/resources/webhook#execute-webhook).
+    Discord,
+    /// A Slack [incoming webhook](https://api.slack.com/messaging/webhooks).
+    Slack,
+}
+
+/// Template used by a [`NotifySink`] if none is configured.
+pub const DEFAULT_TEMPLATE: &str =
+    "{payload.item.metadata.name} ({payload.collection.slug}): {event_type} {payload.item.permalink}";
+
+/// Number of times a [`NotifySink`] retries a post if none is configured.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// Delay before the first retry of a post when the webhook does not send `Retry-After`. It doubles on each retry.
+const BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest delay before a retry, including one requested with `Retry-After`.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+type Filter = Box<dyn Fn(&StreamEvent) -> bool + Send + Sync>;
+
+/// Builder for a [`NotifySink`].
+pub struct NotifySinkBuilder {
+    service: Service,
+    webhook: Url,
+    template: String,
+    filter: Option<Filter>,
+    retries: u32,
+}
+
+impl NotifySinkBuilder {
+    /// Constructs a new `NotifySinkBuilder` which posts every event to `webhook`, formatted with [`DEFAULT_TEMPLATE`].
+    pub fn new(service: Service, webhook: Url) -> Self {
+        Self {
+            service,
+            webhook,
+            template: DEFAULT_TEMPLATE.to_owned(),
+            filter: None,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+
+    /// Sets the template of messages.
+    ///
+    /// Placeholders are dot-separated paths into the JSON representation of a [`StreamEvent`] in braces, such as
+    /// `{payload.collection.slug}`. A path can be followed by `|units:N` to format an integer amount with `N`
+    /// decimals, such as `{payload.sale_price|units:18}`. Missing fields are replaced with an empty string, and
+    /// `{{` and `}}` are replaced with literal braces.
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Only posts events for which `filter` returns `true`, such as those returned by [`sales_above`].
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&StreamEvent) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Sets how many times a post is retried when the webhook is rate limited (`429 Too Many Requests`), fails with
+    /// a server error (`5xx`) or cannot be reached. Defaults to [`DEFAULT_RETRIES`].
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Builds the `NotifySink`.
+    pub fn build(self) -> NotifySink {
+        NotifySink {
+            http: reqwest::Client::new(),
+            service: self.service,
+            webhook: self.webhook,
+            template: self.template,
+            filter: self.filter,
+            retries: self.retries,
+        }
+    }
+}
+
+impl fmt::Debug for NotifySinkBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotifySinkBuilder")
+            .field("service", &self.service)
+            .field("template", &self.template)
+            .field("retries", &self.retries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns a filter for [`NotifySinkBuilder::filter`] that passes sales with a price of at least `price`
+/// (in the smallest unit of the payment token, e.g. wei).
+///
+/// The price is compared without regard to the payment token, so a threshold of 1 ETH also passes a sale for
+/// 10<sup>18</sup> units of any other token with 18 decimals, and every sale for 10<sup>18</sup> units of a token
+/// with fewer decimals. Use [`sales_above_in`] to only pass sales in one token.
+pub fn sales_above(price: U256) -> impl Fn(&StreamEvent) -> bool + Send + Sync + 'static {
+    move |event| matches!(&event.payload, Payload::ItemSold(sale) if sale.sale_price >= price)
+}
+
+/// Returns a filter for [`NotifySinkBuilder::filter`] that passes sales paid in the token at `token` with a price
+/// of at least `price` (in the smallest unit of that token). The address of ETH is the zero address.
+pub fn sales_above_in(
+    token: Address,
+    price: U256,
+) -> impl Fn(&StreamEvent) -> bool + Send + Sync + 'static {
+    move |event| {
+        matches!(&event.payload, Payload::ItemSold(sale)
+            if sale.payment_token.address == token && sale.sale_price >= price)
+    }
+}
+
+/// Posts events to a Discord or Slack webhook, formatted with a template.
+/// ```no_run
+/// # use opensea_stream::sinks::notify::{sales_above, NotifySinkBuilder, Service};
+/// # use ethers_core::utils::parse_ether;
+/// # fn main() -> anyhow::Result<()> {
+/// let sink = NotifySinkBuilder::new(Service::Discord, "https://discord.com/api/webhooks/...".parse()?)
+///     .template("{payload.item.metadata.name} sold for {payload.sale_price|units:18} {payload.payment_token.symbol}")
+///     .filter(sales_above(parse_ether(1)?))
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+pub struct NotifySink {
+    http: reqwest::Client,
+    service: Service,
+    webhook: Url,
+    template: String,
+    filter: Option<Filter>,
+    retries: u32,
+}
+
+impl NotifySink {
+    /// Constructs a new [`NotifySinkBuilder`].
+    pub fn builder(service: Service, webhook: Url) -> NotifySinkBuilder {
+        NotifySinkBuilder::new(service, webhook)
+    }
+
+    /// Formats an event with the template of the sink.
+    pub fn render(&self, event: &StreamEvent) -> String {
+        render(
+            &self.template,
+            &serde_json::to_value(event).unwrap_or_default(),
+            |value| value,
+        )
+    }
+
+    /// Posts an event if it passes the filter, returning whether it was posted.
+    ///
+    /// Posts that are rate limited, fail with a server error or cannot be sent are retried (see
+    /// [`NotifySinkBuilder::retries`]), after the delay requested by the `Retry-After` header of the response, or
+    /// else after an exponential backoff. The error of the last attempt is returned.
+    pub async fn notify(&self, event: &StreamEvent) -> Result<bool, reqwest::Error> {
+        if self.filter.as_ref().is_some_and(|filter| !filter(event)) {
+            return Ok(false);
+        }
+
+        let text = self.render(event);
+        let body = match self.service {
+            Service::Discord => json!({ "content": text }),
+            Service::Slack => json!({ "text": text }),
+        };
+        let mut backoff = BACKOFF;
+        for attempt in 0.. {
+            let result = self
+                .http
+                .post(self.webhook.clone())
+                .json(&body)
+                .send()
+                .await;
+            let retry = match &result {
+                Ok(response)
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error() =>
+                {
+                    Some(retry_after(response).unwrap_or(backoff))
+                }
+                Ok(_) => None,
+                Err(e) if e.is_connect() || e.is_timeout() => Some(backoff),
+                Err(_) => None,
+            };
+            match retry {
+                Some(delay) if attempt < self.retries => {
+                    tokio::time::sleep(delay.min(MAX_BACKOFF)).await;
+                    backoff *= 2;
+                }
+                _ => {
+                    result?.error_for_status()?;
+                    break;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Posts every event received from `events` that passes the filter, until it ends.
+    ///
+    /// Events that cannot be posted, even after retrying, are logged and dropped, as are errors of the source, such
+    /// as missed events.
+    pub async fn run(self, mut events: impl EventSource) {
+        while let Some(event) = events.recv().await {
+            match event {
+                Ok(event) => {
+                    if let Err(e) = self.notify(&event).await {
+                        warn!(error = %e, "failed to post event to webhook, dropping it");
+                    }
+                }
+                Err(e) => warn!(error = %e, "notify sink received an error, skipping it"),
+            }
+        }
+    }
+}
+
+/// Returns the delay requested by the `Retry-After` header of a response, if it is given in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+impl fmt::Debug for NotifySink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotifySink")
+            .field("service", &self.service)
+            .field("template", &self.template)
+            .field("retries", &self.retries)
+            .finish_non_exhaustive()
+    }
+}
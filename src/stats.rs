@@ -0,0 +1,310 @@
+use crate::{schema::StreamEvent, Event};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Handle to statistics about the events received by a client.
+///
+/// The handle is cheap to clone; all clones share the same statistics. A [`Client`](crate::Client) records the
+/// events of its subscriptions in the handle returned by [`Client::stats`](crate::Client::stats). Otherwise, events
+/// are recorded with [`Stats::record`] (and missed events with [`Stats::record_missed`]) as they are received. The
+/// statistics can be read at any time with [`Stats::snapshot`], for example to adapt the rate of requests made by a
+/// bot.
+///
+/// Reconnects can only be observed for connections made through the local listener of the `rustls-config` feature,
+/// such as those of [`ClientBuilder::connect`](crate::ClientBuilder::connect) or of sockets built with
+/// `ClientBuilder::stats`; otherwise [`Snapshot::reconnects`] is `None`.
+/// ```no_run
+/// # use opensea_stream::{stats::Stats, Client, Collection, Error, Network};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let stats = Stats::new();
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let mut subscription = client.subscribe(Collection::All).await?;
+///
+/// while let Some(event) = subscription.recv().await {
+///     match event {
+///         Ok(event) => stats.record(&event),
+///         Err(Error::MissedEvents(n)) => stats.record_missed(n),
+///         Err(e) => return Err(e.into()),
+///     }
+///     println!("{:.1} events/s", stats.snapshot().events_per_second);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Stats {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    window: Duration,
+    start: Instant,
+    all: Rate,
+    by_event: HashMap<Event, Rate>,
+    by_collection: HashMap<String, Rate>,
+    total: u64,
+    lag: Option<Duration>,
+    skew: Skew,
+    /// `(second, latency)` of recorded events, oldest first.
+    latencies: VecDeque<(u64, Duration)>,
+    missed: u64,
+    connections: Option<u64>,
+}
+
+/// Statistics at a point in time, returned by [`Stats::snapshot`].
+///
+/// Rates are averaged over the window of the [`Stats`] (10 seconds by default).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// Events received per second.
+    pub events_per_second: f64,
+    /// Events received per second, by event type. Event types without events in the window are omitted.
+    pub by_event: HashMap<Event, f64>,
+    /// Events received per second, by collection slug. Collections without events in the window are omitted.
+    pub by_collection: HashMap<String, f64>,
+    /// Number of events received.
+    pub total_events: u64,
+    /// Time between OpenSea sending the last event and it being recorded.
+    pub lag: Option<Duration>,
+    /// Percentiles of the time between events happening and them being recorded, if any event in the window has a
+    /// timestamp. See [`Sequenced::latency`](crate::ordering::Sequenced::latency).
+    pub latency: Option<Latency>,
+    /// Number of events that were dropped because the consumer fell behind.
+    pub missed: u64,
+    /// Number of times the client reconnected, if known.
+    pub reconnects: Option<u64>,
+}
+
+impl Stats {
+    /// Constructs a new `Stats` which averages rates over 10 seconds.
+    pub fn new() -> Self {
+        Self::with_window(Duration::from_secs(10))
+    }
+
+    /// Constructs a new `Stats` which averages rates over `window`, rounded up to whole seconds.
+    pub fn with_window(window: Duration) -> Self {
+        let window = Duration::from_secs(window.as_secs_f64().ceil().max(1.0) as u64);
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                window,
+                start: Instant::now(),
+                all: Rate::default(),
+                by_event: HashMap::new(),
+                by_collection: HashMap::new(),
+                total: 0,
+                lag: None,
+                skew: Skew::default(),
+                latencies: VecDeque::new(),
+                missed: 0,
+                connections: None,
+            })),
+        }
+    }
+
+    /// Records a received event.
+    pub fn record(&self, event: &StreamEvent) {
+        let mut inner = self.inner.lock().unwrap();
+        let second = inner.start.elapsed().as_secs();
+        let window = inner.window.as_secs();
+
+        inner.all.add(second, window);
+        inner
+            .by_event
+            .entry(event.payload.event())
+            .or_default()
+            .add(second, window);
+        inner
+            .by_collection
+            .entry(event.payload.collection().0.clone())
+            .or_default()
+            .add(second, window);
+        inner.total += 1;
+
+        let now = Utc::now();
+        inner.lag = (now - event.sent_at).to_std().ok();
+        inner.skew.observe(event.sent_at, now);
+        if let Some(latency) = inner.skew.latency(event, now) {
+            inner.latencies.push_back((second, latency));
+        }
+        expire(&mut inner.latencies, second, window);
+    }
+
+    /// Records events that were missed, such as those reported by
+    /// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+    pub fn record_missed(&self, n: u64) {
+        self.inner.lock().unwrap().missed += n;
+    }
+
+    /// Records that the client opened a connection. Every connection after the first is a reconnect.
+    #[cfg(feature = "rustls-config")]
+    pub(crate) fn record_connection(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.connections = Some(inner.connections.unwrap_or(0) + 1);
+    }
+
+    /// Starts tracking connections, so that reconnects are reported as a number rather than `None`.
+    #[cfg(feature = "rustls-config")]
+    pub(crate) fn track_connections(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.connections.get_or_insert(0);
+    }
+
+    /// Returns the current statistics.
+    pub fn snapshot(&self) -> Snapshot {
+        let mut inner = self.inner.lock().unwrap();
+        let second = inner.start.elapsed().as_secs();
+        let window = inner.window.as_secs();
+
+        let Inner {
+            all,
+            by_event,
+            by_collection,
+            ..
+        } = &mut *inner;
+        let events_per_second = all.per_second(second, window);
+        let by_event = rates(by_event, second, window);
+        let by_collection = rates(by_collection, second, window);
+        expire(&mut inner.latencies, second, window);
+        let latency = Latency::of(
+            inner
+                .latencies
+                .iter()
+                .map(|(_, latency)| *latency)
+                .collect(),
+        );
+
+        Snapshot {
+            events_per_second,
+            by_event,
+            by_collection,
+            total_events: inner.total,
+            lag: inner.lag,
+            latency,
+            missed: inner.missed,
+            reconnects: inner.connections.map(|c| c.saturating_sub(1)),
+        }
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computes the rates of every key, forgetting keys without events in the window.
+fn rates<K>(rates: &mut HashMap<K, Rate>, second: u64, window: u64) -> HashMap<K, f64>
+where
+    K: Clone + Eq + std::hash::Hash,
+{
+    rates.retain(|_, rate| rate.per_second(second, window) > 0.0);
+    rates
+        .iter_mut()
+        .map(|(k, rate)| (k.clone(), rate.per_second(second, window)))
+        .collect()
+}
+
+/// Number of events in each second of a sliding window.
+#[derive(Debug, Default)]
+struct Rate {
+    /// `(second, count)`, oldest first.
+    buckets: VecDeque<(u64, u64)>,
+}
+
+impl Rate {
+    fn add(&mut self, second: u64, window: u64) {
+        self.expire(second, window);
+        match self.buckets.back_mut() {
+            Some((s, count)) if *s == second => *count += 1,
+            _ => self.buckets.push_back((second, 1)),
+        }
+    }
+
+    fn per_second(&mut self, second: u64, window: u64) -> f64 {
+        self.expire(second, window);
+        self.buckets.iter().map(|(_, count)| count).sum::<u64>() as f64 / window as f64
+    }
+
+    fn expire(&mut self, second: u64, window: u64) {
+        expire(&mut self.buckets, second, window);
+    }
+}
+
+/// Removes the entries of seconds before the window.
+fn expire<T>(entries: &mut VecDeque<(u64, T)>, second: u64, window: u64) {
+    while matches!(entries.front(), Some((s, _)) if s + window <= second) {
+        entries.pop_front();
+    }
+}
+
+/// Percentiles of latencies, from the time events happened to the time they were delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latency {
+    /// Median latency.
+    pub p50: Duration,
+    /// 90th percentile.
+    pub p90: Duration,
+    /// 99th percentile.
+    pub p99: Duration,
+    /// Largest latency.
+    pub max: Duration,
+}
+
+impl Latency {
+    /// Computes the percentiles of `latencies` by nearest rank, or returns `None` if there are none.
+    fn of(mut latencies: Vec<Duration>) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        latencies.sort_unstable();
+        let rank = |p: usize| latencies[(latencies.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: latencies[latencies.len() - 1],
+        })
+    }
+}
+
+/// Number of recent events that the clock offset is estimated from.
+const SKEW_SAMPLES: usize = 256;
+
+/// Estimate of how far the local clock is behind the clock of OpenSea.
+///
+/// An event cannot be received before it was sent, so if the local clock says otherwise it is behind by at least
+/// that much. The estimate is the largest such difference among recent events, and zero if there is none.
+#[derive(Debug, Default)]
+pub(crate) struct Skew {
+    /// Receive time minus `sent_at` of recent events, oldest first.
+    offsets: VecDeque<chrono::Duration>,
+}
+
+impl Skew {
+    /// Records that an event sent at `sent_at` was received at `received_at`, by the local clock.
+    pub(crate) fn observe(&mut self, sent_at: DateTime<Utc>, received_at: DateTime<Utc>) {
+        if self.offsets.len() == SKEW_SAMPLES {
+            self.offsets.pop_front();
+        }
+        self.offsets.push_back(received_at - sent_at);
+    }
+
+    /// Returns the time between `event` happening and `now`, by the local clock, corrected for the estimated clock
+    /// offset. Events without a timestamp return `None`.
+    pub(crate) fn latency(&self, event: &StreamEvent, now: DateTime<Utc>) -> Option<Duration> {
+        let behind = self
+            .offsets
+            .iter()
+            .min()
+            .map_or(chrono::Duration::zero(), |min| -*min)
+            .max(chrono::Duration::zero());
+        let latency = now + behind - event.payload.event_timestamp()?;
+        Some(latency.to_std().unwrap_or_default())
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    coalesce::{Coalesce, Coalescer},
+    middleware::Pipeline,
+    ordering::{Reorder, Reorderer, Sequenced},
+    ratelimit::{Limiter, RateLimit},
+    schema::StreamEvent,
+    stats::{Skew, Stats},
+    Collection, Error, Event,
+};
+use chrono::Utc;
+use phyllo::message::Message;
+use serde_json::Value;
+use std::future::Future;
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        watch,
+    },
+    time::{self, Instant},
+};
+use tracing::warn;
+
+/// What an [`EventStream`] does when the consumer falls behind and events are dropped from the broadcast channel.
+///
+/// In both cases the stream continues with the oldest event still in the channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LagPolicy {
+    /// Yield [`Error::MissedEvents`] with the number of dropped events.
+    #[default]
+    Error,
+    /// Log a warning with [`tracing`](https://crates.io/crates/tracing) and skip over the dropped events.
+    Skip,
+}
+
+/// Source of events read by the router, the sinks and the other consumers of this crate, such as an [`EventStream`]
+/// or a [`Subscription`](crate::Subscription).
+///
+/// Events dropped because the consumer fell behind are handled by the source, according to its [`LagPolicy`];
+/// consumers log the errors it yields and keep receiving.
+pub trait EventSource: Send {
+    /// Receives the next event, or `None` once the source has ended.
+    fn recv(&mut self) -> impl Future<Output = Option<Result<StreamEvent, Error>>> + Send;
+}
+
+impl EventSource for EventStream {
+    fn recv(&mut self) -> impl Future<Output = Option<Result<StreamEvent, Error>>> + Send {
+        EventStream::recv(self)
+    }
+}
+
+/// Events of a subscription, as received by a [`Subscription`](crate::Subscription) or from
+/// [`Shards::stream`](crate::shard::Shards::stream).
+///
+/// Messages other than events are skipped, and dropped events are handled according to the [`LagPolicy`].
+/// Events are run through the [`Pipeline`] of the stream, if any, then put in order by its [`Reorder`], if any, then
+/// held by its [`Coalesce`] window, if any, then delivered at the pace of its [`RateLimit`], if any.
+/// ```no_run
+/// # use opensea_stream::{Client, Collection, LagPolicy, Network, SubscribeConfig};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let config = SubscribeConfig::new().broadcast_buffer(1024).lag_policy(LagPolicy::Skip);
+/// let mut events = client.subscribe_with_config(Collection::All, config).await?;
+///
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EventStream {
+    receiver: broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>>,
+    lag_policy: LagPolicy,
+    middleware: Pipeline,
+    reorder: Option<Reorderer>,
+    /// Late event to deliver after its [`Error::OutOfOrder`].
+    late: Option<Sequenced>,
+    coalesce: Option<Coalescer>,
+    rate_limit: Option<Limiter>,
+    received: u64,
+    skew: Skew,
+    stats: Option<Stats>,
+    /// Total number of events missed before they reached the receiver, such as by the forward tasks of
+    /// [`Shards`](crate::shard::Shards).
+    upstream_missed: Option<(watch::Receiver<u64>, u64)>,
+    closed: bool,
+}
+
+impl EventStream {
+    /// Constructs a new `EventStream` from the receiver of a subscription, using [`LagPolicy::Error`].
+    #[cfg(feature = "unstable-phyllo")]
+    pub fn new(
+        receiver: broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>>,
+    ) -> Self {
+        Self::from_receiver(receiver)
+    }
+
+    pub(crate) fn from_receiver(
+        receiver: broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>>,
+    ) -> Self {
+        Self {
+            receiver,
+            lag_policy: LagPolicy::default(),
+            middleware: Pipeline::new(),
+            reorder: None,
+            late: None,
+            coalesce: None,
+            rate_limit: None,
+            received: 0,
+            skew: Skew::default(),
+            stats: None,
+            upstream_missed: None,
+            closed: false,
+        }
+    }
+
+    /// Sets what happens when the consumer falls behind.
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+
+    /// Sets the pipeline that events are run through before they are returned.
+    pub fn middleware(mut self, middleware: Pipeline) -> Self {
+        self.middleware = middleware;
+        self
+    }
+
+    /// Sets the per-collection ordering of events.
+    pub fn reorder(mut self, reorder: Reorder) -> Self {
+        self.reorder = Some(Reorderer::new(reorder));
+        self
+    }
+
+    /// Sets the coalescing of bursts of events about the same item.
+    pub fn coalesce(mut self, coalesce: Coalesce) -> Self {
+        self.coalesce = Some(Coalescer::new(coalesce));
+        self
+    }
+
+    /// Sets the rate limit of delivered events.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(Limiter::new(rate_limit));
+        self
+    }
+
+    /// Records every received event, and every event missed because the consumer fell behind, in `stats`.
+    pub(crate) fn stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Handles the events missed before they reached the receiver, as counted by `missed`, like the events missed by
+    /// the receiver itself. Events missed before this is called are not reported.
+    pub(crate) fn upstream_missed(mut self, mut missed: watch::Receiver<u64>) -> Self {
+        let reported = *missed.borrow_and_update();
+        self.upstream_missed = Some((missed, reported));
+        self
+    }
+
+    /// Receives the next event, or `None` once the subscription is closed and every queued event was delivered.
+    pub async fn recv(&mut self) -> Option<Result<StreamEvent, Error>> {
+        let event = self.recv_sequenced().await?;
+        Some(event.map(|event| event.event))
+    }
+
+    /// Receives the next event with its sequence number and latency. See [`recv`](Self::recv).
+    pub async fn recv_sequenced(&mut self) -> Option<Result<Sequenced, Error>> {
+        let event = self.next().await?;
+        Some(event.map(|mut event| {
+            event.latency = self.skew.latency(&event.event, Utc::now());
+            event
+        }))
+    }
+
+    async fn next(&mut self) -> Option<Result<Sequenced, Error>> {
+        loop {
+            let now = Instant::now();
+            if let Some(event) = self.late.take() {
+                if let Some(event) = self.hold(event, now) {
+                    return Some(Ok(event));
+                }
+            }
+
+            // Time at which a held or queued event can be delivered, if any.
+            let mut deadline = None;
+            while let Some(reorderer) = &mut self.reorder {
+                match reorderer.pop(now) {
+                    Ok(event) => {
+                        if let Some(event) = self.hold(event, now) {
+                            return Some(Ok(event));
+                        }
+                    }
+                    Err(next) => {
+                        deadline = next;
+                        break;
+                    }
+                }
+            }
+            while let Some(coalescer) = &mut self.coalesce {
+                match coalescer.pop(now) {
+                    Ok(event) => {
+                        if let Some(event) = self.limit(event, now) {
+                            return Some(Ok(event));
+                        }
+                    }
+                    Err(next) => {
+                        deadline = earliest(deadline, next);
+                        break;
+                    }
+                }
+            }
+            if let Some(limiter) = &mut self.rate_limit {
+                match limiter.pop(now) {
+                    Ok(event) => return Some(Ok(event)),
+                    Err(next) => deadline = earliest(deadline, next),
+                }
+            }
+
+            let received = match self.take_upstream_missed() {
+                Some(missed) => Err(RecvError::Lagged(missed)),
+                None => match (deadline, self.closed) {
+                    (None, true) => return None,
+                    (Some(deadline), true) => {
+                        time::sleep_until(deadline).await;
+                        continue;
+                    }
+                    // Keep receiving while waiting, so that the overflow policy applies rather than the lag policy.
+                    (Some(deadline), false) => {
+                        match time::timeout_at(deadline, self.receiver.recv()).await {
+                            Ok(received) => received,
+                            Err(_) => continue,
+                        }
+                    }
+                    (None, false) => self.receiver.recv().await,
+                },
+            };
+
+            match received {
+                Ok(message) => {
+                    let Some(event) = message.into_custom_payload() else {
+                        continue;
+                    };
+                    let sequence = self.received;
+                    let received_at = Utc::now();
+                    self.received += 1;
+                    self.skew.observe(event.sent_at, received_at);
+                    if let Some(stats) = &self.stats {
+                        stats.record(&event);
+                    }
+                    let Some(event) = self.middleware.handle(event).await else {
+                        continue;
+                    };
+                    let event = Sequenced {
+                        sequence,
+                        received_at,
+                        latency: None,
+                        event,
+                    };
+                    let now = Instant::now();
+                    match &mut self.reorder {
+                        Some(reorderer) => {
+                            if let Some((error, late)) = reorderer.push(event, now) {
+                                self.late = late;
+                                return Some(Err(error));
+                            }
+                        }
+                        None => {
+                            if let Some(event) = self.hold(event, now) {
+                                return Some(Ok(event));
+                            }
+                        }
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    if let Some(stats) = &self.stats {
+                        stats.record_missed(n);
+                    }
+                    match self.lag_policy {
+                        LagPolicy::Error => return Some(Err(Error::MissedEvents(n))),
+                        LagPolicy::Skip => {
+                            warn!(missed = n, "consumer fell behind, skipping events")
+                        }
+                    }
+                }
+                Err(RecvError::Closed) => self.closed = true,
+            }
+        }
+    }
+
+    /// Returns the number of events missed upstream since the last call, if any.
+    fn take_upstream_missed(&mut self) -> Option<u64> {
+        let (missed, reported) = self.upstream_missed.as_mut()?;
+        let total = *missed.borrow_and_update();
+        let n = total - std::mem::replace(reported, total);
+        (n > 0).then_some(n)
+    }
+
+    /// Holds an event with the coalescing window, then queues it with the rate limit, or returns it if there is
+    /// neither.
+    fn hold(&mut self, event: Sequenced, now: Instant) -> Option<Sequenced> {
+        let event = match &mut self.coalesce {
+            Some(coalescer) => coalescer.push(event, now)?,
+            None => event,
+        };
+        self.limit(event, now)
+    }
+
+    /// Queues an event with the rate limit, or returns it if there is none.
+    fn limit(&mut self, event: Sequenced, now: Instant) -> Option<Sequenced> {
+        match &mut self.rate_limit {
+            Some(limiter) => {
+                limiter.push(event, now);
+                None
+            }
+            None => Some(event),
+        }
+    }
+
+    /// Returns the underlying receiver. Events held for ordering or by the coalescing window, or queued by the rate
+    /// limit, are lost.
+    #[cfg(feature = "unstable-phyllo")]
+    pub fn into_inner(self) -> broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>> {
+        self.receiver
+    }
+}
+
+/// Returns the earlier of two deadlines, if any.
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
@@ -0,0 +1,712 @@
+use crate::{
+    client::Registry,
+    coalesce::Coalesce,
+    middleware::Pipeline,
+    ordering::{Reorder, Sequenced},
+    ratelimit::RateLimit,
+    schema::StreamEvent,
+    stats::Stats,
+    ClientError, CloseReason, Collection, Error, Event, EventSource, EventStream, LagPolicy,
+    Network,
+};
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use phyllo::{
+    channel::{ChannelBuilder, ChannelHandler},
+    error::{Error as ChannelError, RegisterChannelError},
+    message::{Message, Payload, PushStatus},
+    socket::{SocketBuilder, SocketHandler},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fmt::Debug, future::Future, time::Duration};
+use tokio::{sync::broadcast, time};
+use tracing::warn;
+use url::Url;
+
+#[cfg(feature = "unstable-phyllo")]
+use crate::guardrails::Guardrails;
+#[cfg(feature = "unstable-phyllo")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "unstable-phyllo")]
+use std::collections::HashMap;
+#[cfg(feature = "unstable-phyllo")]
+use thiserror::Error;
+
+/// Creates a client.
+///
+/// To connect through a proxy or with a custom TLS configuration, use [`ClientBuilder`](crate::ClientBuilder) instead.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn client(network: Network, token: &str) -> SocketHandler<Collection> {
+    connect(network, token).await
+}
+
+/// Connects a socket to `network`, authenticating with the API key `token`.
+pub(crate) async fn connect(network: Network, token: &str) -> SocketHandler<Collection> {
+    let mut network: Url = Url::from(network);
+    network.query_pairs_mut().append_pair("token", token);
+    SocketBuilder::new(network).build().await
+}
+
+/// Payload type that events can be deserialized into, in place of [`StreamEvent`].
+///
+/// This is implemented for every type with the required bounds; see [`subscribe_to_as`].
+#[cfg(feature = "unstable-phyllo")]
+pub trait CustomPayload: Serialize + DeserializeOwned + Clone + Send + Debug + 'static {}
+
+#[cfg(feature = "unstable-phyllo")]
+impl<T> CustomPayload for T where T: Serialize + DeserializeOwned + Clone + Send + Debug + 'static {}
+
+/// Subscribes to all the events of a particular [`Collection`].
+///
+/// This returns once the channel is registered with the socket, and the channel is joined in the background. If the
+/// server rejects the join, it is retried with backoff; [`phyllo`] does not report the reply of the server, so a
+/// misconfigured slug shows up as a subscription without events (and as warnings in the logs of `phyllo`).
+/// [`Client::subscribe`](crate::Client::subscribe) reports rejected joins with the `rustls-config` feature, and the
+/// `wasm` client reports them as `wasm::Error::Rejected`.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_to(
+    socket: &mut SocketHandler<Collection>,
+    collection: Collection,
+) -> SubscribeResult {
+    subscribe_to_as(socket, collection).await
+}
+
+/// Subscribes to all the events of a particular [`Collection`] using
+/// a custom configuration.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_to_with_config(
+    socket: &mut SocketHandler<Collection>,
+    channel_builder: ChannelBuilder<Collection>,
+) -> SubscribeResult {
+    subscribe_to_with_config_as(socket, channel_builder).await
+}
+
+/// Subscribes to all the events of a particular [`Collection`], deserializing them into a custom payload type
+/// instead of [`StreamEvent`].
+///
+/// This is useful to deserialize only the fields you need, or fields that the schema of this crate does not have.
+/// Messages whose payload does not deserialize into `R` are not received.
+/// ```no_run
+/// # use opensea_stream::{client, subscribe_to_as, Collection, Network};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize, Clone, Debug)]
+/// struct Slim {
+///     event_type: String,
+///     sent_at: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut client = client(Network::Mainnet, "YOUR_API_KEY_HERE").await;
+/// let (_handler, mut subscription) = subscribe_to_as::<Slim>(&mut client, Collection::All).await?;
+///
+/// loop {
+///     if let Some(event) = subscription.recv().await?.into_custom_payload() {
+///         println!("{} at {}", event.event_type, event.sent_at);
+///     }
+/// }
+/// # }
+/// ```
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_to_as<R: CustomPayload>(
+    socket: &mut SocketHandler<Collection>,
+    collection: Collection,
+) -> SubscribeResult<R> {
+    socket.channel(ChannelBuilder::new(collection)).await
+}
+
+/// Subscribes to all the events of a particular [`Collection`] using a custom configuration, deserializing them
+/// into a custom payload type. See [`subscribe_to_as`].
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_to_with_config_as<R: CustomPayload>(
+    socket: &mut SocketHandler<Collection>,
+    channel_builder: ChannelBuilder<Collection>,
+) -> SubscribeResult<R> {
+    socket.channel(channel_builder).await
+}
+
+/// Configuration of a subscription, for [`Client::subscribe_with_config`](crate::Client::subscribe_with_config).
+#[derive(Debug, Clone)]
+pub struct SubscribeConfig {
+    broadcast_buffer: usize,
+    lag_policy: LagPolicy,
+    middleware: Pipeline,
+    reorder: Option<Reorder>,
+    coalesce: Option<Coalesce>,
+    rate_limit: Option<RateLimit>,
+    rejoin: Option<ExponentialBackoff>,
+    rejoin_on_close: bool,
+    stats: Option<Stats>,
+}
+
+impl SubscribeConfig {
+    /// Constructs a new `SubscribeConfig` with a buffer of 128 messages, surfacing dropped events as errors.
+    pub fn new() -> Self {
+        Self {
+            broadcast_buffer: 128,
+            lag_policy: LagPolicy::default(),
+            middleware: Pipeline::new(),
+            reorder: None,
+            coalesce: None,
+            rate_limit: None,
+            rejoin: None,
+            rejoin_on_close: false,
+            stats: None,
+        }
+    }
+
+    /// Sets the buffer size of the broadcast channel of the subscription. See [`tokio::sync::broadcast`].
+    pub fn broadcast_buffer(mut self, broadcast_buffer: usize) -> Self {
+        self.broadcast_buffer = broadcast_buffer;
+        self
+    }
+
+    /// Sets what happens when the consumer falls behind and events are dropped.
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+
+    /// Sets the pipeline that events are run through before they are delivered.
+    pub fn middleware(mut self, middleware: Pipeline) -> Self {
+        self.middleware = middleware;
+        self
+    }
+
+    /// Sets the per-collection ordering of events.
+    pub fn reorder(mut self, reorder: Reorder) -> Self {
+        self.reorder = Some(reorder);
+        self
+    }
+
+    /// Sets the coalescing of bursts of events about the same item.
+    pub fn coalesce(mut self, coalesce: Coalesce) -> Self {
+        self.coalesce = Some(coalesce);
+        self
+    }
+
+    /// Sets the rate limit of delivered events.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Sets the strategy for rejoining the channel using exponential backoff, after the server reports an error on
+    /// it (`phx_error`) or, with [`SubscribeConfig::rejoin_on_close`], closes it.
+    ///
+    /// Errors are handled by [`phyllo`], which rejoins without interrupting the stream or reporting the error.
+    pub fn rejoin(mut self, rejoin: ExponentialBackoff) -> Self {
+        self.rejoin = Some(rejoin);
+        self
+    }
+
+    /// Sets whether a [`Subscription`] joins the channel again when the server closes it (`phx_close`). The close
+    /// is still delivered as [`Error::Closed`], followed by the events of the new channel. Disabled by default.
+    pub fn rejoin_on_close(mut self, rejoin_on_close: bool) -> Self {
+        self.rejoin_on_close = rejoin_on_close;
+        self
+    }
+}
+
+impl SubscribeConfig {
+    /// Records the events of the stream in `stats`, as a [`Client`](crate::Client) does.
+    pub(crate) fn stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Constructs the builder of the channel of `collection` with this configuration.
+    fn channel_builder(&self, collection: Collection) -> ChannelBuilder<Collection> {
+        let builder = ChannelBuilder::new(collection).broadcast_buffer(self.broadcast_buffer);
+        match &self.rejoin {
+            Some(rejoin) => builder.rejoin(rejoin.clone()),
+            None => builder,
+        }
+    }
+
+    /// Constructs the stream of `receiver` with this configuration.
+    fn stream(
+        self,
+        receiver: broadcast::Receiver<Message<Collection, Event, Value, StreamEvent>>,
+    ) -> EventStream {
+        let mut stream = EventStream::from_receiver(receiver)
+            .lag_policy(self.lag_policy)
+            .middleware(self.middleware);
+        if let Some(reorder) = self.reorder {
+            stream = stream.reorder(reorder);
+        }
+        if let Some(coalesce) = self.coalesce {
+            stream = stream.coalesce(coalesce);
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            stream = stream.rate_limit(rate_limit);
+        }
+        if let Some(stats) = self.stats {
+            stream = stream.stats(stats);
+        }
+        stream
+    }
+}
+
+impl Default for SubscribeConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Subscribes to all the events of a particular [`Collection`], receiving them as an [`EventStream`].
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_stream(
+    socket: &mut SocketHandler<Collection>,
+    collection: Collection,
+) -> Result<
+    (
+        ChannelHandler<Collection, Event, Value, StreamEvent>,
+        EventStream,
+    ),
+    RegisterChannelError,
+> {
+    subscribe_stream_with_config(socket, collection, SubscribeConfig::new()).await
+}
+
+/// Subscribes to all the events of a particular [`Collection`], receiving them as an [`EventStream`]
+/// configured by `config`.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_stream_with_config(
+    socket: &mut SocketHandler<Collection>,
+    collection: Collection,
+    config: SubscribeConfig,
+) -> Result<
+    (
+        ChannelHandler<Collection, Event, Value, StreamEvent>,
+        EventStream,
+    ),
+    RegisterChannelError,
+> {
+    join(socket, collection, config).await
+}
+
+/// Joins the channel of `collection` on `socket`, returning its handler and its events configured by `config`.
+pub(crate) async fn join(
+    socket: &mut SocketHandler<Collection>,
+    collection: Collection,
+    config: SubscribeConfig,
+) -> Result<
+    (
+        ChannelHandler<Collection, Event, Value, StreamEvent>,
+        EventStream,
+    ),
+    RegisterChannelError,
+> {
+    let (handler, receiver) = socket.channel(config.channel_builder(collection)).await?;
+    Ok((handler, config.stream(receiver)))
+}
+
+/// Subscribes to all the events of a particular [`Collection`] with [`Guardrails`] on the size and parse time of
+/// payloads, receiving them as an [`EventStream`] configured by `config`.
+///
+/// Payloads are received as JSON values and deserialized by the guardrails, so the channel handler is typed with
+/// [`Value`] payloads; it can be passed to [`unsubscribe`] as usual. Must be called from within a Tokio runtime.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_guarded(
+    socket: &mut SocketHandler<Collection>,
+    collection: Collection,
+    guardrails: &Guardrails,
+    config: SubscribeConfig,
+) -> Result<(ChannelHandler<Collection, Event, Value, Value>, EventStream), RegisterChannelError> {
+    let (handler, receiver) = socket.channel(config.channel_builder(collection)).await?;
+    let receiver = guardrails.spawn(receiver, config.broadcast_buffer);
+    Ok((handler, config.stream(receiver)))
+}
+
+/// Configuration for [`Client::subscribe_many_with_config`](crate::Client::subscribe_many_with_config), and for
+/// `subscribe_many_with_config` with the `unstable-phyllo` feature.
+#[derive(Debug, Clone)]
+pub struct SubscribeManyConfig {
+    pub(crate) interval: Duration,
+    pub(crate) rejoin: ExponentialBackoff,
+    broadcast_buffer: usize,
+}
+
+impl SubscribeManyConfig {
+    /// Constructs a new `SubscribeManyConfig` which joins 5 channels per second.
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_millis(200),
+            rejoin: ExponentialBackoff::default(),
+            broadcast_buffer: 128,
+        }
+    }
+
+    /// Sets the interval between two channel joins.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the strategy with which joins that were rejected (for example, by throttling) are retried.
+    pub fn rejoin(mut self, rejoin: ExponentialBackoff) -> Self {
+        self.rejoin = rejoin;
+        self
+    }
+
+    /// Sets the buffer size of the broadcast channel of each subscription. See [`tokio::sync::broadcast`].
+    pub fn broadcast_buffer(mut self, broadcast_buffer: usize) -> Self {
+        self.broadcast_buffer = broadcast_buffer;
+        self
+    }
+
+    /// Returns the configuration of each subscription.
+    pub(crate) fn subscribe_config(&self) -> SubscribeConfig {
+        SubscribeConfig::new()
+            .rejoin(self.rejoin.clone())
+            .broadcast_buffer(self.broadcast_buffer)
+    }
+}
+
+impl Default for SubscribeManyConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of subscribing to a single [`Collection`], with events deserialized into `R`.
+#[cfg(feature = "unstable-phyllo")]
+pub type SubscribeResult<R = StreamEvent> = Result<
+    (
+        ChannelHandler<Collection, Event, Value, R>,
+        broadcast::Receiver<Message<Collection, Event, Value, R>>,
+    ),
+    RegisterChannelError,
+>;
+
+/// Subscribes to all the events of many [`Collection`]s, pacing the joins so that the server does not throttle them.
+///
+/// See [`subscribe_many_with_config`] for details.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_many(
+    socket: &mut SocketHandler<Collection>,
+    collections: impl IntoIterator<Item = Collection>,
+) -> HashMap<Collection, SubscribeResult> {
+    subscribe_many_with_config(socket, collections, SubscribeManyConfig::new()).await
+}
+
+/// Subscribes to all the events of many [`Collection`]s using a custom configuration.
+///
+/// At most one channel is joined per configured interval, and a collection that appears more than once is only
+/// subscribed to once. The result of a collection reports whether its channel could be registered with the socket,
+/// not whether the server accepted the join: [`phyllo`] retries rejected joins according to the configured backoff
+/// without reporting them. [`Client::subscribe_many_with_config`](crate::Client::subscribe_many_with_config) reports
+/// rejected joins with the `rustls-config` feature.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn subscribe_many_with_config(
+    socket: &mut SocketHandler<Collection>,
+    collections: impl IntoIterator<Item = Collection>,
+    config: SubscribeManyConfig,
+) -> HashMap<Collection, SubscribeResult> {
+    let mut interval = time::interval(config.interval);
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+
+    let mut results = HashMap::new();
+    for collection in collections {
+        if results.contains_key(&collection) {
+            continue;
+        }
+
+        interval.tick().await;
+        let channel_builder = ChannelBuilder::new(collection.clone())
+            .rejoin(config.rejoin.clone())
+            .broadcast_buffer(config.broadcast_buffer);
+        let result = socket.channel(channel_builder).await;
+        results.insert(collection, result);
+    }
+    results
+}
+
+/// Errors that can be encountered while unsubscribing from a [`Collection`].
+#[cfg(feature = "unstable-phyllo")]
+#[derive(Debug, Error)]
+pub enum UnsubscribeError {
+    /// There is no subscription to the collection.
+    #[error("not subscribed to {0}")]
+    NotSubscribed(Collection),
+    /// The server replied to the leave message with an error.
+    #[error("server rejected leave: {0}")]
+    Rejected(Value),
+    /// The leave message could not be sent, or no reply was received in time.
+    #[error("could not leave channel")]
+    Channel(#[from] ChannelError),
+}
+
+/// Unsubscribes from the channel of `handler`, sending a leave message and waiting for the reply of the server.
+///
+/// The channel is dropped even if the server rejects the leave, so the collection can be subscribed to again.
+#[cfg(feature = "unstable-phyllo")]
+pub async fn unsubscribe<R: CustomPayload>(
+    handler: ChannelHandler<Collection, Event, Value, R>,
+) -> Result<(), UnsubscribeError> {
+    let reply = handler.close().await?;
+    match rejection(reply) {
+        Some(response) => Err(UnsubscribeError::Rejected(response)),
+        None => Ok(()),
+    }
+}
+
+/// Leaves the channel of `handler`, sending a leave message and waiting for the reply of the server.
+pub(crate) async fn leave(
+    handler: ChannelHandler<Collection, Event, Value, StreamEvent>,
+) -> Result<(), ClientError> {
+    let reply = handler.close().await.map_err(|e| match e {
+        ChannelError::Timeout => ClientError::Timeout,
+        ChannelError::ChannelDropped | ChannelError::SocketDropped => ClientError::Closed,
+        e => ClientError::Reply(e.to_string()),
+    })?;
+    match rejection(reply) {
+        Some(response) => Err(ClientError::Rejected(response)),
+        None => Ok(()),
+    }
+}
+
+/// Returns the response of a reply that reports an error, if it does.
+fn rejection<R>(reply: Message<Collection, Event, Value, R>) -> Option<Value> {
+    match reply.payload {
+        Some(Payload::PushReply {
+            status: PushStatus::Error,
+            response,
+        }) => Some(response),
+        _ => None,
+    }
+}
+
+/// Subscription of a [`Client`](crate::Client) to a [`Collection`], returned by
+/// [`Client::subscribe`](crate::Client::subscribe).
+///
+/// Events are received as from an [`EventStream`]. Dropping the subscription does not leave the channel; use
+/// [`Subscription::unsubscribe`] to stop receiving events.
+///
+/// When the channel is closed, by the server or because the socket of the client was closed, the subscription
+/// delivers [`Error::Closed`] as its last item, so that a channel that was closed can be told apart from one without
+/// events. With [`SubscribeConfig::rejoin_on_close`], channels closed by the server are joined again. Errors on the
+/// channel (`phx_error`) are handled by [`phyllo`], which rejoins it; they are not delivered (see [`CloseReason`]).
+#[derive(Debug)]
+pub struct Subscription {
+    collection: Collection,
+    handler: ChannelHandler<Collection, Event, Value, StreamEvent>,
+    events: EventStream,
+    socket: SocketHandler<Collection>,
+    config: SubscribeConfig,
+    registry: Registry,
+    /// Whether the close of the channel was delivered, and the channel was not joined again.
+    closed: bool,
+}
+
+impl Subscription {
+    pub(crate) fn new(
+        collection: Collection,
+        handler: ChannelHandler<Collection, Event, Value, StreamEvent>,
+        events: EventStream,
+        socket: SocketHandler<Collection>,
+        config: SubscribeConfig,
+        registry: Registry,
+    ) -> Self {
+        Self {
+            collection,
+            handler,
+            events,
+            socket,
+            config,
+            registry,
+            closed: false,
+        }
+    }
+
+    /// Returns the collection that is subscribed to.
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+
+    /// Receives the next event. See [`EventStream::recv`].
+    ///
+    /// Once the channel is closed, [`Error::Closed`] is returned, then `None`.
+    pub async fn recv(&mut self) -> Option<Result<StreamEvent, Error>> {
+        match self.events.recv().await {
+            Some(event) => Some(event),
+            None => self.close().await.map(Err),
+        }
+    }
+
+    /// Receives the next event with its sequence number. See [`EventStream::recv_sequenced`].
+    ///
+    /// Once the channel is closed, [`Error::Closed`] is returned, then `None`.
+    pub async fn recv_sequenced(&mut self) -> Option<Result<Sequenced, Error>> {
+        match self.events.recv_sequenced().await {
+            Some(event) => Some(event),
+            None => self.close().await.map(Err),
+        }
+    }
+
+    /// Returns the error reporting that the channel was closed, joining it again if configured, or `None` if the
+    /// close was already reported.
+    async fn close(&mut self) -> Option<Error> {
+        if self.closed {
+            return None;
+        }
+        // Channels outlive reconnects of the socket, so a channel that ends while the socket runs was closed by the
+        // server.
+        let reason = match self.socket.alive().await {
+            true => CloseReason::Server,
+            false => CloseReason::Socket,
+        };
+        warn!(collection = %self.collection, %reason, "channel closed");
+
+        self.closed =
+            !(reason == CloseReason::Server && self.config.rejoin_on_close && self.rejoin().await);
+        if self.closed {
+            self.registry.remove(&self.collection);
+        }
+        Some(Error::Closed { reason })
+    }
+
+    /// Joins the channel again, retrying with the rejoin backoff while the socket still holds the closed channel.
+    async fn rejoin(&mut self) -> bool {
+        let mut backoff = self.config.rejoin.clone().unwrap_or_default();
+        loop {
+            let result = join(
+                &mut self.socket,
+                self.collection.clone(),
+                self.config.clone(),
+            )
+            .await;
+            match result {
+                Ok((handler, events)) => {
+                    self.handler = handler;
+                    self.events = events;
+                    return true;
+                }
+                Err(RegisterChannelError::DuplicateTopic) => match backoff.next_backoff() {
+                    Some(delay) => time::sleep(delay).await,
+                    None => break,
+                },
+                Err(RegisterChannelError::SocketDropped) => break,
+            }
+        }
+        warn!(collection = %self.collection, "could not rejoin closed channel");
+        false
+    }
+
+    /// Unsubscribes from the collection, sending a leave message and waiting for the reply of the server.
+    ///
+    /// The channel is left even if the server rejects the leave, so the collection can be subscribed to again.
+    pub async fn unsubscribe(self) -> Result<(), ClientError> {
+        self.registry.remove(&self.collection);
+        leave(self.handler).await
+    }
+
+    /// Returns the underlying channel handler of [`phyllo`] and the stream of events.
+    #[cfg(feature = "unstable-phyllo")]
+    pub fn into_parts(
+        self,
+    ) -> (
+        ChannelHandler<Collection, Event, Value, StreamEvent>,
+        EventStream,
+    ) {
+        (self.handler, self.events)
+    }
+}
+
+impl EventSource for Subscription {
+    fn recv(&mut self) -> impl Future<Output = Option<Result<StreamEvent, Error>>> + Send {
+        Subscription::recv(self)
+    }
+}
+
+/// Subscriptions of a client, by [`Collection`].
+///
+/// This keeps the channel handlers of subscriptions so that collections can be unsubscribed from by name.
+/// ```no_run
+/// # use opensea_stream::{client, Collection, Network, Subscriptions};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let mut subscriptions = Subscriptions::new(client(Network::Mainnet, "YOUR_API_KEY_HERE").await);
+/// let collection = Collection::Collection("wandernauts".to_string());
+/// let mut events = subscriptions.subscribe(collection.clone()).await?;
+///
+/// while let Some(event) = events.recv().await {
+///     println!("{:?}", event?);
+///     subscriptions.unsubscribe(&collection).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "unstable-phyllo")]
+#[derive(Debug)]
+pub struct Subscriptions {
+    socket: SocketHandler<Collection>,
+    handlers: HashMap<Collection, ChannelHandler<Collection, Event, Value, StreamEvent>>,
+}
+
+#[cfg(feature = "unstable-phyllo")]
+impl Subscriptions {
+    /// Constructs a new `Subscriptions` without any subscriptions.
+    pub fn new(socket: SocketHandler<Collection>) -> Self {
+        Self {
+            socket,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Subscribes to all the events of a particular [`Collection`].
+    pub async fn subscribe(
+        &mut self,
+        collection: Collection,
+    ) -> Result<EventStream, RegisterChannelError> {
+        self.subscribe_with_config(collection, SubscribeConfig::new())
+            .await
+    }
+
+    /// Subscribes to all the events of a particular [`Collection`] using a custom configuration.
+    pub async fn subscribe_with_config(
+        &mut self,
+        collection: Collection,
+        config: SubscribeConfig,
+    ) -> Result<EventStream, RegisterChannelError> {
+        let (handler, events) =
+            subscribe_stream_with_config(&mut self.socket, collection.clone(), config).await?;
+        self.handlers.insert(collection, handler);
+        Ok(events)
+    }
+
+    /// Unsubscribes from a [`Collection`]. See [`unsubscribe`].
+    pub async fn unsubscribe(&mut self, collection: &Collection) -> Result<(), UnsubscribeError> {
+        let handler = self
+            .handlers
+            .remove(collection)
+            .ok_or_else(|| UnsubscribeError::NotSubscribed(collection.clone()))?;
+        unsubscribe(handler).await
+    }
+
+    /// Returns whether there is a subscription to `collection`.
+    pub fn contains(&self, collection: &Collection) -> bool {
+        self.handlers.contains_key(collection)
+    }
+
+    /// Returns the collections that are subscribed to.
+    pub fn collections(&self) -> impl Iterator<Item = &Collection> {
+        self.handlers.keys()
+    }
+
+    /// Returns the underlying socket.
+    pub fn socket(&self) -> &SocketHandler<Collection> {
+        &self.socket
+    }
+}
+
+/// Set of subscribed collections, returned by [`Client::snapshot`](crate::Client::snapshot) so that they can be
+/// subscribed to again with [`Client::restore`](crate::Client::restore).
+///
+/// Collections are serialized as their topics, e.g. `{"collections":["collection:wandernauts"]}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionSnapshot {
+    /// Subscribed collections, sorted by topic.
+    pub collections: Vec<Collection>,
+}
@@ -0,0 +1,224 @@
+use crate::{schema::StreamEvent, Collection, Event, Network};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    select, SinkExt, Stream, StreamExt,
+};
+use gloo_net::websocket::{futures::WebSocket, Message};
+use gloo_timers::future::IntervalStream;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+use url::Url;
+use wasm_bindgen_futures::spawn_local;
+
+/// Interval between heartbeat messages, in milliseconds.
+const HEARTBEAT_INTERVAL: u32 = 30_000;
+
+/// Error returned by a [`Client`].
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The websocket could not be opened.
+    #[error("could not open websocket: {0}")]
+    Open(String),
+    /// The websocket failed or was closed.
+    #[error("{0}")]
+    WebSocket(String),
+    /// The server replied to the join of a collection with an error, for example because the slug does not exist
+    /// or the API key may not subscribe to it. The collection is no longer subscribed to.
+    #[error("subscription to {collection} rejected: {reason}")]
+    Rejected {
+        /// The collection that was not joined.
+        collection: Collection,
+        /// The `reason` of the reply, or the whole response if it has none.
+        reason: String,
+        /// The response of the reply.
+        payload: Value,
+    },
+}
+
+/// Client for the browser, using the websocket of the browser (through [`gloo-net`](https://crates.io/crates/gloo-net)).
+///
+/// This client implements as much of the Phoenix protocol as events require, and is not a replacement for
+/// [`phyllo`](https://crates.io/crates/phyllo): it does not reconnect or rejoin channels. Events of every
+/// subscription are received from the client itself, which is a [`Stream`]; after an error, the stream ends, except
+/// after [`Error::Rejected`], which only ends the subscription that was rejected.
+/// ```ignore
+/// # use opensea_stream::{wasm::Client, schema, Collection, Network};
+/// # use futures::StreamExt;
+/// # async fn run() -> Result<(), opensea_stream::wasm::Error> {
+/// let mut client = Client::connect(Network::Mainnet, "YOUR_API_KEY_HERE")?;
+/// client.subscribe(Collection::Collection("wandernauts".to_string()));
+///
+/// while let Some(event) = client.next().await {
+///     if let schema::Payload::ItemListed(listing) = event?.payload {
+///         web_sys::console::log_1(&format!("{:?}", listing).into());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Client {
+    outgoing: UnboundedSender<String>,
+    events: UnboundedReceiver<Result<StreamEvent, Error>>,
+    next_ref: u64,
+    joined: HashMap<Collection, String>,
+}
+
+impl Client {
+    /// Opens the websocket of `network`, authenticating with the API key `token`.
+    ///
+    /// Must be called from within a browser (or another environment providing `WebSocket`).
+    pub fn connect(network: Network, token: &str) -> Result<Self, Error> {
+        let mut endpoint: Url = Url::from(network);
+        endpoint
+            .query_pairs_mut()
+            .append_pair("token", token)
+            .append_pair("vsn", "2.0.0");
+        let ws = WebSocket::open(endpoint.as_str()).map_err(|e| Error::Open(e.to_string()))?;
+
+        let (outgoing, outgoing_rx) = mpsc::unbounded();
+        let (events_tx, events) = mpsc::unbounded();
+        spawn_local(run(ws, outgoing_rx, events_tx));
+
+        Ok(Self {
+            outgoing,
+            events,
+            next_ref: 0,
+            joined: HashMap::new(),
+        })
+    }
+
+    /// Subscribes to all the events of a particular [`Collection`]. Subscribing to a collection twice has no effect.
+    pub fn subscribe(&mut self, collection: Collection) {
+        if self.joined.contains_key(&collection) {
+            return;
+        }
+        let join_ref = self.next_ref();
+        self.send(
+            Some(&join_ref),
+            &join_ref,
+            &collection.to_string(),
+            "phx_join",
+        );
+        self.joined.insert(collection, join_ref);
+    }
+
+    /// Unsubscribes from a [`Collection`].
+    pub fn unsubscribe(&mut self, collection: &Collection) {
+        if let Some(join_ref) = self.joined.remove(collection) {
+            let message_ref = self.next_ref();
+            self.send(
+                Some(&join_ref),
+                &message_ref,
+                &collection.to_string(),
+                "phx_leave",
+            );
+        }
+    }
+
+    fn next_ref(&mut self) -> String {
+        self.next_ref += 1;
+        self.next_ref.to_string()
+    }
+
+    fn send(&self, join_ref: Option<&str>, message_ref: &str, topic: &str, event: &str) {
+        // If the connection task has ended, the error has already been sent to the stream.
+        let _ = self
+            .outgoing
+            .unbounded_send(json!([join_ref, message_ref, topic, event, {}]).to_string());
+    }
+}
+
+impl Stream for Client {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let next = self.events.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Err(Error::Rejected { collection, .. }))) = &next {
+            // Allows subscribing to the collection again.
+            self.joined.remove(collection);
+        }
+        next
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // Ends the connection task, which closes the websocket.
+        self.outgoing.close_channel();
+    }
+}
+
+/// Relays messages between the websocket and the [`Client`], sending heartbeats in between.
+async fn run(
+    ws: WebSocket,
+    mut outgoing: UnboundedReceiver<String>,
+    events: UnboundedSender<Result<StreamEvent, Error>>,
+) {
+    let mut ws = ws.fuse();
+    let mut heartbeat = IntervalStream::new(HEARTBEAT_INTERVAL).fuse();
+    let mut heartbeat_ref = 0u64;
+
+    loop {
+        let result = select! {
+            message = outgoing.next() => match message {
+                Some(message) => ws.send(Message::Text(message)).await,
+                None => break,
+            },
+            _ = heartbeat.next() => {
+                heartbeat_ref += 1;
+                let message = json!([null, format!("heartbeat:{}", heartbeat_ref), "phoenix", "heartbeat", {}]);
+                ws.send(Message::Text(message.to_string())).await
+            },
+            message = ws.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Some(item) = parse(&text) {
+                        let _ = events.unbounded_send(item);
+                    }
+                    Ok(())
+                }
+                Some(Ok(Message::Bytes(_))) => Ok(()),
+                Some(Err(e)) => Err(e),
+                None => break,
+            },
+        };
+
+        if let Err(e) = result {
+            let _ = events.unbounded_send(Err(Error::WebSocket(e.to_string())));
+            break;
+        }
+    }
+
+    let _ = ws.into_inner().close(None, None);
+}
+
+/// Parses a Phoenix message (`[join_ref, ref, topic, event, payload]`), returning its payload if it is an event,
+/// or an error if it is an error reply to a join.
+fn parse(text: &str) -> Option<Result<StreamEvent, Error>> {
+    let (join_ref, message_ref, topic, event, payload): (Value, Value, String, String, Value) =
+        serde_json::from_str(text).ok()?;
+    if event == "phx_reply" {
+        // Joins are sent with the same join and message reference; other replies are not reported.
+        if join_ref.is_null() || join_ref != message_ref || payload["status"] != "error" {
+            return None;
+        }
+        let collection = serde_json::from_value(Value::String(topic)).ok()?;
+        let payload = payload["response"].clone();
+        let reason = match &payload["reason"] {
+            Value::String(reason) => reason.clone(),
+            _ => payload.to_string(),
+        };
+        return Some(Err(Error::Rejected {
+            collection,
+            reason,
+            payload,
+        }));
+    }
+    event.parse::<Event>().ok()?;
+    serde_json::from_value(payload).ok().map(Ok)
+}
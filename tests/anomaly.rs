@@ -0,0 +1,82 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use common::fixture;
+use opensea_stream::{
+    anomaly::{Anomaly, AnomalyDetector},
+    schema::{Payload, StreamEvent},
+};
+
+fn start() -> DateTime<Utc> {
+    "2022-07-19T18:00:00Z".parse().unwrap()
+}
+
+/// A listing of a single item for `eth`, sent `secs` seconds after the start.
+fn listing(secs: i64, eth: f64) -> StreamEvent {
+    let mut event = fixture("item_listed.json");
+    event.sent_at = start() + Duration::seconds(secs);
+    if let Payload::ItemListed(v) = &mut event.payload {
+        v.base_price = ((eth * 1e9) as u64 * 1_000_000_000u64).into();
+        v.quantity = 1;
+    }
+    event
+}
+
+/// A sale of a single item for `eth`, sent `secs` seconds after the start.
+fn sale(secs: i64, eth: f64) -> StreamEvent {
+    let mut event = fixture("item_sold.json");
+    event.sent_at = start() + Duration::seconds(secs);
+    if let Payload::ItemSold(v) = &mut event.payload {
+        v.sale_price = ((eth * 1e9) as u64 * 1_000_000_000u64).into();
+        v.quantity = 1;
+    }
+    event
+}
+
+#[test]
+fn listing_floods_are_reported_once() {
+    let detector = AnomalyDetector::new()
+        .window(std::time::Duration::from_secs(600))
+        .flood_window(std::time::Duration::from_secs(60))
+        .flood_factor(2.0)
+        .min_listings(5);
+
+    // A listing every minute for the first window.
+    for minute in 0..10 {
+        assert!(detector.record(&listing(minute * 60, 1.0)).is_empty());
+    }
+
+    let anomalies: Vec<_> = (0..10)
+        .flat_map(|i| detector.record(&listing(600 + i, 1.0)))
+        .collect();
+    assert_eq!(anomalies.len(), 1);
+    let Anomaly::ListingFlood { rate, baseline, .. } = &anomalies[0] else {
+        panic!("expected a listing flood, got {:?}", anomalies[0]);
+    };
+    assert_eq!(*rate, 5.0 / 60.0);
+    assert_eq!(*baseline, 9.0 / 540.0);
+}
+
+#[test]
+fn sales_far_below_the_floor_are_reported() {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let detector = AnomalyDetector::new()
+        .min_sales(4)
+        .sigmas(3.0)
+        .anomalies(tx);
+
+    detector.record(&listing(0, 1.0));
+    for (i, eth) in [0.9, 1.1, 1.0, 1.0].into_iter().enumerate() {
+        assert!(detector.record(&sale(i as i64, eth)).is_empty());
+    }
+    // Within three standard deviations (about 0.07 ETH) of the floor.
+    assert!(detector.record(&sale(10, 0.8)).is_empty());
+
+    let anomalies = detector.record(&sale(11, 0.1));
+    assert_eq!(anomalies.len(), 1);
+    assert!(matches!(
+        &anomalies[0],
+        Anomaly::LowSale { price, floor, .. } if *price == 0.1 && *floor == 1.0
+    ));
+    assert_eq!(rx.try_recv().unwrap(), anomalies[0]);
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use common::{events, fixture};
+use opensea_stream::{
+    backfill::{convert, Bootstrapped, Spliced},
+    schema::Payload,
+    Event,
+};
+use serde_json::json;
+use tokio::sync::mpsc;
+
+#[test]
+fn sale_is_converted() {
+    let event = convert(&json!({
+        "event_type": "sale",
+        "order_hash": "0x1a2b36a6d1ce3e34557d9b47f4c54b34efa4b6bd6da66a7f83a73a4d3f2a4c7e",
+        "chain": "ethereum",
+        "protocol_address": "0x00000000000000adc04c56bf30ac9d3c0aaf14dc",
+        "closing_date": 1658256311,
+        "nft": {
+            "identifier": "1",
+            "collection": "wandernauts",
+            "contract": "0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4",
+            "token_standard": "erc721",
+            "name": "Wandernaut #1",
+            "description": null,
+            "image_url": "https://i.seadn.io/gae/wandernaut-1.png",
+            "metadata_url": "",
+            "opensea_url": "https://opensea.io/assets/ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4/1"
+        },
+        "payment": {
+            "quantity": "50000000000000000",
+            "token_address": "0x0000000000000000000000000000000000000000",
+            "decimals": 18,
+            "symbol": "ETH"
+        },
+        "quantity": 1,
+        "seller": "0x2f29f5d0d4388ee3e0b3d1b8b1e2db7bf1b2f0d2",
+        "buyer": "0x8e1a0d4a3f2a0aa3d3b6b2e2b6c1c4e5d8e3f9a1",
+        "transaction": "0x5e2b1c70a8d0f4c5bb0e3c6f1e8a1fd4e47a36c0ad3b1d5b2d24c51f3a6bd9e2",
+        "event_timestamp": 1658256311
+    }))
+    .unwrap();
+
+    let sale = match event.payload {
+        Payload::ItemSold(sale) => sale,
+        _ => panic!("expected item_sold"),
+    };
+    assert_eq!(sale.context.collection.0, "wandernauts");
+    assert_eq!(sale.sale_price, 50000000000000000u64.into());
+    assert_eq!(sale.context.item.metadata.metadata_url, None);
+}
+
+#[tokio::test]
+async fn live_events_already_in_history_are_skipped() {
+    let live = events(["item_listed.json", "item_sold.json"].map(fixture));
+    let mut events = Spliced::new(vec![fixture("item_listed.json")], live);
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemListed);
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemSold);
+    assert!(events.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn bootstrap_merges_buffered_live_events_into_history() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tx.send(Ok(fixture("item_received_offer.json"))).unwrap();
+    tx.send(Ok(fixture("item_cancelled.json"))).unwrap();
+
+    let history = vec![
+        fixture("item_listed.json"),
+        fixture("item_received_offer.json"),
+    ];
+    let mut events = Bootstrapped::new(history, rx);
+    tx.send(Ok(fixture("item_listed.json"))).unwrap();
+    tx.send(Ok(fixture("trait_offer.json"))).unwrap();
+    drop(tx);
+
+    let mut received = Vec::new();
+    while let Some(event) = events.recv().await {
+        received.push(event.unwrap().payload.event());
+    }
+    assert_eq!(
+        received,
+        [
+            Event::ItemListed,
+            Event::ItemCancelled,
+            Event::ItemReceivedOffer,
+            Event::TraitOffer,
+        ]
+    );
+}
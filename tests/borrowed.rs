@@ -0,0 +1,38 @@
+mod common;
+
+use common::fixture_json;
+use opensea_stream::{borrowed::StreamEventRef, Event};
+use serde_json::Value;
+use std::borrow::Cow;
+
+#[test]
+fn every_fixture_deserializes_borrowed() {
+    for event in Event::ALL {
+        let json = fixture_json(&format!("{}.json", event));
+        let borrowed = StreamEventRef::from_str(&json).unwrap();
+        assert_eq!(borrowed.event_type, *event);
+
+        let owned = borrowed.to_owned().unwrap();
+        assert_eq!(owned.payload.event(), *event);
+        assert_eq!(borrowed.timestamp(), owned.timestamp());
+        assert_eq!(borrowed.payload.collection(), owned.payload.collection().0);
+    }
+}
+
+#[test]
+fn strings_are_borrowed() {
+    let json = fixture_json("item_sold.json");
+    let event = StreamEventRef::from_str(&json).unwrap();
+    let item = event.payload.item.as_ref().unwrap();
+    assert!(matches!(item.nft_id, Cow::Borrowed(_)));
+    assert_eq!(event.payload.price(), Some("50000000000000000"));
+    assert_eq!(
+        event.payload.taker.unwrap().address,
+        "0x8e1a0d4a3f2a0aa3d3b6b2e2b6c1c4e5d8e3f9a1"
+    );
+
+    let value: Value = serde_json::from_str(&json).unwrap();
+    let event = StreamEventRef::from_value(&value).unwrap();
+    assert!(matches!(event.payload.collection.slug, Cow::Borrowed(_)));
+    assert_eq!(event.to_owned().unwrap().payload.event(), Event::ItemSold);
+}
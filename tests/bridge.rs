@@ -0,0 +1,121 @@
+#![cfg(feature = "rustls-config")]
+
+mod common;
+
+use backoff::ExponentialBackoff;
+use common::{fixture_value, mock_server, mock_server_rejecting, MockServer};
+use opensea_stream::{
+    Client, ClientBuilder, ClientError, Collection, Network, SubscribeManyConfig,
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::timeout;
+
+async fn next_join(server: &mut MockServer) -> String {
+    timeout(Duration::from_secs(10), server.joins.recv())
+        .await
+        .expect("no join received")
+        .unwrap()
+}
+
+async fn connect(builder: ClientBuilder, server: &MockServer) -> Client {
+    builder
+        .endpoint(server.url.clone())
+        .connect()
+        .await
+        .unwrap()
+}
+
+fn push(server: &MockServer, slug: &str) {
+    let mut event = fixture_value("item_sold.json");
+    event["payload"]["collection"]["slug"] = slug.into();
+    let message = json!([null, null, "collection:wandernauts", "item_sold", event]);
+    server.push.send(message).unwrap();
+}
+
+#[tokio::test]
+async fn messages_larger_than_the_limit_are_dropped() {
+    let mut server = mock_server().await;
+    let builder = ClientBuilder::new(Network::Mainnet, "key").max_message_bytes(4096);
+    let mut client = connect(builder, &server).await;
+    let mut subscription = client
+        .subscribe(Collection::Collection("wandernauts".to_owned()))
+        .await
+        .unwrap();
+    assert_eq!(next_join(&mut server).await, "collection:wandernauts");
+
+    push(&server, &"a".repeat(4096));
+    push(&server, "wandernauts");
+    let event = timeout(Duration::from_secs(10), subscription.recv())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.payload.collection().0, "wandernauts");
+}
+
+#[tokio::test]
+async fn rejected_joins_are_reported() {
+    let mut server = mock_server_rejecting(&["collection:missing"]).await;
+    let mut client = connect(ClientBuilder::new(Network::Mainnet, "key"), &server).await;
+
+    let result = client
+        .subscribe(Collection::Collection("missing".to_owned()))
+        .await;
+    assert_eq!(
+        result.err(),
+        Some(ClientError::Rejected(json!({ "reason": "unauthorized" })))
+    );
+    assert_eq!(next_join(&mut server).await, "collection:missing");
+    assert!(client.subscriptions().is_empty());
+
+    // Accepted joins are not affected.
+    client
+        .subscribe(Collection::Collection("wandernauts".to_owned()))
+        .await
+        .unwrap();
+    assert_eq!(next_join(&mut server).await, "collection:wandernauts");
+    assert_eq!(
+        client.subscriptions(),
+        [Collection::Collection("wandernauts".to_owned())]
+    );
+}
+
+#[tokio::test]
+async fn subscribe_many_retries_rejected_joins_then_reports_them() {
+    let mut server = mock_server_rejecting(&["collection:missing"]).await;
+    let mut client = connect(ClientBuilder::new(Network::Mainnet, "key"), &server).await;
+
+    let rejoin = ExponentialBackoff {
+        initial_interval: Duration::from_millis(10),
+        max_elapsed_time: Some(Duration::from_millis(50)),
+        ..ExponentialBackoff::default()
+    };
+    let missing = Collection::Collection("missing".to_owned());
+    let wandernauts = Collection::Collection("wandernauts".to_owned());
+    let results = client
+        .subscribe_many_with_config(
+            [missing.clone(), wandernauts.clone()],
+            SubscribeManyConfig::new()
+                .interval(Duration::from_millis(1))
+                .rejoin(rejoin),
+        )
+        .await;
+
+    assert_eq!(
+        results[&missing].as_ref().err(),
+        Some(&ClientError::Rejected(json!({ "reason": "unauthorized" })))
+    );
+    assert!(results[&wandernauts].is_ok());
+    assert_eq!(client.subscriptions(), [wandernauts]);
+
+    let mut joins = Vec::new();
+    while let Ok(join) = server.joins.try_recv() {
+        joins.push(join);
+    }
+    assert!(joins.len() > 2, "{:?}", joins);
+    assert!(joins[..joins.len() - 1]
+        .iter()
+        .all(|j| j == "collection:missing"));
+    assert_eq!(joins.last().unwrap(), "collection:wandernauts");
+}
@@ -0,0 +1,165 @@
+#![cfg(feature = "unstable-phyllo")]
+
+mod common;
+
+use common::{fixture_value, mock_server, MockServer};
+use opensea_stream::{
+    phyllo::socket::SocketBuilder, subscribe_many_with_config, Client, CloseReason, Collection,
+    Error, SubscribeConfig, SubscribeManyConfig,
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::timeout;
+
+async fn client(server: &MockServer) -> Client {
+    Client::from_socket(SocketBuilder::new(server.url.clone()).build().await)
+}
+
+async fn next_join(server: &mut MockServer) -> String {
+    timeout(Duration::from_secs(10), server.joins.recv())
+        .await
+        .expect("no join received")
+        .unwrap()
+}
+
+fn collection(slug: &str) -> Collection {
+    Collection::Collection(slug.to_owned())
+}
+
+#[tokio::test]
+async fn subscriptions_are_tracked_and_restored() {
+    let mut server = mock_server().await;
+    let mut client = client(&server).await;
+
+    let wandernauts = client.subscribe(collection("wandernauts")).await.unwrap();
+    let _apes = client
+        .subscribe(collection("boredapeyachtclub"))
+        .await
+        .unwrap();
+    assert_eq!(next_join(&mut server).await, "collection:wandernauts");
+    assert_eq!(next_join(&mut server).await, "collection:boredapeyachtclub");
+    assert_eq!(
+        client.clone().subscriptions(),
+        [collection("boredapeyachtclub"), collection("wandernauts")]
+    );
+
+    wandernauts.unsubscribe().await.unwrap();
+    let snapshot = client.snapshot();
+    assert_eq!(snapshot.collections, [collection("boredapeyachtclub")]);
+    client.close();
+
+    let mut replacement = self::client(&server).await;
+    let restored = replacement.restore(snapshot.clone()).await;
+    assert_eq!(restored.len(), 1);
+    assert!(restored[&collection("boredapeyachtclub")].is_ok());
+    assert_eq!(next_join(&mut server).await, "collection:boredapeyachtclub");
+    assert_eq!(replacement.subscriptions(), snapshot.collections);
+
+    // Collections that are already subscribed to are skipped.
+    assert!(replacement.restore(snapshot).await.is_empty());
+}
+
+#[tokio::test]
+async fn server_closes_are_delivered_and_rejoined() {
+    let mut server = mock_server().await;
+    let mut client = client(&server).await;
+
+    let mut rejoined = client
+        .subscribe_with_config(
+            collection("wandernauts"),
+            SubscribeConfig::new().rejoin_on_close(true),
+        )
+        .await
+        .unwrap();
+    let mut closed = client
+        .subscribe(collection("boredapeyachtclub"))
+        .await
+        .unwrap();
+    next_join(&mut server).await;
+    next_join(&mut server).await;
+
+    for topic in ["collection:wandernauts", "collection:boredapeyachtclub"] {
+        server
+            .push
+            .send(json!([null, null, topic, "phx_close", {}]))
+            .unwrap();
+    }
+
+    let next = timeout(Duration::from_secs(10), closed.recv())
+        .await
+        .unwrap();
+    assert!(matches!(
+        next,
+        Some(Err(Error::Closed {
+            reason: CloseReason::Server
+        }))
+    ));
+    assert!(closed.recv().await.is_none());
+
+    let next = timeout(Duration::from_secs(10), rejoined.recv())
+        .await
+        .unwrap();
+    assert!(matches!(
+        next,
+        Some(Err(Error::Closed {
+            reason: CloseReason::Server
+        }))
+    ));
+    assert_eq!(next_join(&mut server).await, "collection:wandernauts");
+    assert_eq!(client.subscriptions(), [collection("wandernauts")]);
+}
+
+#[tokio::test]
+async fn client_records_events_of_its_subscriptions() {
+    let mut server = mock_server().await;
+    let mut client = client(&server).await;
+    let mut subscription = client.subscribe(collection("wandernauts")).await.unwrap();
+    next_join(&mut server).await;
+
+    let event = fixture_value("item_sold.json");
+    server
+        .push
+        .send(json!([
+            null,
+            null,
+            "collection:wandernauts",
+            "item_sold",
+            event
+        ]))
+        .unwrap();
+    timeout(Duration::from_secs(10), subscription.recv())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    let snapshot = client.stats().snapshot();
+    assert_eq!(snapshot.total_events, 1);
+    assert_eq!(snapshot.by_collection["wandernauts"], 0.1);
+    assert!(snapshot.lag.is_some());
+    // Sockets of phyllo do not report reconnects.
+    assert_eq!(snapshot.reconnects, None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn subscribe_many_paces_joins_and_skips_duplicates() {
+    let mut server = mock_server().await;
+    let mut socket = SocketBuilder::new(server.url.clone()).build().await;
+
+    let start = tokio::time::Instant::now();
+    let results = subscribe_many_with_config(
+        &mut socket,
+        ["wandernauts", "boredapeyachtclub", "wandernauts", "azuki"].map(collection),
+        SubscribeManyConfig::new().interval(Duration::from_secs(10)),
+    )
+    .await;
+    // The first join is immediate.
+    assert_eq!(start.elapsed(), Duration::from_secs(20));
+    assert_eq!(results.len(), 3);
+    assert!(results.values().all(Result::is_ok));
+
+    for slug in ["wandernauts", "boredapeyachtclub", "azuki"] {
+        assert_eq!(next_join(&mut server).await, format!("collection:{}", slug));
+    }
+    assert!(server.joins.try_recv().is_err());
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "unstable-phyllo")]
+
+mod common;
+
+use common::{fixture, message};
+use opensea_stream::{coalesce::Coalesce, Event, EventStream};
+use std::time::Duration;
+use tokio::{sync::broadcast, time::Instant};
+
+#[tokio::test(start_paused = true)]
+async fn updates_within_the_window_are_coalesced() {
+    let (tx, rx) = broadcast::channel(16);
+    for _ in 0..3 {
+        tx.send(message(fixture("item_metadata_updated.json")))
+            .unwrap();
+    }
+    tx.send(message(fixture("item_listed.json"))).unwrap();
+    drop(tx);
+
+    let coalesce = Coalesce::new(Duration::from_secs(5));
+    let mut events = EventStream::new(rx).coalesce(coalesce.clone());
+    let start = Instant::now();
+
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemListed);
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemMetadataUpdated);
+    assert_eq!(start.elapsed(), Duration::from_secs(5));
+
+    assert!(events.recv().await.is_none());
+    assert_eq!(coalesce.coalesced(), 2);
+}
@@ -0,0 +1,201 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use futures_util::{SinkExt, StreamExt};
+#[cfg(feature = "unstable-phyllo")]
+use opensea_stream::{
+    phyllo::message::{self as phoenix, Event as MessageEvent, Payload},
+    Collection, Event,
+};
+use opensea_stream::{schema::StreamEvent, Error, EventSource};
+use serde_json::{json, Value};
+use std::{collections::VecDeque, fs, path::PathBuf};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+/// Returns the contents of `tests/fixtures/<name>`.
+pub fn fixture_json(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    fs::read_to_string(path).unwrap()
+}
+
+/// Returns the fixture `name` as a JSON value.
+pub fn fixture_value(name: &str) -> Value {
+    serde_json::from_str(&fixture_json(name)).unwrap()
+}
+
+/// Returns the fixture `name` as an event.
+pub fn fixture(name: &str) -> StreamEvent {
+    serde_json::from_str(&fixture_json(name)).unwrap()
+}
+
+/// Returns the fixture `name` as an event of the collection `slug`.
+pub fn fixture_in(name: &str, slug: &str) -> StreamEvent {
+    let mut json = fixture_value(name);
+    json["payload"]["collection"]["slug"] = slug.into();
+    serde_json::from_value(json).unwrap()
+}
+
+/// Source that yields its items in order, then ends.
+pub struct Events(pub VecDeque<Result<StreamEvent, Error>>);
+
+impl EventSource for Events {
+    async fn recv(&mut self) -> Option<Result<StreamEvent, Error>> {
+        self.0.pop_front()
+    }
+}
+
+/// Returns a source that yields `events`, then ends.
+pub fn events(events: impl IntoIterator<Item = StreamEvent>) -> Events {
+    Events(events.into_iter().map(Ok).collect())
+}
+
+/// Message of a subscription, with events deserialized into [`StreamEvent`]s.
+#[cfg(feature = "unstable-phyllo")]
+pub type StreamMessage = phoenix::Message<Collection, Event, Value, StreamEvent>;
+
+/// Wraps an event in a message, as received from a subscription.
+#[cfg(feature = "unstable-phyllo")]
+pub fn message(event: StreamEvent) -> StreamMessage {
+    phoenix::Message::new(
+        0,
+        0,
+        Collection::All,
+        MessageEvent::Event(event.payload.event()),
+        Some(Payload::Custom(event)),
+    )
+}
+
+/// A Phoenix server on the loopback interface that accepts every join and leave.
+pub struct MockServer {
+    /// Endpoint of the server.
+    pub url: Url,
+    /// Topics of the joins received, in order.
+    pub joins: mpsc::UnboundedReceiver<String>,
+    /// Messages (`[join_ref, ref, topic, event, payload]`) to send to the connected client.
+    pub push: mpsc::UnboundedSender<Value>,
+}
+
+/// Starts a [`MockServer`], which serves one connection at a time.
+pub async fn mock_server() -> MockServer {
+    mock_server_rejecting(&[]).await
+}
+
+/// Starts a [`MockServer`] which rejects the joins of `rejected` topics with `{"reason": "unauthorized"}`.
+pub async fn mock_server_rejecting(rejected: &[&str]) -> MockServer {
+    let rejected: Vec<String> = rejected.iter().map(|t| t.to_string()).collect();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!(
+        "ws://{}/socket/websocket",
+        listener.local_addr().unwrap()
+    ))
+    .unwrap();
+    let (joins_tx, joins) = mpsc::unbounded_channel();
+    let (push, mut push_rx) = mpsc::unbounded_channel::<Value>();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut ws = match tokio_tungstenite::accept_async(stream).await {
+                Ok(ws) => ws,
+                Err(_) => continue,
+            };
+            loop {
+                tokio::select! {
+                    message = ws.next() => {
+                        let text = match message {
+                            Some(Ok(Message::Text(text))) => text,
+                            Some(Ok(_)) => continue,
+                            _ => break,
+                        };
+                        let (join_ref, reference, topic, event, _): (Value, Value, String, String, Value) =
+                            serde_json::from_str(&text).unwrap();
+                        if event == "phx_join" {
+                            let _ = joins_tx.send(topic.clone());
+                        }
+                        let reply = match event == "phx_join" && rejected.contains(&topic) {
+                            true => json!({ "status": "error", "response": { "reason": "unauthorized" } }),
+                            false => json!({ "status": "ok", "response": {} }),
+                        };
+                        let reply = json!([join_ref, reference, topic, "phx_reply", reply]);
+                        if ws.send(Message::Text(reply.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(message) = push_rx.recv() => {
+                        if ws.send(Message::Text(message.to_string())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    MockServer { url, joins, push }
+}
+
+/// An HTTP server on the loopback interface that answers requests with canned responses.
+pub struct MockHttp {
+    /// Base URL of the server.
+    pub url: Url,
+    /// Heads (request line and headers) and bodies of the requests received, in order.
+    pub requests: mpsc::UnboundedReceiver<(String, String)>,
+}
+
+/// Starts a [`MockHttp`] which answers the `n`th request, with head `head`, with `responses(n, head)`: a status code
+/// followed by headers, such as `"429 Too Many Requests\r\nRetry-After: 1"`, and a body.
+pub async fn mock_http<F>(responses: F) -> MockHttp
+where
+    F: Fn(usize, &str) -> (&'static str, String) + Send + 'static,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let (tx, requests) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut n = 0;
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut stream = BufReader::new(stream);
+            let mut head = String::new();
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        len = value.trim().parse().unwrap();
+                    }
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            if head.is_empty() {
+                continue;
+            }
+            let mut body = vec![0; len];
+            stream.read_exact(&mut body).await.unwrap();
+            let (status, response) = responses(n, &head);
+            let _ = tx.send((head, String::from_utf8(body).unwrap()));
+
+            n += 1;
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            );
+            let _ = stream.get_mut().write_all(response.as_bytes()).await;
+        }
+    });
+
+    MockHttp { url, requests }
+}
@@ -0,0 +1,153 @@
+#![cfg(feature = "config")]
+
+use opensea_stream::{
+    config::{Config, ConfigError, CONFIG_VAR},
+    Collection, Event, Network,
+};
+use std::{env, fs};
+
+const TOML: &str = r#"
+api_key = "from-file"
+network = "testnet"
+collections = ["wandernauts", "azuki"]
+events = ["item_listed"]
+
+[sinks.file]
+path = "events.csv"
+format = "csv"
+max_age_secs = 3600
+"#;
+
+#[test]
+fn toml_and_json_configs_are_equivalent() {
+    let toml = Config::from_toml(TOML).unwrap();
+    let json = Config::from_json(
+        r#"{
+            "api_key": "from-file",
+            "network": "testnet",
+            "collections": ["wandernauts", "azuki"],
+            "events": ["item_listed"],
+            "sinks": {"file": {"path": "events.csv", "format": "csv", "max_age_secs": 3600}}
+        }"#,
+    )
+    .unwrap();
+
+    for config in [toml, json] {
+        assert_eq!(config.network, Network::Testnet);
+        assert_eq!(
+            config.collections(),
+            [
+                Collection::Collection("wandernauts".to_string()),
+                Collection::Collection("azuki".to_string())
+            ]
+        );
+        assert_eq!(config.events, [Event::ItemListed]);
+        assert_eq!(config.sinks.file.unwrap().max_age_secs, Some(3600));
+    }
+
+    assert!(matches!(
+        Config::from_toml("api_key = \"key\"\nchannel = \"#nfts\""),
+        Err(ConfigError::Invalid(_))
+    ));
+}
+
+#[test]
+fn environment_overrides_the_config_file() {
+    let path = env::temp_dir().join(format!("opensea-stream-{}.toml", std::process::id()));
+    fs::write(&path, TOML).unwrap();
+
+    env::remove_var("OPENSEA_API_KEY");
+    assert!(matches!(
+        Config::from_env(),
+        Err(ConfigError::Missing("OPENSEA_API_KEY"))
+    ));
+
+    env::set_var(CONFIG_VAR, &path);
+    env::set_var("OPENSEA_API_KEY", "from-env");
+    env::set_var("OPENSEA_EVENTS", "item_sold, item_cancelled");
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.api_key, "from-env");
+    assert_eq!(config.network, Network::Testnet);
+    assert_eq!(config.events, [Event::ItemSold, Event::ItemCancelled]);
+
+    // The API key can be left out of the file when it is set in the environment.
+    let keyless = env::temp_dir().join(format!(
+        "opensea-stream-keyless-{}.toml",
+        std::process::id()
+    ));
+    fs::write(&keyless, "network = \"testnet\"\n").unwrap();
+    assert!(matches!(
+        Config::from_file(&keyless),
+        Err(ConfigError::Missing("api_key"))
+    ));
+    env::set_var(CONFIG_VAR, &keyless);
+    let config = Config::from_env().unwrap();
+    assert_eq!(config.api_key, "from-env");
+    assert_eq!(config.network, Network::Testnet);
+    env::remove_var("OPENSEA_API_KEY");
+    assert!(matches!(
+        Config::from_env(),
+        Err(ConfigError::Missing("OPENSEA_API_KEY"))
+    ));
+    fs::remove_file(keyless).unwrap();
+    env::set_var(CONFIG_VAR, &path);
+    env::set_var("OPENSEA_API_KEY", "from-env");
+
+    env::set_var("OPENSEA_NETWORK", "moonnet");
+    assert!(matches!(
+        Config::from_env(),
+        Err(ConfigError::InvalidVar {
+            var: "OPENSEA_NETWORK",
+            ..
+        })
+    ));
+
+    for var in [
+        CONFIG_VAR,
+        "OPENSEA_API_KEY",
+        "OPENSEA_EVENTS",
+        "OPENSEA_NETWORK",
+    ] {
+        env::remove_var(var);
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "mqtt")]
+#[test]
+fn qos_above_two_is_rejected() {
+    let mqtt = |qos: u8| {
+        Config::from_toml(&format!(
+            "api_key = \"key\"\n[sinks.mqtt]\nhost = \"localhost\"\nqos = {}",
+            qos
+        ))
+    };
+    assert_eq!(mqtt(2).unwrap().sinks.mqtt.unwrap().qos, 2);
+    assert!(matches!(mqtt(3), Err(ConfigError::Invalid(_))));
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn contracts_follow_the_collections() {
+    let config = Config::from_toml(
+        "api_key = \"key\"\n\
+         collections = [\"wandernauts\"]\n\
+         contracts = [\"ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4\"]",
+    )
+    .unwrap();
+    let contract = "ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        config.collections(),
+        [
+            Collection::Collection("wandernauts".to_string()),
+            Collection::ContractAddress(contract)
+        ]
+    );
+
+    assert!(matches!(
+        Config::from_toml("api_key = \"key\"\ncontracts = [\"0x6e3e\"]"),
+        Err(ConfigError::Invalid(_))
+    ));
+}
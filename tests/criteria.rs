@@ -0,0 +1,71 @@
+mod common;
+
+use common::fixture;
+use ethers_core::{
+    types::{H256, U256},
+    utils::keccak256,
+};
+use opensea_stream::{
+    criteria::{Attribute, TokenIds},
+    schema::{Chain, NftId, Payload},
+};
+
+fn nft(id: u64) -> NftId {
+    NftId {
+        network: Chain::Ethereum,
+        address: "0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4"
+            .parse()
+            .unwrap(),
+        id: id.into(),
+    }
+}
+
+#[test]
+fn trait_offer_criteria() {
+    let Payload::TraitOffer(offer) = fixture("trait_offer.json").payload else {
+        panic!("expected a trait offer");
+    };
+    let criteria = offer.criteria();
+
+    let red: Vec<Attribute> = serde_json::from_str(
+        r#"[{"trait_type":"Background","value":"Red"},{"trait_type":"Level","value":5}]"#,
+    )
+    .unwrap();
+    assert_eq!(red[1], Attribute::new("Level", "5"));
+    assert!(criteria.matches(&nft(1), &red));
+    assert!(!criteria.matches(&nft(1), &[Attribute::new("Background", "Blue")]));
+
+    let criteria = criteria.token_ids("1,3:5".parse().unwrap());
+    assert!(criteria.matches(&nft(4), &red));
+    assert!(!criteria.matches(&nft(2), &red));
+}
+
+#[test]
+fn token_ids_round_trip() {
+    let ids: TokenIds = "1,3:5, 10".parse().unwrap();
+    assert_eq!(ids.to_string(), "1,3:5,10");
+    assert!(ids.contains(U256::from(10)));
+    assert_eq!("*".parse::<TokenIds>().unwrap(), TokenIds::All);
+    assert!("1:x".parse::<TokenIds>().is_err());
+}
+
+#[test]
+fn merkle_proofs() {
+    let leaf = |id: u64| {
+        let mut word = [0; 32];
+        U256::from(id).to_big_endian(&mut word);
+        H256(keccak256(word))
+    };
+    let (a, b) = (leaf(3), leaf(8));
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let root = H256(keccak256([lo.as_bytes(), hi.as_bytes()].concat()));
+
+    let Payload::CollectionOffer(offer) = fixture("collection_offer.json").payload else {
+        panic!("expected a collection offer");
+    };
+    let criteria = offer.criteria().merkle_root(root);
+    assert!(!criteria.matches(&nft(3), &[]));
+    assert!(criteria.matches_with_proof(&nft(3), &[], &[b]));
+    assert!(criteria.matches_with_proof(&nft(8), &[], &[a]));
+    assert!(!criteria.matches_with_proof(&nft(9), &[], &[a]));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::fixture_value;
+use opensea_stream::{
+    cursor::{CursorStore, FileStore, MemoryStore, Resume, Status},
+    schema::StreamEvent,
+};
+use std::{fs, path::PathBuf, time::Duration};
+
+/// Returns a sale of item `token_id` of the sale fixture, `seconds` after the fixture, in the fixture's transaction.
+fn sale(token_id: u64, seconds: i64) -> StreamEvent {
+    let mut json = fixture_value("item_sold.json");
+    let timestamp = "2022-07-19T18:45:11Z"
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .unwrap()
+        + chrono::Duration::seconds(seconds);
+    let payload = &mut json["payload"];
+    payload["item"]["nft_id"] = format!(
+        "ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4/{}",
+        token_id
+    )
+    .into();
+    payload["event_timestamp"] = timestamp.to_rfc3339().into();
+    serde_json::from_value(json).unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("opensea-stream-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn sales_sharing_a_transaction_are_both_new() {
+    let mut resume = Resume::new(MemoryStore::new());
+    assert_eq!(resume.process(&sale(1, 0)).unwrap(), Status::New);
+    assert_eq!(resume.process(&sale(2, 0)).unwrap(), Status::New);
+    assert_eq!(resume.process(&sale(1, 0)).unwrap(), Status::Duplicate);
+}
+
+#[test]
+fn out_of_order_events_within_the_tolerance_are_new() {
+    let mut resume = Resume::new(MemoryStore::new()).tolerance(Duration::from_secs(60));
+    assert_eq!(resume.process(&sale(1, 30)).unwrap(), Status::New);
+    assert_eq!(resume.process(&sale(2, 0)).unwrap(), Status::New);
+    assert_eq!(resume.process(&sale(2, 0)).unwrap(), Status::Duplicate);
+    // Too old to tell whether it was seen.
+    assert_eq!(resume.process(&sale(3, -60)).unwrap(), Status::Duplicate);
+}
+
+#[test]
+fn restart_reports_the_gap_and_skips_replayed_events() {
+    let dir = temp_dir("restart");
+    let mut resume = Resume::new(FileStore::new(&dir).unwrap());
+    resume.process(&sale(1, 0)).unwrap();
+    resume.process(&sale(2, 10)).unwrap();
+    resume.flush().unwrap();
+
+    let mut resume = Resume::new(FileStore::new(&dir).unwrap());
+    assert_eq!(resume.process(&sale(1, 0)).unwrap(), Status::Duplicate);
+    match resume.process(&sale(3, 100)).unwrap() {
+        Status::Resumed(gap) => {
+            assert_eq!(gap.collection, "wandernauts");
+            assert_eq!(gap.from, sale(2, 10).timestamp());
+            assert_eq!(gap.to, sale(3, 100).timestamp());
+        }
+        status => panic!("unexpected status {:?}", status),
+    }
+    assert_eq!(resume.process(&sale(4, 101)).unwrap(), Status::New);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn saves_are_batched_until_flushed() {
+    let dir = temp_dir("batched");
+    let mut resume =
+        Resume::new(FileStore::new(&dir).unwrap()).flush_interval(Duration::from_secs(3600));
+    resume.process(&sale(1, 0)).unwrap();
+    resume.process(&sale(2, 0)).unwrap();
+    assert!(FileStore::new(&dir)
+        .unwrap()
+        .load("wandernauts")
+        .unwrap()
+        .is_none());
+
+    let mut store = resume.into_inner().unwrap();
+    let cursor = store.load("wandernauts").unwrap().unwrap();
+    assert_eq!(cursor.keys.len(), 2);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "sled")]
+#[test]
+fn sled_store_keeps_cursors() {
+    use opensea_stream::cursor::SledStore;
+
+    let db = sled::Config::new().temporary(true).open().unwrap();
+    let mut resume = Resume::new(SledStore::new(db.open_tree("cursors").unwrap()));
+    resume.process(&sale(1, 0)).unwrap();
+    resume.flush().unwrap();
+
+    let mut resume = Resume::new(SledStore::new(db.open_tree("cursors").unwrap()));
+    assert_eq!(resume.process(&sale(1, 0)).unwrap(), Status::Duplicate);
+}
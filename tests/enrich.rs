@@ -0,0 +1,260 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use common::{events, fixture, fixture_in, mock_http, MockHttp};
+use opensea_stream::{
+    enrich::{ContractAddress, EnrichError, Enricher, EnricherBuilder, SubscribeContractError},
+    schema::Chain,
+    ClientBuilder, ClientError, Collection, Network,
+};
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+use tokio::time::Instant;
+
+/// Starts a REST API that serves the stats of every collection and the metadata of every token.
+async fn api() -> MockHttp {
+    mock_http(|_, head| {
+        let body = if head.contains("/stats ") {
+            json!({ "total": { "volume": 12.5, "floor_price": 0.05, "floor_price_symbol": "ETH" } })
+        } else {
+            json!({
+                "nft": {
+                    "identifier": "1",
+                    "collection": "wandernauts",
+                    "token_standard": "erc721",
+                    "name": "Wandernaut #1",
+                    "traits": [{ "trait_type": "Background", "value": "Blue" }]
+                }
+            })
+        };
+        ("200 OK", body.to_string())
+    })
+    .await
+}
+
+fn enricher(api: &MockHttp) -> EnricherBuilder {
+    Enricher::builder(Network::Mainnet, "key")
+        .endpoint(api.url.clone())
+        .min_interval(Duration::ZERO)
+}
+
+/// Returns the request lines of the requests received so far.
+fn requests(api: &mut MockHttp) -> Vec<String> {
+    let mut requests = Vec::new();
+    while let Ok((head, _)) = api.requests.try_recv() {
+        assert!(head.contains("x-api-key: key\r\n"), "{}", head);
+        requests.push(head.lines().next().unwrap().to_owned());
+    }
+    requests
+}
+
+#[tokio::test]
+async fn responses_are_cached() {
+    let mut api = api().await;
+    let enricher = enricher(&api).build();
+
+    for _ in 0..2 {
+        let enriched = enricher.enrich(fixture("item_sold.json")).await;
+        let stats = enriched.collection_stats.unwrap();
+        assert_eq!(stats.volume, 12.5);
+        assert_eq!(stats.floor_price_symbol, "ETH");
+        let nft = enriched.nft.unwrap();
+        assert_eq!(nft.name.as_deref(), Some("Wandernaut #1"));
+        assert_eq!(nft.traits[0].trait_type, "Background");
+    }
+
+    assert_eq!(
+        requests(&mut api),
+        [
+            "GET /collections/wandernauts/stats HTTP/1.1",
+            "GET /chain/ethereum/contract/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4/nfts/1 HTTP/1.1"
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn responses_expire_after_the_ttl() {
+    let mut api = api().await;
+    let enricher = enricher(&api).cache_ttl(Duration::from_secs(10)).build();
+
+    enricher.collection_stats("wandernauts").await.unwrap();
+    tokio::time::advance(Duration::from_secs(9)).await;
+    enricher.collection_stats("wandernauts").await.unwrap();
+    assert_eq!(requests(&mut api).len(), 1);
+
+    tokio::time::advance(Duration::from_secs(1)).await;
+    enricher.collection_stats("wandernauts").await.unwrap();
+    assert_eq!(requests(&mut api).len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn requests_are_paced() {
+    let mut api = api().await;
+    let enricher = enricher(&api).min_interval(Duration::from_secs(5)).build();
+
+    let start = Instant::now();
+    for slug in ["wandernauts", "boredapeyachtclub", "azuki"] {
+        enricher.collection_stats(slug).await.unwrap();
+    }
+    // The first request is sent immediately.
+    assert_eq!(start.elapsed(), Duration::from_secs(10));
+    assert_eq!(requests(&mut api).len(), 3);
+
+    // Cached responses do not wait for a slot.
+    let start = Instant::now();
+    enricher.collection_stats("wandernauts").await.unwrap();
+    assert_eq!(start.elapsed(), Duration::ZERO);
+}
+
+#[tokio::test]
+async fn spawned_enrichments_are_delivered_in_order() {
+    let api = api().await;
+    let enricher = Arc::new(enricher(&api).concurrency(2).build());
+
+    let slugs = ["wandernauts", "azuki", "wandernauts", "boredapeyachtclub"];
+    let mut enriched = enricher.spawn(
+        events(slugs.map(|slug| fixture_in("item_sold.json", slug))),
+        1,
+    );
+    for slug in slugs {
+        let event = enriched.recv().await.unwrap();
+        assert_eq!(event.event.payload.collection().0, slug);
+        assert!(event.collection_stats.is_some());
+    }
+    assert!(enriched.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn failed_fetches_leave_fields_empty() {
+    let api = mock_http(|_, _| ("404 Not Found", String::new())).await;
+    let enricher = enricher(&api).nft(false).build();
+
+    let enriched = enricher.enrich(fixture("item_sold.json")).await;
+    assert!(enriched.collection_stats.is_none());
+    assert!(enriched.nft.is_none());
+}
+
+/// Starts a REST API that knows the contract of the `wandernauts` collection only.
+async fn contracts_api() -> MockHttp {
+    mock_http(|_, head| {
+        let collection = head
+            .contains("/contract/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4 ")
+            .then_some("wandernauts");
+        ("200 OK", json!({ "collection": collection }).to_string())
+    })
+    .await
+}
+
+fn contract(address: &str) -> ContractAddress {
+    ContractAddress::new(Chain::Ethereum, address.parse().unwrap())
+}
+
+#[tokio::test]
+async fn collection_of_contract_is_resolved_once() {
+    let mut api = contracts_api().await;
+    let enricher = enricher(&api).build();
+
+    let wandernauts = contract("0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4");
+    for _ in 0..2 {
+        assert_eq!(
+            enricher.collection_of(wandernauts).await.unwrap(),
+            Collection::Collection("wandernauts".to_owned())
+        );
+    }
+    assert_eq!(
+        requests(&mut api),
+        ["GET /chain/ethereum/contract/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4 HTTP/1.1"]
+    );
+
+    let unknown = contract("0x0000000000000000000000000000000000000001");
+    assert!(matches!(
+        enricher.collection_of(unknown).await,
+        Err(EnrichError::NoCollection(c)) if c == unknown
+    ));
+}
+
+#[tokio::test]
+async fn subscribe_to_contract_joins_its_collection() {
+    let api = contracts_api().await;
+    let enricher = enricher(&api).build();
+    let mut server = common::mock_server().await;
+    let mut client = ClientBuilder::new(Network::Mainnet, "key")
+        .endpoint(server.url.clone())
+        .connect()
+        .await
+        .unwrap();
+
+    enricher
+        .subscribe_to_contract(
+            &mut client,
+            contract("0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4"),
+        )
+        .await
+        .unwrap();
+    let join = tokio::time::timeout(Duration::from_secs(10), server.joins.recv())
+        .await
+        .unwrap();
+    assert_eq!(join.as_deref(), Some("collection:wandernauts"));
+
+    // Nothing is joined for a contract without a collection.
+    let result = enricher
+        .subscribe_to_contract(
+            &mut client,
+            contract("0x0000000000000000000000000000000000000001"),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(SubscribeContractError::Resolve(EnrichError::NoCollection(
+            _
+        )))
+    ));
+    assert!(server.joins.try_recv().is_err());
+}
+
+#[test]
+fn contract_collections_roundtrip_as_topics() {
+    let collection =
+        Collection::ContractAddress(contract("0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4"));
+    let topic = "contract:ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4";
+    assert_eq!(collection.to_string(), topic);
+    assert_eq!(serde_json::to_value(&collection).unwrap(), json!(topic));
+    assert_eq!(
+        serde_json::from_value::<Collection>(json!(topic)).unwrap(),
+        collection
+    );
+    assert!("ethereum".parse::<ContractAddress>().is_err());
+}
+
+#[tokio::test]
+async fn clients_resolve_contract_collections_when_subscribing() {
+    let api = contracts_api().await;
+    let mut server = common::mock_server().await;
+    let mut client = ClientBuilder::new(Network::Mainnet, "key")
+        .endpoint(server.url.clone())
+        .enricher(Arc::new(enricher(&api).build()))
+        .connect()
+        .await
+        .unwrap();
+
+    let wandernauts = contract("0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4");
+    let subscription = client
+        .subscribe(Collection::ContractAddress(wandernauts))
+        .await
+        .unwrap();
+    assert_eq!(
+        subscription.collection(),
+        &Collection::Collection("wandernauts".to_owned())
+    );
+    let join = tokio::time::timeout(Duration::from_secs(10), server.joins.recv())
+        .await
+        .unwrap();
+    assert_eq!(join.as_deref(), Some("collection:wandernauts"));
+
+    // Nothing is joined for a contract without a collection.
+    let unknown = contract("0x0000000000000000000000000000000000000001");
+    let result = client.subscribe(Collection::ContractAddress(unknown)).await;
+    assert!(matches!(result, Err(ClientError::Unresolved(c, _)) if c == unknown));
+    assert!(server.joins.try_recv().is_err());
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use common::{events, fixture};
+use opensea_stream::{
+    schema::Payload,
+    sinks::file::{FileSinkBuilder, Format},
+};
+use std::{fs, path::PathBuf, time::Duration};
+
+/// Returns an empty directory for the files of a test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "opensea-stream-file-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Returns the contents of the files in `dir`, sorted by name.
+fn files(dir: &PathBuf) -> Vec<(String, String)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            (
+                path.file_name().unwrap().to_string_lossy().into_owned(),
+                fs::read_to_string(&path).unwrap(),
+            )
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn files_are_rotated_by_size_without_overwriting() {
+    let dir = temp_dir("size");
+    let mut sink = FileSinkBuilder::new(dir.join("events.ndjson"))
+        .fields(["event_type"])
+        .max_bytes(1)
+        .build()
+        .unwrap();
+    for name in ["item_listed.json", "item_sold.json", "item_cancelled.json"] {
+        sink.write(&fixture(name)).unwrap();
+    }
+    sink.flush().unwrap();
+
+    // Rotations within the same millisecond get distinct names.
+    let files = files(&dir);
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[2].0, "events.ndjson");
+    assert_eq!(files[2].1, "{\"event_type\":\"item_cancelled\"}\n");
+    let mut rotated: Vec<_> = files[..2].iter().map(|(_, c)| c.as_str()).collect();
+    rotated.sort();
+    assert_eq!(
+        rotated,
+        [
+            "{\"event_type\":\"item_listed\"}\n",
+            "{\"event_type\":\"item_sold\"}\n"
+        ]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn files_are_rotated_by_age_once_written_to() {
+    let dir = temp_dir("age");
+    let mut sink = FileSinkBuilder::new(dir.join("events.csv"))
+        .format(Format::Csv)
+        .fields(["event_type"])
+        .max_age(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(30));
+    sink.write(&fixture("item_listed.json")).unwrap();
+    assert_eq!(files(&dir).len(), 1);
+
+    std::thread::sleep(Duration::from_millis(30));
+    sink.write(&fixture("item_sold.json")).unwrap();
+    sink.flush().unwrap();
+
+    // Every file starts with a header.
+    let files = files(&dir);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].1, "event_type\nitem_listed\n");
+    assert_eq!(
+        files[1],
+        (
+            "events.csv".to_owned(),
+            "event_type\nitem_sold\n".to_owned()
+        )
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn selected_fields_are_written_and_quoted() {
+    let dir = temp_dir("fields");
+    let mut event = fixture("item_sold.json");
+    if let Payload::ItemSold(sale) = &mut event.payload {
+        sale.context.item.metadata.name = Some("Wandernaut, \"the first\"".to_owned());
+    }
+    let fields = [
+        "payload.collection.slug",
+        "payload.item.metadata.name",
+        "payload.missing",
+    ];
+
+    let mut csv = FileSinkBuilder::new(dir.join("events.csv"))
+        .format(Format::Csv)
+        .fields(fields)
+        .build()
+        .unwrap();
+    csv.write(&event).unwrap();
+    csv.flush().unwrap();
+    let mut ndjson = FileSinkBuilder::new(dir.join("events.ndjson"))
+        .fields(fields)
+        .build()
+        .unwrap();
+    ndjson.write(&event).unwrap();
+    ndjson.flush().unwrap();
+
+    let files = files(&dir);
+    assert_eq!(
+        files[0].1,
+        "payload.collection.slug,payload.item.metadata.name,payload.missing\n\
+         wandernauts,\"Wandernaut, \"\"the first\"\"\",\n"
+    );
+    let record: serde_json::Value = serde_json::from_str(&files[1].1).unwrap();
+    assert_eq!(
+        record,
+        serde_json::json!({
+            "payload.collection.slug": "wandernauts",
+            "payload.item.metadata.name": "Wandernaut, \"the first\"",
+            "payload.missing": null
+        })
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn run_writes_every_event_of_the_subscription() {
+    let dir = temp_dir("run");
+    let sink = FileSinkBuilder::new(dir.join("events.ndjson"))
+        .fields(["event_type"])
+        .build()
+        .unwrap();
+
+    let events = events(["item_listed.json", "item_sold.json"].map(fixture));
+    sink.run(events).await.unwrap();
+
+    assert_eq!(
+        files(&dir)[0].1,
+        "{\"event_type\":\"item_listed\"}\n{\"event_type\":\"item_sold\"}\n"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,122 @@
+//! Deserializes every payload in `tests/fixtures` (see `tests/fixtures/README.md`).
+
+use opensea_stream::{schema::StreamEvent, Event};
+use serde_json::Value;
+use std::{collections::HashSet, fs, path::PathBuf, str::FromStr};
+
+/// Setting this environment variable also fails on fields that the schema does not know about.
+const STRICT: &str = "OPENSEA_STREAM_STRICT_FIXTURES";
+
+fn fixtures() -> Vec<(String, Value)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut fixtures: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let value = serde_json::from_slice(&fs::read(&path).unwrap())
+                .unwrap_or_else(|e| panic!("{}: invalid json: {}", name, e));
+            (name, value)
+        })
+        .collect();
+    fixtures.sort_by(|a, b| a.0.cmp(&b.0));
+    fixtures
+}
+
+/// Lists the fields of `original` that are missing from `roundtrip`, i.e. fields that were dropped by deserializing.
+fn unknown_fields(original: &Value, roundtrip: &Value, path: &str, unknown: &mut Vec<String>) {
+    match (original, roundtrip) {
+        (Value::Object(original), Value::Object(roundtrip)) => {
+            for (key, value) in original {
+                let path = format!("{}.{}", path, key);
+                match roundtrip.get(key) {
+                    Some(other) => unknown_fields(value, other, &path, unknown),
+                    None => unknown.push(path),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(roundtrip)) => {
+            for (i, (value, other)) in original.iter().zip(roundtrip).enumerate() {
+                unknown_fields(value, other, &format!("{}[{}]", path, i), unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn fixtures_deserialize() {
+    let strict = std::env::var_os(STRICT).is_some();
+    let mut failures = Vec::new();
+
+    for (name, value) in fixtures() {
+        let event: StreamEvent = match serde_json::from_value(value.clone()) {
+            Ok(event) => event,
+            Err(e) => {
+                failures.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+
+        let expected = name.split('.').next().unwrap();
+        if event.payload.event().to_string() != expected {
+            failures.push(format!(
+                "{}: deserialized as {}",
+                name,
+                event.payload.event()
+            ));
+        }
+
+        if strict {
+            let mut unknown = Vec::new();
+            unknown_fields(
+                &value,
+                &serde_json::to_value(&event).unwrap(),
+                "",
+                &mut unknown,
+            );
+            if !unknown.is_empty() {
+                failures.push(format!("{}: unknown fields {}", name, unknown.join(", ")));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn fixtures_cover_every_event() {
+    let covered: HashSet<_> = fixtures()
+        .iter()
+        .map(|(name, _)| name.split('.').next().unwrap().to_owned())
+        .collect();
+
+    for event in Event::ALL {
+        let name = event.to_string();
+        assert_eq!(Event::from_str(&name), Ok(*event));
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            Value::String(name.clone())
+        );
+        assert!(covered.contains(&name), "no fixture for {}", name);
+    }
+}
+
+#[test]
+fn fixtures_roundtrip() {
+    for (name, value) in fixtures() {
+        let event: StreamEvent = serde_json::from_value(value)
+            .unwrap_or_else(|e| panic!("{}: fixture does not deserialize: {}", name, e));
+        let serialized = serde_json::to_value(&event).unwrap();
+        let roundtrip: StreamEvent = serde_json::from_value(serialized.clone())
+            .unwrap_or_else(|e| panic!("{}: serialized event does not deserialize: {}", name, e));
+        assert_eq!(roundtrip.payload.event(), event.payload.event(), "{}", name);
+        assert_eq!(
+            serde_json::to_value(&roundtrip).unwrap(),
+            serialized,
+            "{}: serializing is not stable",
+            name
+        );
+    }
+}
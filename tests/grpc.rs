@@ -0,0 +1,136 @@
+#![cfg(feature = "grpc")]
+
+mod common;
+
+use common::{events, fixture, fixture_in};
+use futures_util::StreamExt;
+use opensea_stream::{
+    grpc::{
+        proto::{open_sea_stream_server::OpenSeaStream, Event, SubscribeRequest},
+        StreamService,
+    },
+    schema::StreamEvent,
+    Error,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+use tonic::{Code, Request, Status};
+
+type Events = <StreamService as OpenSeaStream>::SubscribeStream;
+
+async fn subscribe(
+    service: &StreamService,
+    collections: &[&str],
+    event_types: &[&str],
+) -> Result<Events, Status> {
+    let request = SubscribeRequest {
+        collections: collections.iter().map(|c| c.to_string()).collect(),
+        event_types: event_types.iter().map(|e| e.to_string()).collect(),
+    };
+    Ok(service.subscribe(Request::new(request)).await?.into_inner())
+}
+
+/// Forwards `events` into `service`, waiting until they have been fed into it.
+async fn forward(service: &StreamService, events: Vec<StreamEvent>) {
+    service.forward(common::events(events)).await.unwrap();
+}
+
+async fn next(events: &mut Events) -> Option<Result<Event, Status>> {
+    timeout(Duration::from_secs(10), events.next())
+        .await
+        .expect("no event received")
+}
+
+#[tokio::test]
+async fn events_are_filtered_by_collection_and_event_type() {
+    let service = StreamService::new(16);
+    let all = subscribe(&service, &[], &[]).await.unwrap();
+    let azuki = subscribe(&service, &["azuki"], &[]).await.unwrap();
+    let sales = subscribe(&service, &[], &["item_sold"]).await.unwrap();
+
+    forward(
+        &service,
+        vec![
+            fixture("item_listed.json"),
+            fixture_in("item_sold.json", "azuki"),
+            fixture("item_sold.json"),
+        ],
+    )
+    .await;
+    // The streams end with the service.
+    drop(service);
+
+    let received = |events: Vec<Result<Event, Status>>| {
+        events
+            .into_iter()
+            .map(|event| {
+                let event = event.unwrap();
+                format!("{} {}", event.collection, event.event_type)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        received(all.collect().await),
+        [
+            "wandernauts item_listed",
+            "azuki item_sold",
+            "wandernauts item_sold"
+        ]
+    );
+    assert_eq!(received(azuki.collect().await), ["azuki item_sold"]);
+    assert_eq!(
+        received(sales.collect().await),
+        ["azuki item_sold", "wandernauts item_sold"]
+    );
+}
+
+#[tokio::test]
+async fn unknown_event_types_are_invalid_arguments() {
+    let service = StreamService::new(16);
+    let status = match subscribe(&service, &[], &["item_sold", "item_burned"]).await {
+        Ok(_) => panic!("subscribed with an unknown event type"),
+        Err(status) => status,
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "unknown event type `item_burned`");
+}
+
+#[tokio::test]
+async fn lagging_callers_are_sent_data_loss() {
+    let service = StreamService::new(1);
+    let mut events = subscribe(&service, &[], &[]).await.unwrap();
+    forward(
+        &service,
+        vec![
+            fixture("item_listed.json"),
+            fixture("item_sold.json"),
+            fixture("item_cancelled.json"),
+        ],
+    )
+    .await;
+
+    let status = next(&mut events).await.unwrap().unwrap_err();
+    assert_eq!(status.code(), Code::DataLoss);
+    assert_eq!(status.message(), "missed 2 events");
+}
+
+#[tokio::test]
+async fn missed_events_of_the_source_are_sent_as_data_loss() {
+    let service = StreamService::new(16);
+    let mut all = subscribe(&service, &[], &[]).await.unwrap();
+    let mut azuki = subscribe(&service, &["azuki"], &[]).await.unwrap();
+
+    let mut source = events([fixture("item_listed.json")]);
+    source.0.push_back(Err(Error::MissedEvents(3)));
+    service.forward(source).await.unwrap();
+
+    assert_eq!(
+        next(&mut all).await.unwrap().unwrap().event_type,
+        "item_listed"
+    );
+    for events in [&mut all, &mut azuki] {
+        let status = next(events).await.unwrap().unwrap_err();
+        assert_eq!(status.code(), Code::DataLoss);
+        assert_eq!(status.message(), "missed 3 events");
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "unstable-phyllo")]
+
+mod common;
+
+use common::fixture_value;
+use opensea_stream::{
+    guardrails::{DeadLetterReason, GuardrailMetrics, Guardrails, RawMessage},
+    phyllo::message::{Event as MessageEvent, Message, Payload},
+    Collection, Event,
+};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+
+fn raw(payload: Value) -> RawMessage {
+    Message::new(
+        0,
+        0,
+        Collection::All,
+        MessageEvent::Event(Event::ItemListed),
+        Some(Payload::Custom(payload)),
+    )
+}
+
+#[tokio::test]
+async fn oversized_and_invalid_payloads_are_diverted() {
+    let listed = fixture_value("item_listed.json");
+    let size = serde_json::to_vec(&listed).unwrap().len();
+    let mut huge = listed.clone();
+    huge["payload"]["item"]["metadata"]["traits"] = json!(vec!["trait"; size]);
+
+    let (tx, rx) = broadcast::channel(16);
+    tx.send(raw(huge)).unwrap();
+    tx.send(raw(json!({ "event_type": "item_listed" })))
+        .unwrap();
+    tx.send(raw(listed)).unwrap();
+    drop(tx);
+
+    let (dead_tx, mut dead_letters) = mpsc::unbounded_channel();
+    let guardrails = Guardrails::new()
+        .max_payload_bytes(size)
+        .dead_letters(dead_tx);
+    let mut events = guardrails.stream(rx);
+
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemListed);
+    assert!(events.recv().await.is_none());
+
+    assert_eq!(
+        dead_letters.recv().await.unwrap().reason,
+        DeadLetterReason::TooLarge { max: size }
+    );
+    assert!(matches!(
+        dead_letters.recv().await.unwrap().reason,
+        DeadLetterReason::Invalid(_)
+    ));
+    assert_eq!(
+        guardrails.metrics(),
+        GuardrailMetrics {
+            delivered: 1,
+            too_large: 1,
+            too_slow: 0,
+            invalid: 1,
+        }
+    );
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::fixture;
+use opensea_stream::{
+    middleware::{self, EventMiddleware, Next, Pipeline},
+    schema::StreamEvent,
+    Event,
+};
+use std::sync::{Arc, Mutex};
+
+/// Records the name of the stage and the event type of every event it sees.
+struct Trace(&'static str, Arc<Mutex<Vec<String>>>);
+
+impl EventMiddleware for Trace {
+    async fn handle(&self, event: StreamEvent, next: Next<'_>) -> Option<StreamEvent> {
+        self.1
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", self.0, event.payload.event()));
+        next.run(event).await
+    }
+}
+
+#[tokio::test]
+async fn stages_run_in_order_until_an_event_is_dropped() {
+    let trace = Arc::new(Mutex::new(Vec::new()));
+    let pipeline = Pipeline::new()
+        .layer(Trace("first", trace.clone()))
+        .layer(middleware::filter(|event| {
+            event.payload.event() == Event::ItemListed
+        }))
+        .layer(Trace("last", trace.clone()));
+
+    let listed = pipeline.handle(fixture("item_listed.json")).await;
+    assert_eq!(listed.unwrap().payload.event(), Event::ItemListed);
+    assert!(pipeline.handle(fixture("item_sold.json")).await.is_none());
+
+    assert_eq!(
+        *trace.lock().unwrap(),
+        ["first item_listed", "last item_listed", "first item_sold"]
+    );
+}
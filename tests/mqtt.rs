@@ -0,0 +1,28 @@
+#![cfg(feature = "mqtt")]
+
+mod common;
+
+use common::fixture;
+use opensea_stream::{
+    rumqttc::MqttOptions,
+    sinks::mqtt::{MqttSinkBuilder, DEFAULT_TOPIC},
+};
+
+#[tokio::test]
+async fn topics_are_rendered_from_the_template() {
+    let options = MqttOptions::new("test", "localhost", 1883);
+    let sink = MqttSinkBuilder::new(options.clone()).build();
+    assert_eq!(
+        sink.topic(&fixture("item_sold.json")),
+        "opensea/wandernauts/item_sold"
+    );
+    assert_eq!(DEFAULT_TOPIC, "opensea/{collection}/{event_type}");
+
+    let sink = MqttSinkBuilder::new(options)
+        .topic("nft/{payload.item.chain.name}/{payload.item.metadata.name}")
+        .build();
+    assert_eq!(
+        sink.topic(&fixture("item_sold.json")),
+        "nft/ethereum/Wandernaut _1"
+    );
+}
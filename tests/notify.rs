@@ -0,0 +1,111 @@
+#![cfg(feature = "notify")]
+
+mod common;
+
+use common::{events, fixture, mock_http};
+use opensea_stream::sinks::notify::{sales_above, sales_above_in, NotifySinkBuilder, Service};
+
+#[test]
+fn template_renders_fields_and_units() {
+    let sink = NotifySinkBuilder::new(Service::Slack, "https://hooks.slack.com/x".parse().unwrap())
+        .template("{{sale}} {payload.item.metadata.name} for {payload.sale_price|units:18} {payload.payment_token.symbol}{payload.missing}")
+        .build();
+
+    assert_eq!(
+        sink.render(&fixture("item_sold.json")),
+        "{sale} Wandernaut #1 for 0.05 ETH"
+    );
+}
+
+#[test]
+fn sales_above_threshold() {
+    let filter = sales_above(10_000_000_000_000_000u64.into());
+    assert!(filter(&fixture("item_sold.json")));
+    assert!(!filter(&fixture("item_listed.json")));
+
+    let filter = sales_above(100_000_000_000_000_000u64.into());
+    assert!(!filter(&fixture("item_sold.json")));
+}
+
+#[test]
+fn sales_above_in_only_passes_the_token() {
+    let eth = "0x0000000000000000000000000000000000000000"
+        .parse()
+        .unwrap();
+    let weth = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+        .parse()
+        .unwrap();
+    assert!(sales_above_in(eth, 10_000_000_000_000_000u64.into())(
+        &fixture("item_sold.json")
+    ));
+    assert!(!sales_above_in(weth, 10_000_000_000_000_000u64.into())(
+        &fixture("item_sold.json")
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn posts_are_retried_after_rate_limits_and_server_errors() {
+    let mut server = mock_http(|n, _| match n {
+        0 => ("429 Too Many Requests\r\nRetry-After: 30", String::new()),
+        1 => ("503 Service Unavailable", String::new()),
+        _ => ("204 No Content", String::new()),
+    })
+    .await;
+    let sink = NotifySinkBuilder::new(Service::Discord, server.url.clone())
+        .template("{event_type}")
+        .build();
+
+    let start = tokio::time::Instant::now();
+    assert!(sink.notify(&fixture("item_sold.json")).await.unwrap());
+    // 30 seconds requested by the webhook, then the backoff of the second retry.
+    assert_eq!(start.elapsed().as_secs(), 32);
+    for _ in 0..3 {
+        let (head, body) = server.requests.recv().await.unwrap();
+        assert!(head.starts_with("POST / HTTP/1.1"));
+        assert_eq!(body, r#"{"content":"item_sold"}"#);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_are_limited() {
+    let mut server = mock_http(|_, _| ("500 Internal Server Error", String::new())).await;
+    let sink = NotifySinkBuilder::new(Service::Slack, server.url.clone())
+        .retries(2)
+        .build();
+
+    let error = sink.notify(&fixture("item_sold.json")).await.unwrap_err();
+    assert_eq!(
+        error.status(),
+        Some(reqwest::StatusCode::INTERNAL_SERVER_ERROR)
+    );
+    for _ in 0..3 {
+        server.requests.recv().await.unwrap();
+    }
+    assert!(server.requests.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn run_continues_after_failed_posts() {
+    let mut server = mock_http(|n, _| match n {
+        0 => ("400 Bad Request", String::new()),
+        _ => ("204 No Content", String::new()),
+    })
+    .await;
+    let sink = NotifySinkBuilder::new(Service::Slack, server.url.clone())
+        .template("{event_type}")
+        .build();
+
+    sink.run(events(["item_listed.json", "item_sold.json"].map(fixture)))
+        .await;
+
+    // Client errors are not retried.
+    assert_eq!(
+        server.requests.recv().await.unwrap().1,
+        r#"{"text":"item_listed"}"#
+    );
+    assert_eq!(
+        server.requests.recv().await.unwrap().1,
+        r#"{"text":"item_sold"}"#
+    );
+    assert!(server.requests.try_recv().is_err());
+}
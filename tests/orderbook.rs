@@ -0,0 +1,38 @@
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::fixture;
+use opensea_stream::orderbook::{Change, OrderBook, Removal};
+
+fn at(timestamp: &str) -> DateTime<Utc> {
+    timestamp.parse().unwrap()
+}
+
+#[test]
+fn orders_are_removed_on_sale_and_expiry() {
+    let mut book = OrderBook::new();
+    let now = at("2022-07-19T19:30:00Z");
+    book.apply_at(&fixture("item_listed.json"), now);
+    book.apply_at(&fixture("item_received_offer.json"), now);
+
+    let before = book.snapshot("wandernauts");
+    assert_eq!(before.listings.len(), 1);
+    assert_eq!(before.offers.len(), 1);
+
+    // The listed item is sold by the maker of the listing.
+    let changes = book.apply_at(&fixture("item_sold.json"), now);
+    assert!(matches!(changes[..], [Change::Removed(_, Removal::Sold)]));
+
+    let after = book.snapshot("wandernauts");
+    let diff = after.diff(&before);
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed[0].order_hash, before.listings[0].order_hash);
+
+    // The offer expires on 2022-07-22.
+    let changes = book.expire(at("2022-07-23T00:00:00Z"));
+    assert!(matches!(
+        changes[..],
+        [Change::Removed(_, Removal::Expired)]
+    ));
+    assert!(book.is_empty());
+}
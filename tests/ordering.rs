@@ -0,0 +1,42 @@
+#![cfg(feature = "unstable-phyllo")]
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{fixture, message, StreamMessage};
+use opensea_stream::{ordering::Reorder, Error, EventStream};
+use std::time::Duration;
+use tokio::{sync::broadcast, time::Instant};
+
+fn listed_at(secs: i64) -> StreamMessage {
+    let mut event = fixture("item_listed.json");
+    event.sent_at = Utc.timestamp_opt(secs, 0).unwrap();
+    message(event)
+}
+
+#[tokio::test(start_paused = true)]
+async fn events_are_reordered_within_the_window() {
+    let (tx, rx) = broadcast::channel(16);
+    tx.send(listed_at(10)).unwrap();
+    tx.send(listed_at(5)).unwrap();
+
+    let mut events = EventStream::new(rx).reorder(Reorder::new(Duration::from_secs(2)));
+    let start = Instant::now();
+
+    let event = events.recv_sequenced().await.unwrap().unwrap();
+    assert_eq!((event.sequence, event.event.sent_at.timestamp()), (1, 5));
+    assert_eq!(start.elapsed(), Duration::from_secs(2));
+    let event = events.recv_sequenced().await.unwrap().unwrap();
+    assert_eq!((event.sequence, event.event.sent_at.timestamp()), (0, 10));
+
+    tx.send(listed_at(3)).unwrap();
+    match events.recv().await {
+        Some(Err(Error::OutOfOrder {
+            sequence, latest, ..
+        })) => assert_eq!((sequence, latest.timestamp()), (2, 10)),
+        other => panic!("expected an out of order event, got {:?}", other),
+    }
+
+    drop(tx);
+    assert!(events.recv().await.is_none());
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "proxy")]
+
+use opensea_stream::{ClientBuilder, Network};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+
+/// Binds a mock proxy, returning it with its address.
+async fn mock_proxy() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    (listener, addr)
+}
+
+/// Accepts the first connection that the client makes to the mock proxy.
+async fn accept(listener: &TcpListener) -> TcpStream {
+    timeout(Duration::from_secs(10), listener.accept())
+        .await
+        .expect("client did not connect to the proxy")
+        .unwrap()
+        .0
+}
+
+async fn read_exact<const N: usize>(stream: &mut TcpStream) -> [u8; N] {
+    let mut buf = [0; N];
+    stream.read_exact(&mut buf).await.unwrap();
+    buf
+}
+
+#[tokio::test]
+async fn http_proxy_is_sent_connect_with_credentials() {
+    let (listener, addr) = mock_proxy().await;
+    let _socket = ClientBuilder::new(Network::Mainnet, "key")
+        .proxy(format!("http://user:pass@{}", addr).parse().unwrap())
+        .build()
+        .await
+        .unwrap();
+
+    let mut stream = accept(&listener).await;
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        head.push(stream.read_u8().await.unwrap());
+    }
+    let head = String::from_utf8(head).unwrap();
+    assert!(
+        head.starts_with("CONNECT stream.openseabeta.com:443 HTTP/1.1\r\n"),
+        "{}",
+        head
+    );
+    assert!(head.contains("Host: stream.openseabeta.com:443\r\n"));
+    // base64 of `user:pass`.
+    assert!(head.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+}
+
+#[tokio::test]
+async fn socks5_proxy_is_sent_credentials_and_domain() {
+    let (listener, addr) = mock_proxy().await;
+    let _socket = ClientBuilder::new(Network::Mainnet, "key")
+        .proxy(format!("socks5://user:pass@{}", addr).parse().unwrap())
+        .build()
+        .await
+        .unwrap();
+
+    let mut stream = accept(&listener).await;
+    assert_eq!(read_exact::<4>(&mut stream).await, [5, 2, 0, 2]);
+    stream.write_all(&[5, 2]).await.unwrap();
+
+    assert_eq!(
+        &read_exact::<11>(&mut stream).await,
+        b"\x01\x04user\x04pass"
+    );
+    stream.write_all(&[1, 0]).await.unwrap();
+
+    let host = b"stream.openseabeta.com";
+    assert_eq!(
+        read_exact::<5>(&mut stream).await,
+        [5, 1, 0, 3, host.len() as u8]
+    );
+    assert_eq!(&read_exact::<22>(&mut stream).await, host);
+    assert_eq!(read_exact::<2>(&mut stream).await, 443u16.to_be_bytes());
+}
+
+#[tokio::test]
+async fn socks5_rejects_long_credentials() {
+    let (listener, addr) = mock_proxy().await;
+    let _socket = ClientBuilder::new(Network::Mainnet, "key")
+        .proxy(
+            format!("socks5://{}:pass@{}", "a".repeat(256), addr)
+                .parse()
+                .unwrap(),
+        )
+        .build()
+        .await
+        .unwrap();
+
+    // The bridge gives up before sending anything, rather than sending a truncated length.
+    let mut stream = accept(&listener).await;
+    let mut received = Vec::new();
+    stream.read_to_end(&mut received).await.unwrap();
+    assert!(received.is_empty());
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "unstable-phyllo")]
+
+mod common;
+
+use common::{fixture, message};
+use opensea_stream::{
+    ratelimit::{Overflow, Quota, RateLimit},
+    Event, EventStream,
+};
+use tokio::sync::broadcast;
+
+fn stream(limit: RateLimit) -> EventStream {
+    let (tx, rx) = broadcast::channel(16);
+    tx.send(message(fixture("item_listed.json"))).unwrap();
+    for _ in 0..3 {
+        tx.send(message(fixture("item_metadata_updated.json")))
+            .unwrap();
+    }
+    tx.send(message(fixture("item_sold.json"))).unwrap();
+    EventStream::new(rx).rate_limit(limit)
+}
+
+async fn events(mut stream: EventStream) -> Vec<Event> {
+    let mut events = Vec::new();
+    while let Some(event) = stream.recv().await {
+        events.push(event.unwrap().payload.event());
+    }
+    events
+}
+
+#[tokio::test(start_paused = true)]
+async fn overflow_policies() {
+    let limit = RateLimit::new()
+        .global(Quota::per_minute(1))
+        .overflow(Overflow::Coalesce(16));
+    assert_eq!(
+        events(stream(limit.clone())).await,
+        [
+            Event::ItemListed,
+            Event::ItemMetadataUpdated,
+            Event::ItemSold
+        ]
+    );
+    assert_eq!(limit.dropped(), 2);
+
+    let limit = RateLimit::new()
+        .global(Quota::per_minute(1).burst(2))
+        .overflow(Overflow::Drop);
+    assert_eq!(
+        events(stream(limit.clone())).await,
+        [Event::ItemListed, Event::ItemMetadataUpdated]
+    );
+    assert_eq!(limit.dropped(), 3);
+
+    let limit = RateLimit::new()
+        .global(Quota::per_minute(1))
+        .overflow(Overflow::Buffer(2));
+    assert_eq!(
+        events(stream(limit.clone())).await,
+        [
+            Event::ItemListed,
+            Event::ItemMetadataUpdated,
+            Event::ItemMetadataUpdated
+        ]
+    );
+    assert_eq!(limit.dropped(), 2);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{events, fixture_in};
+use opensea_stream::{
+    router::{Route, Router},
+    schema::StreamEvent,
+    Error, EventSource,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::timeout};
+
+/// Source that yields the same event forever.
+struct Endless(StreamEvent);
+
+impl EventSource for Endless {
+    async fn recv(&mut self) -> Option<Result<StreamEvent, Error>> {
+        Some(Ok(self.0.clone()))
+    }
+}
+
+fn slug(event: &StreamEvent) -> &str {
+    &event.payload.collection().0
+}
+
+#[tokio::test]
+async fn events_are_routed_by_slug() {
+    let (wandernauts_tx, mut wandernauts_rx) = mpsc::channel(4);
+    let (azuki_tx, mut azuki_rx) = mpsc::unbounded_channel();
+    let mut router = Router::new()
+        .route("wandernauts", wandernauts_tx)
+        .route("azuki", azuki_tx);
+
+    router
+        .dispatch(fixture_in("item_listed.json", "wandernauts"))
+        .await
+        .unwrap();
+    router
+        .dispatch(fixture_in("item_sold.json", "azuki"))
+        .await
+        .unwrap();
+
+    assert_eq!(slug(&wandernauts_rx.try_recv().unwrap()), "wandernauts");
+    assert_eq!(slug(&azuki_rx.try_recv().unwrap()), "azuki");
+    assert!(wandernauts_rx.try_recv().is_err());
+    assert!(azuki_rx.try_recv().is_err());
+
+    // Without a default route, events of other collections are returned.
+    let event = router
+        .dispatch(fixture_in("item_sold.json", "boredapeyachtclub"))
+        .await
+        .unwrap_err();
+    assert_eq!(slug(&event), "boredapeyachtclub");
+}
+
+#[tokio::test]
+async fn other_collections_take_the_default_route() {
+    let (tx, mut rx) = mpsc::channel(4);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let handler = {
+        let seen = seen.clone();
+        Route::handler(move |event| seen.lock().unwrap().push(slug(&event).to_owned()))
+    };
+    let router = Router::new()
+        .route("wandernauts", tx)
+        .default_route(handler);
+
+    let events = events(
+        ["azuki", "wandernauts", "boredapeyachtclub"]
+            .map(|slug| fixture_in("item_listed.json", slug)),
+    );
+    router.run(events).await;
+
+    assert_eq!(slug(&rx.try_recv().unwrap()), "wandernauts");
+    assert_eq!(*seen.lock().unwrap(), ["azuki", "boredapeyachtclub"]);
+}
+
+#[tokio::test]
+async fn closed_routes_are_removed() {
+    let (tx, rx) = mpsc::channel(4);
+    let (unbounded_tx, unbounded_rx) = mpsc::unbounded_channel();
+    let (default_tx, mut default_rx) = mpsc::unbounded_channel();
+    let mut router = Router::new()
+        .route("wandernauts", tx)
+        .route("azuki", unbounded_tx)
+        .default_route(default_tx);
+    drop(rx);
+    drop(unbounded_rx);
+
+    // Closed routes are removed, and their events take the default route.
+    for slug in ["wandernauts", "azuki", "wandernauts"] {
+        router
+            .dispatch(fixture_in("item_listed.json", slug))
+            .await
+            .unwrap();
+        assert_eq!(self::slug(&default_rx.try_recv().unwrap()), slug);
+    }
+
+    // Once the default route is closed, it is removed too.
+    drop(default_rx);
+    assert!(router
+        .dispatch(fixture_in("item_listed.json", "azuki"))
+        .await
+        .is_err());
+    let event = router
+        .dispatch(fixture_in("item_sold.json", "azuki"))
+        .await
+        .unwrap_err();
+    assert_eq!(slug(&event), "azuki");
+}
+
+#[tokio::test]
+async fn run_returns_once_every_route_is_closed() {
+    let (tx, rx) = mpsc::channel(4);
+    let (default_tx, default_rx) = mpsc::unbounded_channel();
+    let router = Router::new()
+        .route("wandernauts", tx)
+        .default_route(default_tx);
+    drop(rx);
+    drop(default_rx);
+
+    // The source never ends, so the router only returns because it has no routes left.
+    let events = Endless(fixture_in("item_listed.json", "wandernauts"));
+    timeout(Duration::from_secs(10), router.run(events))
+        .await
+        .unwrap();
+}
@@ -0,0 +1,229 @@
+mod common;
+
+use common::fixture;
+use opensea_stream::schema::{Payload, StreamEvent};
+use serde_json::{json, Value};
+
+fn item_listed(payload: Value) -> StreamEvent {
+    serde_json::from_value(json!({
+        "event_type": "item_listed",
+        "sent_at": "2022-07-19T18:42:03.235322+00:00",
+        "payload": payload,
+    }))
+    .unwrap()
+}
+
+fn item_listed_payload() -> Value {
+    json!({
+        "base_price": "50000000000000000",
+        "collection": { "slug": "wandernauts" },
+        "event_timestamp": "2022-07-19T18:42:02.268945+00:00",
+        "expiration_date": "2022-08-19T18:41:49.000000+00:00",
+        "item": {
+            "chain": { "name": "ethereum" },
+            "metadata": {
+                "animation_url": null,
+                "image_url": "https://lh3.googleusercontent.com/wandernaut",
+                "metadata_url": null,
+                "name": "Wandernaut #1",
+                "description": null,
+            },
+            "nft_id": "ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4/1",
+            "permalink": "https://opensea.io/assets/ethereum/0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4/1",
+        },
+        "listing_date": "2022-07-19T18:41:49.000000+00:00",
+        "listing_type": null,
+        "maker": { "address": "0x2f29f5d0d4388ee3e0b3d1b8b1e2db7bf1b2f0d2" },
+        "order_hash": "0x1a2b36a6d1ce3e34557d9b47f4c54b34efa4b6bd6da66a7f83a73a4d3f2a4c7e",
+        "payment_token": {
+            "address": "0x0000000000000000000000000000000000000000",
+            "decimals": 18,
+            "eth_price": "1.000000000000000",
+            "name": "Ether",
+            "symbol": "ETH",
+            "usd_price": "1530.000000000000000000",
+        },
+        "quantity": 1,
+    })
+}
+
+#[test]
+fn item_listed_without_private_or_taker() {
+    let event = item_listed(item_listed_payload());
+
+    let listing = match event.payload {
+        Payload::ItemListed(listing) => listing,
+        other => panic!("expected item listed, got {:?}", other),
+    };
+    assert!(!listing.is_private);
+    assert!(listing.taker.is_none());
+}
+
+#[test]
+fn item_listed_with_null_taker() {
+    let mut payload = item_listed_payload();
+    payload["is_private"] = json!(false);
+    payload["taker"] = Value::Null;
+    let event = item_listed(payload);
+
+    let listing = match event.payload {
+        Payload::ItemListed(listing) => listing,
+        other => panic!("expected item listed, got {:?}", other),
+    };
+    assert!(!listing.is_private);
+    assert!(listing.taker.is_none());
+}
+
+#[test]
+fn item_listed_private_with_taker() {
+    let mut payload = item_listed_payload();
+    payload["is_private"] = json!(true);
+    payload["taker"] = json!({ "address": "0x8e1a0d4a3f2a0aa3d3b6b2e2b6c1c4e5d8e3f9a1" });
+    let event = item_listed(payload);
+
+    let listing = match event.payload {
+        Payload::ItemListed(listing) => listing,
+        other => panic!("expected item listed, got {:?}", other),
+    };
+    assert!(listing.is_private);
+    assert_eq!(
+        listing.taker.map(|taker| taker.address),
+        Some(
+            "0x8e1a0d4a3f2a0aa3d3b6b2e2b6c1c4e5d8e3f9a1"
+                .parse()
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn trait_offer_criteria() {
+    let event: StreamEvent = serde_json::from_value(json!({
+        "event_type": "trait_offer",
+        "sent_at": "2022-07-19T18:42:03.235322+00:00",
+        "payload": {
+            "asset_contract_criteria": { "address": "0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4" },
+            "base_price": "10000000000000000",
+            "collection": { "slug": "wandernauts" },
+            "created_date": "2022-07-19T18:41:49.000000+00:00",
+            "event_timestamp": "2022-07-19T18:42:02.268945+00:00",
+            "expiration_date": "2022-07-20T18:41:49.000000+00:00",
+            "maker": { "address": "0x2f29f5d0d4388ee3e0b3d1b8b1e2db7bf1b2f0d2" },
+            "order_hash": "0x1a2b36a6d1ce3e34557d9b47f4c54b34efa4b6bd6da66a7f83a73a4d3f2a4c7e",
+            "payment_token": {
+                "address": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                "decimals": 18,
+                "eth_price": "1.000000000000000",
+                "name": "Wrapped Ether",
+                "symbol": "WETH",
+                "usd_price": "1530.000000000000000000",
+            },
+            "quantity": 1,
+            "taker": null,
+            "trait_criteria": { "trait_type": "Background", "trait_name": "Red" },
+        },
+    }))
+    .unwrap();
+
+    let offer = match event.payload {
+        Payload::TraitOffer(offer) => offer,
+        other => panic!("expected trait offer, got {:?}", other),
+    };
+    assert_eq!(offer.trait_criteria.trait_type, "Background");
+    assert_eq!(offer.trait_criteria.trait_value, "Red");
+}
+
+#[cfg(feature = "unknown-fields")]
+#[test]
+fn item_listed_keeps_unknown_fields() {
+    let mut payload = item_listed_payload();
+    payload["royalty_fee"] = json!("250");
+    let event = item_listed(payload);
+
+    let extra = event.payload.extra();
+    assert_eq!(extra.len(), 1, "unexpected fields {:?}", extra.keys());
+    assert_eq!(extra["royalty_fee"], json!("250"));
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn json_schema_defines_every_payload() {
+    let schema = serde_json::to_value(opensea_stream::schema::json_schema()).unwrap();
+
+    for name in [
+        "ItemListedData",
+        "ItemSoldData",
+        "ItemTransferredData",
+        "ItemMetadataUpdatedData",
+        "ItemCancelledData",
+        "ItemReceivedOfferData",
+        "ItemReceivedBidData",
+        "CollectionOfferData",
+        "TraitOfferData",
+    ] {
+        assert!(
+            schema["definitions"].get(name).is_some(),
+            "no definition for {}",
+            name
+        );
+    }
+}
+
+#[test]
+fn item_sold_parties() {
+    let event = fixture("item_sold.json");
+    let sale = match event.payload {
+        Payload::ItemSold(sale) => sale,
+        _ => panic!("expected item_sold"),
+    };
+
+    assert_eq!(sale.seller(), &sale.maker);
+    assert_eq!(sale.buyer(), &sale.taker);
+}
+
+#[test]
+fn item_transferred_accounts() {
+    let event = fixture("item_transferred.json");
+    let transaction = event.payload.transaction().unwrap().hash;
+    let transfer = match event.payload {
+        Payload::ItemTransferred(transfer) => transfer,
+        _ => panic!("expected item_transferred"),
+    };
+
+    assert_ne!(transfer.from_account, transfer.to_account);
+    assert_eq!(transfer.quantity, 1);
+    assert_eq!(transfer.transaction.hash, transaction);
+}
+
+#[test]
+fn summaries() {
+    let summary = |name: &str| fixture(name).payload.summary();
+
+    assert_eq!(
+        summary("item_listed.json"),
+        "wandernauts #1 listed for 0.05 ETH by 0x2f29…f0d2"
+    );
+    assert_eq!(
+        summary("item_metadata_updated.json"),
+        "wandernauts #3 metadata updated"
+    );
+}
+
+#[test]
+fn erc1155_quantity() {
+    let mut payload = item_listed_payload();
+    payload["quantity"] = "4".into();
+    payload["base_price"] = "200000000000000000".into();
+
+    let event = item_listed(payload);
+    let listing = match &event.payload {
+        Payload::ItemListed(listing) => listing,
+        _ => panic!("expected item_listed"),
+    };
+    assert_eq!(listing.quantity, 4);
+    assert_eq!(listing.unit_price(), 50000000000000000u64.into());
+    assert_eq!(
+        serde_json::to_value(&event).unwrap()["payload"]["quantity"],
+        4
+    );
+}
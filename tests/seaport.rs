@@ -0,0 +1,107 @@
+#![cfg(feature = "ethers")]
+
+mod common;
+
+use common::fixture_value;
+use opensea_stream::{
+    ethers_core::{
+        abi::{AbiDecode, AbiEncode},
+        types::{Address, H256, U256},
+    },
+    schema::{Payload, StreamEvent},
+    seaport::FulfillOrderCall,
+};
+use serde_json::json;
+
+fn listing() -> StreamEvent {
+    let mut value = fixture_value("item_listed.json");
+    value["payload"]["protocol_address"] = json!("0x00000000000000adc04c56bf30ac9d3c0aaf14dc");
+    value["payload"]["protocol_data"] = json!({
+        "parameters": {
+            "offerer": "0x2f29f5d0d4388ee3e0b3d1b8b1e2db7bf1b2f0d2",
+            "zone": "0x004c00500000ad104d7dbd00e3ae0a5c00560c00",
+            "offer": [{
+                "itemType": 2,
+                "token": "0x6e3ef0a0aaac6d9f4bc4ab1d5df7b9d4d5ed4fb4",
+                "identifierOrCriteria": "1",
+                "startAmount": "1",
+                "endAmount": "1"
+            }],
+            "consideration": [
+                {
+                    "itemType": 0,
+                    "token": "0x0000000000000000000000000000000000000000",
+                    "identifierOrCriteria": "0",
+                    "startAmount": "48750000000000000",
+                    "endAmount": "48750000000000000",
+                    "recipient": "0x2f29f5d0d4388ee3e0b3d1b8b1e2db7bf1b2f0d2"
+                },
+                {
+                    "itemType": 0,
+                    "token": "0x0000000000000000000000000000000000000000",
+                    "identifierOrCriteria": "0",
+                    "startAmount": "1250000000000000",
+                    "endAmount": "1250000000000000",
+                    "recipient": "0x0000a26b00c1f0df003000390027140000faa719"
+                }
+            ],
+            "orderType": 0,
+            "startTime": "1658256109",
+            "endTime": "1660934509",
+            "zoneHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+            "salt": "0x360c6ebe0000000000000000000000000000000000000000b2f9a0a4e3c5a1f3",
+            "conduitKey": "0x0000007b02230091a7ed01230072f7006a004d60a8d4e71d599b8104250f0000",
+            "totalOriginalConsiderationItems": 2,
+            "counter": 0
+        },
+        "signature": null
+    });
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn protocol_data_is_deserialized() {
+    let event = listing();
+    let protocol_data = event.payload.protocol_data().unwrap();
+    assert_eq!(
+        event.payload.protocol_address(),
+        Some(
+            "0x00000000000000adc04c56bf30ac9d3c0aaf14dc"
+                .parse()
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        protocol_data.parameters.offer[0].identifier_or_criteria,
+        U256::one()
+    );
+    assert_eq!(
+        protocol_data.parameters.native_amount(),
+        U256::exp10(16) * 5
+    );
+    assert!(protocol_data.order().signature.is_empty());
+}
+
+#[test]
+fn fulfill_order_calls_round_trip() {
+    assert_eq!(FulfillOrderCall::selector(), [0xb3, 0xa3, 0x4c, 0x4c]);
+
+    let event = listing();
+    let call = FulfillOrderCall {
+        order: event.payload.protocol_data().unwrap().order(),
+        fulfiller_conduit_key: H256::zero(),
+    };
+    let data = call.clone().encode();
+    assert_eq!(data[..4], FulfillOrderCall::selector());
+
+    let decoded = FulfillOrderCall::decode(&data).unwrap();
+    assert_eq!(decoded, call);
+
+    let Payload::ItemListed(listing) = &event.payload else {
+        unreachable!()
+    };
+    assert_eq!(
+        decoded.order.parameters.offerer,
+        Address::from(&listing.maker)
+    );
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::fixture;
+use opensea_stream::{
+    simulator::{ManualClock, Simulator},
+    Event,
+};
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn replay_follows_the_clock() {
+    let listed = fixture("item_listed.json");
+    let sold = fixture("item_sold.json");
+    let gap = (sold.sent_at - listed.sent_at).to_std().unwrap();
+
+    let clock = ManualClock::new();
+    let mut events = Simulator::new(vec![listed, sold])
+        .speed(2.0)
+        .clock(clock.clone())
+        .run();
+
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemListed);
+
+    clock.advance(gap / 2 - Duration::from_secs(1));
+    assert!(timeout(Duration::from_millis(50), events.recv())
+        .await
+        .is_err());
+
+    clock.advance(Duration::from_secs(1));
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemSold);
+    assert!(events.recv().await.is_none());
+}
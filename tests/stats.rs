@@ -0,0 +1,44 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::fixture;
+use opensea_stream::{schema::Payload, stats::Stats, Event};
+
+#[test]
+fn snapshot_counts_events_by_type_and_collection() {
+    let stats = Stats::new();
+    stats.record(&fixture("item_listed.json"));
+    stats.record(&fixture("item_listed.private.json"));
+    stats.record(&fixture("item_sold.json"));
+    stats.record_missed(4);
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.total_events, 3);
+    assert_eq!(snapshot.missed, 4);
+    assert_eq!(snapshot.reconnects, None);
+    assert_eq!(snapshot.events_per_second, 0.3);
+    assert_eq!(snapshot.by_event[&Event::ItemListed], 0.2);
+    assert_eq!(snapshot.by_event[&Event::ItemSold], 0.1);
+    assert_eq!(snapshot.by_collection["wandernauts"], 0.3);
+    assert!(snapshot.lag.is_some());
+}
+
+#[test]
+fn latency_is_corrected_for_a_local_clock_behind() {
+    let stats = Stats::new();
+    assert_eq!(stats.snapshot().latency, None);
+
+    // The local clock is five seconds behind: this event appears to be sent in the future.
+    let mut event = fixture("item_listed.json");
+    let now = Utc::now();
+    event.sent_at = now + Duration::seconds(5);
+    if let Payload::ItemListed(listing) = &mut event.payload {
+        listing.event_timestamp = now + Duration::seconds(4);
+    }
+    stats.record(&event);
+
+    let latency = stats.snapshot().latency.unwrap();
+    assert_eq!(latency.p50, latency.max);
+    assert!(latency.max >= std::time::Duration::from_secs(1));
+    assert!(latency.max < std::time::Duration::from_secs(2));
+}
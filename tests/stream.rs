@@ -0,0 +1,37 @@
+#![cfg(feature = "unstable-phyllo")]
+
+mod common;
+
+use common::{fixture, message};
+use opensea_stream::{Error, Event, EventStream, LagPolicy};
+use tokio::sync::broadcast;
+
+/// Sends three events into a channel with room for two, so that the first is dropped.
+fn lagged(lag_policy: LagPolicy) -> EventStream {
+    let (tx, rx) = broadcast::channel(2);
+    tx.send(message(fixture("item_listed.json"))).unwrap();
+    tx.send(message(fixture("item_sold.json"))).unwrap();
+    tx.send(message(fixture("item_cancelled.json"))).unwrap();
+    EventStream::new(rx).lag_policy(lag_policy)
+}
+
+#[tokio::test]
+async fn lag_is_surfaced_as_missed_events() {
+    let mut events = lagged(LagPolicy::Error);
+    assert!(matches!(
+        events.recv().await,
+        Some(Err(Error::MissedEvents(1)))
+    ));
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemSold);
+}
+
+#[tokio::test]
+async fn lag_is_skipped() {
+    let mut events = lagged(LagPolicy::Skip);
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemSold);
+    let event = events.recv().await.unwrap().unwrap();
+    assert_eq!(event.payload.event(), Event::ItemCancelled);
+    assert!(events.recv().await.is_none());
+}
@@ -0,0 +1,32 @@
+use opensea_stream::{CloseReason, Collection, Error, SubscriptionSnapshot};
+
+#[test]
+fn snapshot_is_serialized_as_topics() {
+    let snapshot = SubscriptionSnapshot {
+        collections: vec![
+            Collection::All,
+            Collection::Collection("wandernauts".to_string()),
+        ],
+    };
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(
+        json,
+        r#"{"collections":["collection:*","collection:wandernauts"]}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<SubscriptionSnapshot>(&json).unwrap(),
+        snapshot
+    );
+}
+
+#[test]
+fn close_reasons_are_displayed() {
+    let error = Error::Closed {
+        reason: CloseReason::Server,
+    };
+    assert_eq!(error.to_string(), "channel closed: closed by the server");
+    let error = Error::Closed {
+        reason: CloseReason::Socket,
+    };
+    assert_eq!(error.to_string(), "channel closed: socket closed");
+}